@@ -1,18 +1,51 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::{mpsc, Arc}};
 use egui_phosphor::bold as ph;
 use crate::{
+    code_actions::CodeActions,
+    commands::{Commands, CoreCommand},
     config::Config, 
+    diagnostics::Diagnostics,
+    document::{char_to_position, word_at, Document, Documents},
+    events::{self, Event, EventBus},
     fonts, 
+    format::FormatJob,
+    hints::Hints,
     i18n::I18n, 
-    plugin::Plugin,
+    languages::Languages,
+    lsp::{self, LspClient, LspEvent},
+    notifications::Notifications,
+    plugin::{ui::ViewSlot, Plugins},
+    project::Project,
+    repl::Repl,
+    session::Session,
+    snippets::Snippets,
+    symbol_index::SymbolIndex,
+    tasks::Tasks,
+    testing::TestExplorer,
+    watch::Watch,
     widgets::{
+        cabal_form::{CabalFormState, ModuleOffer, ModuleOfferDialog},
+        code_actions::CodeActionsMenu,
+        command_palette::{CommandPaletteDialog, CommandPaletteState},
         editor::CodeEditor, 
+        notifications::NotificationsArea,
+        open_dialog::{OpenDialog, OpenState},
+        output::OutputView,
+        problems::ProblemsView,
         pages::{
-            plugins::PluginsPage, 
+            outline::{OutlinePage, OutlineState},
+            plugins::{PluginsPage, PluginsState},
             project::ProjectPage, 
             search::SearchPage, 
-            settings::SettingsPage
+            settings::SettingsPage,
+            tests::TestsPage,
         }, 
+        permission_prompt::PermissionPrompt,
+        plugin_view::PluginViewUi,
+        rename::{RenameDialog, RenameState},
+        repl::ReplView,
+        symbol_search::{SymbolSearchDialog, SymbolSearchState},
+        terminal::{TerminalTab, TerminalTabs, TerminalView},
         toolbar::{ToolbarButton, ToolbarHeading}, 
         window_frame::WindowFrame,
         menubar::MenuBar,
@@ -36,9 +69,24 @@ sumList = foldl1 (+) [1, 2, 3, 4, 5] -- sumList will be 15
 pub enum Page {
     #[default]
     Project,
+    Outline,
     Search,
+    Tests,
     Plugins,
     Settings,
+    /// A page contributed by a plugin, by its view key.
+    Plugin(u64),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BottomView {
+    #[default]
+    Terminal,
+    Repl,
+    Output,
+    Problems,
+    /// A tab contributed by a plugin, by its view key.
+    Plugin(u64),
 }
 
 #[derive(Default)]
@@ -46,11 +94,40 @@ pub struct Fexc {
     pub config: Config,
     pub i18n: I18n,
     pub current_page: Page,
-    pub open_files: Vec<PathBuf>,
+    pub documents: Documents,
     pub current_project: Option<PathBuf>,
-    pub _plugins: Vec<Box<dyn Plugin>>,
-
-    pub code: String,
+    pub project: Option<Project>,
+    pub lsp: Option<LspClient>,
+    pub lsp_root: Option<PathBuf>,
+    /// Language servers of plugin languages by language name, `None` for those that failed to start.
+    pub language_servers: HashMap<String, Option<LspClient>>,
+    pub languages: Languages,
+    pub rename: Option<RenameState>,
+    pub open_dialog: Option<OpenState>,
+    pub cabal_form: CabalFormState,
+    pub module_offer: Option<ModuleOffer>,
+    pub outline: OutlineState,
+    pub hints: Hints,
+    pub code_actions: CodeActions,
+    pub diagnostics: Diagnostics,
+    pub symbol_index: SymbolIndex,
+    pub snippets: Snippets,
+    pub symbol_search: Option<SymbolSearchState>,
+    pub commands: Commands,
+    pub command_palette: Option<CommandPaletteState>,
+    pub format_jobs: Vec<FormatJob>,
+    pub bottom_view: BottomView,
+    pub terminals: TerminalTabs,
+    pub repl: Repl,
+    pub tasks: Tasks,
+    pub tests: TestExplorer,
+    pub watch: Watch,
+    pub notifications: Notifications,
+    pub events: EventBus,
+    /// The core subsystems' subscription to [`Fexc::events`].
+    pub core_events: Option<mpsc::Receiver<Event>>,
+    pub plugins: Plugins,
+    pub plugins_page: PluginsState,
 }
 
 impl Fexc {
@@ -63,11 +140,41 @@ impl Fexc {
             Config::default()
         });
 
-        Fexc {
+        let session = Session::load().unwrap_or_else(|e| {
+            log::error!("{e}");
+            Session::default()
+        });
+
+        let mut app = Fexc {
+            events: EventBus::new(config.theme),
             config,
-            code: HASKELL_DEMO.to_owned(),
+            documents: Documents::new(Document::untitled(HASKELL_DEMO)),
+            terminals: TerminalTabs::restore(&session.terminals, session.split_terminals),
             ..Default::default()
+        };
+        app.commands.set_recent(session.recent_commands);
+        app.core_events = Some(app.events.subscribe(events::core_filter()));
+
+        if app.terminals.tabs.is_empty() {
+            let name = format!("{} 1", app.i18n("terminal"));
+            app.terminals.add(name, 0, None);
         }
+
+        app.load_plugins();
+
+        app
+    }
+
+    fn save_session(&self) {
+        let session = Session {
+            split_terminals: self.terminals.split,
+            terminals: self.terminals.tabs.iter().map(TerminalTab::to_session).collect(),
+            recent_commands: self.commands.recent().to_vec(),
+        };
+
+        session.save().unwrap_or_else(|e| {
+            log::error!("{e}");
+        });
     }
 
     pub fn i18n(&self, key: &str) -> &str {
@@ -80,6 +187,105 @@ impl Fexc {
             .unwrap_or_else(|| self.i18n("empty_project").to_owned())
     }
 
+    /// Starts a language server for the current project, restarting it when the project changes.
+    pub fn update_lsp(&mut self, ctx: &egui::Context) {
+        if self.lsp_root != self.current_project {
+            self.lsp_root = self.current_project.clone();
+            self.lsp = self.current_project.as_ref().and_then(|root| {
+                LspClient::start(&self.config.lsp_command, root, lsp::LANGUAGE_ID, ctx.clone())
+                    .inspect_err(|e| self.notifications.error(e.to_string()))
+                    .ok()
+            });
+            self.language_servers.clear();
+        }
+
+        self.update_language_servers(ctx);
+
+        let Some(lsp) = &mut self.lsp else { return };
+
+        // Files of other languages go to their own servers
+        let languages = &self.languages;
+        let docs = self.documents.iter().filter(|d| languages.is_haskell(d.path.as_deref()));
+        lsp.sync_documents(docs).unwrap_or_else(|e| {
+            log::error!("{e}");
+        });
+
+        for event in lsp.poll() {
+            self.handle_lsp_event(event);
+        }
+    }
+
+    /// Handles a message from the Haskell server or the server of a plugin language.
+    pub fn handle_lsp_event(&mut self, event: LspEvent) {
+        match event {
+            LspEvent::Rename(edit) => {
+                if !matches!(self.rename, Some(RenameState::Pending)) {
+                    return;
+                }
+
+                self.rename = match lsp::workspace_edit_to_file_edits(&self.documents, edit) {
+                    Ok(edits) => Some(RenameState::Preview(edits)),
+                    Err(e) => {
+                        self.notifications.error(e.to_string());
+                        None
+                    }
+                };
+            }
+            LspEvent::Formatting { doc, result } => {
+                self.finish_lsp_format(doc, result);
+            }
+            LspEvent::InlayHints { doc, version, hints } => {
+                self.hints.set_inlay(doc, version, hints);
+            }
+            LspEvent::CodeLenses { doc, version, lenses } => {
+                self.hints.set_lenses(doc, version, lenses);
+            }
+            LspEvent::ResolvedCodeLens { doc, version, index, lens } => {
+                self.hints.set_resolved_lens(doc, version, index, lens);
+            }
+            LspEvent::CodeActions { doc, version, line, actions } => {
+                self.code_actions.set_lsp((doc, version, line), actions);
+            }
+            LspEvent::ResolvedCodeAction(action) => {
+                if action.edit.is_some() || action.command.is_some() {
+                    self.apply_code_action(*action);
+                }
+            }
+            LspEvent::Diagnostics { path, diagnostics } => {
+                self.diagnostics.set_lsp(path.clone(), diagnostics);
+                self.events.publish(Event::DiagnosticsUpdated { path });
+            }
+            LspEvent::ApplyEdit(edit) => {
+                let result = lsp::workspace_edit_to_file_edits(&self.documents, edit)
+                    .and_then(|edits| self.documents.apply(edits));
+
+                if let Err(e) = result {
+                    self.notifications.error(e.to_string());
+                }
+            }
+            LspEvent::Error(e) => {
+                self.notifications.error(format!("Language server error: {e}"));
+
+                if matches!(self.rename, Some(RenameState::Pending)) {
+                    self.rename = None;
+                }
+            }
+        }
+    }
+
+    /// Asks for a new name for the symbol under the cursor in the active document.
+    pub fn start_rename(&mut self) {
+        let doc = self.documents.active();
+
+        if let Some(path) = &doc.path {
+            self.rename = Some(RenameState::Prompt {
+                path: path.clone(),
+                position: char_to_position(&doc.text, doc.cursor),
+                new_name: word_at(&doc.text, doc.cursor).unwrap_or_default(),
+            });
+        }
+    }
+
     pub fn set_editor_font_size(&mut self, ctx: &egui::Context) {
         ctx.options_mut(|opts| {
             opts.light_style = Arc::new({
@@ -103,25 +309,121 @@ impl eframe::App for Fexc {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         ctx.set_theme(self.config.theme);
         self.set_editor_font_size(ctx);
+        self.update_project();
+        self.update_events();
+        self.handle_core_events();
+        self.update_plugins(ctx);
+        self.update_hot_reload(ctx);
+        self.update_lsp(ctx);
+        self.update_hints(ctx);
+        self.update_code_actions(ctx);
+        self.poll_format_jobs();
+        self.repl.poll();
+        self.poll_tasks();
+        self.update_tests();
+        self.update_watch(ctx);
+        self.symbol_index.update(self.current_project.as_deref(), ctx);
+        self.handle_command_shortcuts(ctx);
 
         WindowFrame::new(&self.window_name()).show(ctx, |ui| { 
+            let file_label = self.i18n("file").to_owned();
+            let edit_label = self.i18n("edit").to_owned();
+            let view_label = self.i18n("view").to_owned();
+            let repl_label = self.i18n("repl").to_owned();
+            let build_label = self.i18n("build").to_owned();
+            let plugins_label = self.i18n("plugins").to_owned();
+            let titles = CoreCommand::ALL.map(|command| (command, self.i18n(command.i18n_keys().1).to_owned()));
+
+            let (mut run, mut run_plugin) = (None, None);
+            let mut item = |ui: &mut egui::Ui, command: CoreCommand, enabled: bool| {
+                let title = titles.iter()
+                    .find(|(c, _)| *c == command)
+                    .map(|(_, title)| title.as_str())
+                    .unwrap_or_default();
+                let button = egui::Button::new(title).shortcut_text(command.shortcut_text(ui.ctx()));
+
+                if ui.add_enabled(enabled, button).clicked() {
+                    run = Some(command.id().to_owned());
+                }
+            };
+
             MenuBar::new()
                 .show(ui, |ui| 
             {
-                ui.menu_button(self.i18n("file"), |ui| {
-                    if ui.button(self.i18n("quit")).clicked() {
-                        ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+                ui.menu_button(&file_label, |ui| {
+                    item(ui, CoreCommand::Open, true);
+                    item(ui, CoreCommand::Save, true);
+                    item(ui, CoreCommand::Quit, true);
+                });
+
+                ui.menu_button(&edit_label, |ui| {
+                    item(ui, CoreCommand::FormatDocument, true);
+                    item(ui, CoreCommand::CodeActions, true);
+                    item(ui, CoreCommand::SymbolSearch, true);
+                    item(ui, CoreCommand::ToggleComment, true);
+                    item(ui, CoreCommand::Rename, true);
+                });
+
+                ui.menu_button(&view_label, |ui| {
+                    item(ui, CoreCommand::CommandPalette, true);
+
+                    ui.separator();
+
+                    for command in CoreCommand::ALL {
+                        if command.i18n_keys().0 == "view" && command != CoreCommand::CommandPalette {
+                            item(ui, command, true);
+                        }
                     }
                 });
+
+                ui.menu_button(&repl_label, |ui| {
+                    item(ui, CoreCommand::ReplLoad, true);
+                    item(ui, CoreCommand::ReplReload, true);
+                    item(ui, CoreCommand::ReplEval, true);
+                });
+
+                if self.commands.plugin_commands().next().is_some() {
+                    ui.menu_button(&plugins_label, |ui| {
+                        for command in self.commands.plugin_commands() {
+                            if ui.button(self.command_title(&command.title)).clicked() {
+                                run_plugin = Some(command.id.clone());
+                            }
+                        }
+                    });
+                }
+
+                ui.menu_button(&build_label, |ui| {
+                    for i in 0..self.config.tasks.len() {
+                        let task = &self.config.tasks[i];
+                        if self.project.as_ref().is_some_and(|p| !p.supports(task)) {
+                            continue;
+                        }
+
+                        if ui.button(&task.name).clicked() {
+                            self.run_task(i, ui.ctx());
+                        }
+                    }
+
+                    ui.separator();
+
+                    item(ui, CoreCommand::RunLastTask, true);
+                    item(ui, CoreCommand::StopTask, self.tasks.running.is_some());
+                });
             });
 
+            if let Some(id) = run.or(run_plugin) {
+                self.run_command(&id, ui.ctx());
+            }
+
             egui::SidePanel::left("sidebar")
                 .min_width(256.0)
                 .resizable(true)
                 .show_inside(ui, |sidebar| 
             {
                 let project_label = self.i18n("project").to_owned();
+                let outline_label = self.i18n("outline").to_owned();
                 let search_label = self.i18n("search").to_owned();
+                let tests_label = self.i18n("tests").to_owned();
                 let plugins_label = self.i18n("plugins").to_owned();
                 let settings_label = self.i18n("settings").to_owned();
 
@@ -131,39 +433,121 @@ impl eframe::App for Fexc {
                     toolbar.separator();
 
                     toolbar.add(ToolbarButton::new(ph::FOLDER_OPEN, &project_label, &mut self.current_page, Page::Project));
+                    toolbar.add(ToolbarButton::new(ph::LIST_BULLETS, &outline_label, &mut self.current_page, Page::Outline));
                     toolbar.add(ToolbarButton::new(ph::MAGNIFYING_GLASS, &search_label, &mut self.current_page, Page::Search));
+                    toolbar.add(ToolbarButton::new(ph::FLASK, &tests_label, &mut self.current_page, Page::Tests));
                     toolbar.add(ToolbarButton::new(ph::PUZZLE_PIECE, &plugins_label, &mut self.current_page, Page::Plugins));
                     toolbar.add(ToolbarButton::new(ph::GEAR, &settings_label, &mut self.current_page, Page::Settings));
+
+                    for view in self.plugins.views.in_slot(ViewSlot::Sidebar) {
+                        toolbar.add(ToolbarButton::new(view.icon.as_str(), &view.title, &mut self.current_page, Page::Plugin(view.key)));
+                    }
                 });
 
                 sidebar.separator();
 
+                // The page goes away with the plugin that contributed it
+                if let Page::Plugin(key) = self.current_page
+                    && self.plugins.views.get(key).is_none()
+                {
+                    self.current_page = Page::default();
+                }
+
                 match self.current_page {
                     Page::Project => {
                         sidebar.add(ProjectPage::new(self));
                     }
+                    Page::Outline => {
+                        sidebar.add(OutlinePage::new(self));
+                    }
                     Page::Search => {
                         sidebar.add(SearchPage::new(self));
                     }
+                    Page::Tests => {
+                        sidebar.add(TestsPage::new(self));
+                    }
                     Page::Plugins => {
                         sidebar.add(PluginsPage::new(self));
                     }
                     Page::Settings => {
                         sidebar.add(SettingsPage::new(self));
                     }
+                    Page::Plugin(key) => {
+                        sidebar.add(PluginViewUi::new(self, key).heading());
+                    }
                 };
             });
 
+            if self.plugins.views.in_slot(ViewSlot::StatusBar).next().is_some() {
+                egui::TopBottomPanel::bottom("status_bar").show_inside(ui, |ui| {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        let items = self.plugins.views.in_slot(ViewSlot::StatusBar)
+                            .map(|v| (v.key, v.icon.clone(), v.title.clone()))
+                            .collect::<Vec<_>>();
+
+                        for (key, icon, title) in items {
+                            ui.horizontal(|ui| {
+                                ui.add(PluginViewUi::new(self, key));
+                                if !icon.is_empty() {
+                                    ui.label(icon);
+                                }
+                            })
+                            .response
+                            .on_hover_text(title);
+                        }
+                    });
+                });
+            }
+
             egui::TopBottomPanel::bottom("terminal")
                 .min_height(128.0)
                 .resizable(true)
                 .show_inside(ui, |bottom_ui| 
             {
-                bottom_ui.label("<terminal>");
+                let terminal_label = format!("{} {}", ph::TERMINAL_WINDOW, self.i18n("terminal"));
+                let repl_label = format!("{} {}", ph::CODE, self.i18n("repl"));
+                let output_label = format!("{} {}", ph::HAMMER, self.i18n("output"));
+                let problems_label = format!("{} {} ({})", ph::WARNING_CIRCLE, self.i18n("problems"), self.diagnostics.count());
+
+                bottom_ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.bottom_view, BottomView::Terminal, terminal_label);
+                    ui.selectable_value(&mut self.bottom_view, BottomView::Repl, repl_label);
+                    ui.selectable_value(&mut self.bottom_view, BottomView::Output, output_label);
+                    ui.selectable_value(&mut self.bottom_view, BottomView::Problems, problems_label);
+
+                    for view in self.plugins.views.in_slot(ViewSlot::BottomPanel) {
+                        ui.selectable_value(&mut self.bottom_view, BottomView::Plugin(view.key), format!("{} {}", view.icon, view.title));
+                    }
+                });
+
+                bottom_ui.separator();
+
+                if let BottomView::Plugin(key) = self.bottom_view
+                    && self.plugins.views.get(key).is_none()
+                {
+                    self.bottom_view = BottomView::default();
+                }
+
+                match self.bottom_view {
+                    BottomView::Terminal => bottom_ui.add(TerminalView::new(self)),
+                    BottomView::Repl => bottom_ui.add(ReplView::new(self)),
+                    BottomView::Output => bottom_ui.add(OutputView::new(self)),
+                    BottomView::Problems => bottom_ui.add(ProblemsView::new(self)),
+                    BottomView::Plugin(key) => bottom_ui.add(PluginViewUi::new(self, key)),
+                };
             });
 
             ui.add(CodeEditor::new(self));
         });
+
+        OpenDialog::new(self).show(ctx);
+        RenameDialog::new(self).show(ctx);
+        ModuleOfferDialog::new(self).show(ctx);
+        PermissionPrompt::new(self).show(ctx);
+        SymbolSearchDialog::new(self).show(ctx);
+        CommandPaletteDialog::new(self).show(ctx);
+        CodeActionsMenu::new(self).show(ctx);
+        NotificationsArea::new(&mut self.notifications).show(ctx);
     }
 
     fn save(&mut self, _: &mut dyn eframe::Storage) {
@@ -171,6 +555,11 @@ impl eframe::App for Fexc {
             log::error!("{e}");
         });
     }
+
+    fn on_exit(&mut self, _: Option<&eframe::glow::Context>) {
+        self.unload_plugins();
+        self.save_session();
+    }
 }
 
 fn set_fonts(cc: &eframe::CreationContext<'_>) {
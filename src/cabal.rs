@@ -0,0 +1,713 @@
+use std::{ops::Range, path::Path};
+
+use crate::{
+    app::Fexc,
+    document::FileEdit,
+    project::ComponentKind,
+    symbol_index::is_haskell_file,
+    widgets::cabal_form::{ModuleOffer, OfferTarget},
+};
+
+/// An element of a list field such as one dependency of `build-depends`, located so it can be edited in place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub text: String,
+    pub line: usize,
+    /// Byte range of the item in its line.
+    pub range: Range<usize>,
+}
+
+/// A `key: value` field, possibly continued on more indented lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// Lowercase field name.
+    pub name: String,
+    pub line: usize,
+    /// One past the last line of the value.
+    pub end_line: usize,
+    pub indent: usize,
+    colon: usize,
+    /// The value as `(line, byte offset, text)` parts, without comment lines.
+    segments: Vec<(usize, usize, String)>,
+}
+
+impl Field {
+    /// The value with its lines trimmed, joined by line breaks.
+    pub fn value(&self) -> String {
+        self.segments.iter()
+            .map(|(_, _, text)| text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The elements of a list value, split on commas for dependencies and on commas or whitespace otherwise.
+    pub fn items(&self) -> Vec<Item> {
+        let comma_only = is_comma_list(&self.name);
+        let is_separator = |c: char| c == ',' || !comma_only && c.is_whitespace();
+        let mut items = vec![];
+
+        for (line, offset, text) in &self.segments {
+            let mut start = 0;
+
+            for (i, c) in text.char_indices().chain([(text.len(), ',')]) {
+                if !is_separator(c) {
+                    continue;
+                }
+
+                let piece = &text[start..i];
+                let trimmed = piece.trim();
+                if !trimmed.is_empty() {
+                    let begin = offset + start + (piece.len() - piece.trim_start().len());
+                    items.push(Item {
+                        text: trimmed.to_owned(),
+                        line: *line,
+                        range: begin..begin + trimmed.len(),
+                    });
+                }
+
+                start = i + c.len_utf8();
+            }
+        }
+
+        items
+    }
+
+    fn uses_commas(&self) -> bool {
+        is_comma_list(&self.name) || self.segments.iter().any(|(_, _, text)| text.contains(','))
+    }
+}
+
+/// A stanza such as `library` or `test-suite spec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Lowercase stanza keyword.
+    pub kind: String,
+    pub name: String,
+    pub line: usize,
+    /// The fields of the stanza, including those inside conditionals.
+    pub fields: Vec<Field>,
+}
+
+impl Section {
+    pub fn component_kind(&self) -> Option<ComponentKind> {
+        ComponentKind::from_cabal_stanza(&self.kind)
+    }
+}
+
+/// A parsed `.cabal` file that keeps its lines, so edits only touch the text of the fields they change
+/// and the rest of the formatting and comments survive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CabalFile {
+    lines: Vec<String>,
+    line_break: &'static str,
+    trailing_line_break: bool,
+    /// Package description fields outside of stanzas.
+    pub fields: Vec<Field>,
+    pub sections: Vec<Section>,
+}
+
+impl CabalFile {
+    pub fn parse(text: &str) -> CabalFile {
+        let lines = text.lines().map(str::to_owned).collect::<Vec<_>>();
+        let mut fields = Vec::<Field>::new();
+        let mut sections = Vec::<Section>::new();
+        // Whether the last field can still be continued by more indented lines
+        let mut open = false;
+        let mut in_section = false;
+
+        for (i, line) in lines.iter().enumerate() {
+            let content = line.trim_start();
+            if content.is_empty() || content.starts_with("--") {
+                continue;
+            }
+
+            let indent = line.len() - content.len();
+            let current = match sections.last_mut() {
+                Some(section) if in_section && indent > 0 => &mut section.fields,
+                _ => &mut fields,
+            };
+
+            if open
+                && let Some(field) = current.last_mut()
+                && indent > field.indent
+            {
+                field.segments.push((i, indent, content.trim_end().to_owned()));
+                field.end_line = i + 1;
+                continue;
+            }
+
+            if let Some(colon) = field_colon(content) {
+                let colon = indent + colon;
+                let value_start = colon + 1 + (line[colon + 1..].len() - line[colon + 1..].trim_start().len());
+                let value = line[value_start..].trim_end();
+
+                current.push(Field {
+                    name: content[..colon - indent].to_ascii_lowercase(),
+                    line: i,
+                    end_line: i + 1,
+                    indent,
+                    colon,
+                    segments: if value.is_empty() { vec![] } else { vec![(i, value_start, value.to_owned())] },
+                });
+                open = true;
+
+                // A field at the start of a line ends the stanza before it
+                if indent == 0 {
+                    in_section = false;
+                }
+            } else if indent == 0 {
+                let (kind, name) = content.split_once(char::is_whitespace).unwrap_or((content, ""));
+                sections.push(Section {
+                    kind: kind.to_ascii_lowercase(),
+                    name: name.trim().to_owned(),
+                    line: i,
+                    fields: vec![],
+                });
+                open = false;
+                in_section = true;
+            } else {
+                // `if flag(...)` and `else` lines; the fields inside them belong to the stanza as well
+                open = false;
+            }
+        }
+
+        CabalFile {
+            line_break: if text.contains("\r\n") { "\r\n" } else { "\n" },
+            trailing_line_break: text.ends_with('\n'),
+            lines,
+            fields,
+            sections,
+        }
+    }
+
+    /// The fields of a stanza, or the package description fields for `None`.
+    pub fn fields_of(&self, section: Option<usize>) -> &[Field] {
+        match section {
+            Some(i) => self.sections.get(i).map_or(&[], |s| &s.fields),
+            None => &self.fields,
+        }
+    }
+
+    pub fn field(&self, section: Option<usize>, name: &str) -> Option<&Field> {
+        self.fields_of(section).iter().find(|f| f.name == name)
+    }
+
+    /// Sets a field to `value`, adding it if it is missing and removing it if `value` is empty.
+    pub fn set_value(&self, section: Option<usize>, name: &str, value: &str) -> String {
+        let mut lines = self.lines.clone();
+        let value = value.trim_end();
+
+        match self.field(section, name) {
+            Some(field) if value.trim().is_empty() => {
+                lines.drain(field.line..field.end_line);
+            }
+            Some(field) if !value.contains('\n') && field.end_line == field.line + 1 => {
+                let value_start = field.segments.first().map_or(field.colon + 2, |(_, start, _)| *start);
+                let line = &mut lines[field.line];
+                line.truncate(field.colon + 1);
+                line.push_str(&" ".repeat(value_start - field.colon - 1));
+                line.push_str(value.trim_start());
+            }
+            Some(field) => {
+                // Multi-line values are written below the key, at the indentation of the old continuation lines
+                let indent = field.segments.iter()
+                    .find(|(line, _, _)| *line != field.line)
+                    .map_or(field.indent + 2, |(_, offset, _)| *offset);
+
+                lines[field.line].truncate(field.colon + 1);
+                lines.splice(field.line + 1..field.end_line, continuation_lines(value, indent));
+            }
+            None if value.trim().is_empty() => {}
+            None => {
+                let at = self.insert_line(section);
+                let indent = self.field_indent(section);
+                let new_lines = if value.contains('\n') {
+                    let mut new_lines = vec![format!("{}{name}:", " ".repeat(indent))];
+                    new_lines.extend(continuation_lines(value, indent + 2));
+                    new_lines
+                } else {
+                    vec![format!("{}{name}: {}", " ".repeat(indent), value.trim_start())]
+                };
+                lines.splice(at..at, new_lines);
+            }
+        }
+
+        self.render(lines)
+    }
+
+    /// Replaces the text of a list element.
+    pub fn replace_item(&self, item: &Item, text: &str) -> String {
+        let mut lines = self.lines.clone();
+        lines[item.line].replace_range(item.range.clone(), text.trim());
+        self.render(lines)
+    }
+
+    /// Appends an element to a list field in the layout the list already uses, adding the field if it is missing.
+    pub fn add_item(&self, section: Option<usize>, name: &str, text: &str) -> String {
+        let text = text.trim();
+        let Some(field) = self.field(section, name) else { return self.set_value(section, name, text) };
+        let items = field.items();
+        let Some(last) = items.last() else { return self.set_value(section, name, text) };
+
+        let mut lines = self.lines.clone();
+        let comma = field.uses_commas();
+        let before = lines[last.line][..last.range.start].to_owned();
+        let after = lines[last.line][last.range.end..].trim_start().to_owned();
+        let own_line = last.line != field.line && before.trim().trim_start_matches(',').trim().is_empty();
+
+        if own_line {
+            // One element per line: copy the indentation and leading comma of the last one
+            if comma && before.trim().is_empty() && !after.starts_with(',') {
+                lines[last.line].insert(last.range.end, ',');
+            }
+            lines.insert(last.line + 1, format!("{before}{text}"));
+        } else {
+            let separator = if comma { ", " } else { " " };
+            lines[last.line].insert_str(last.range.end, &format!("{separator}{text}"));
+        }
+
+        self.render(lines)
+    }
+
+    /// Removes an element of a list field along with its separator, or the whole field with its last element.
+    pub fn remove_item(&self, section: Option<usize>, name: &str, index: usize) -> String {
+        let Some(field) = self.field(section, name) else { return self.render(self.lines.clone()) };
+        let items = field.items();
+        let Some(item) = items.get(index) else { return self.render(self.lines.clone()) };
+
+        if items.len() == 1 {
+            return self.set_value(section, name, "");
+        }
+
+        let mut lines = self.lines.clone();
+
+        // Trailing comma layout: the previous element loses its comma when the last one goes
+        if index + 1 == items.len()
+            && let Some(previous) = items.get(index - 1)
+            && previous.line != item.line
+            && lines[previous.line][previous.range.end..].trim() == ","
+        {
+            lines[previous.line].truncate(previous.range.end);
+        }
+
+        // Leading comma layout: the next element loses its comma when the first one goes, unless the first has one too
+        if index == 0
+            && !lines[item.line][..item.range.start].trim().ends_with(',')
+            && let Some(next) = items.get(1)
+            && next.line != item.line
+            && lines[next.line][..next.range.start].trim() == ","
+        {
+            lines[next.line].replace_range(..next.range.start, &" ".repeat(next.range.start));
+        }
+
+        let line = &lines[item.line];
+        let (mut start, mut end) = (item.range.start, item.range.end);
+        let rest = &line[end..];
+        let before = line[..start].trim_end();
+
+        if rest.trim_start().starts_with(',') {
+            end += rest.len() - rest.trim_start().len() + 1;
+            end += line[end..].len() - line[end..].trim_start().len();
+        } else if before.ends_with(',') {
+            start = before.len() - 1;
+        } else if rest.starts_with(char::is_whitespace) && !rest.trim().is_empty() {
+            end += rest.len() - rest.trim_start().len();
+        } else if line[..start].ends_with(char::is_whitespace) && !before.ends_with(':') && !before.is_empty() {
+            start = before.len();
+        }
+
+        lines[item.line].replace_range(start..end, "");
+
+        if item.line == field.line {
+            let len = lines[item.line].trim_end().len();
+            lines[item.line].truncate(len);
+        } else if lines[item.line].trim().is_empty() {
+            lines.remove(item.line);
+        }
+
+        self.render(lines)
+    }
+
+    /// The indentation new fields of a stanza get, taken from its existing fields.
+    fn field_indent(&self, section: Option<usize>) -> usize {
+        match section {
+            Some(_) => self.fields_of(section).iter().map(|f| f.indent).min().unwrap_or(2),
+            None => 0,
+        }
+    }
+
+    /// Where a new field of a stanza goes: after its last field, or below its header.
+    fn insert_line(&self, section: Option<usize>) -> usize {
+        match self.fields_of(section).iter().map(|f| f.end_line).max() {
+            Some(line) => line,
+            None => section.and_then(|i| self.sections.get(i)).map_or(0, |s| s.line + 1),
+        }
+    }
+
+    fn render(&self, lines: Vec<String>) -> String {
+        let mut text = lines.join(self.line_break);
+        if self.trailing_line_break {
+            text.push_str(self.line_break);
+        }
+        text
+    }
+}
+
+/// Splits a dependency like `text >= 2 && < 2.2` into its package name and version bounds.
+pub fn split_dependency(dependency: &str) -> (&str, &str) {
+    let end = dependency.find(|c: char| c.is_whitespace() || "<>=^&|(".contains(c)).unwrap_or(dependency.len());
+    (&dependency[..end], dependency[end..].trim())
+}
+
+/// Fields whose elements may contain spaces, so only commas separate them.
+fn is_comma_list(name: &str) -> bool {
+    matches!(name, "build-depends" | "build-tool-depends" | "mixins" | "reexported-modules")
+}
+
+/// The position of the colon if the line starts with a field name.
+fn field_colon(content: &str) -> Option<usize> {
+    let colon = content.find(':')?;
+    let key = &content[..colon];
+
+    (!key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')).then_some(colon)
+}
+
+/// Value lines below a field key; empty lines become `.` as in package descriptions.
+fn continuation_lines(value: &str, indent: usize) -> Vec<String> {
+    value.lines()
+        .map(|line| match line.trim_end() {
+            "" => format!("{}.", " ".repeat(indent)),
+            line => format!("{}{line}", " ".repeat(indent)),
+        })
+        .collect()
+}
+
+/// The module a source file defines if it lies in `source_dir`, e.g. `Data.Foo` for `src/Data/Foo.hs`.
+fn module_name(path: &Path, source_dir: &Path) -> Option<String> {
+    let relative = path.strip_prefix(source_dir).ok()?.with_extension("");
+    let parts = relative.components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+
+    let is_module_part = |part: &&str| part.starts_with(|c: char| c.is_uppercase())
+        && part.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '\'');
+
+    parts.iter().all(is_module_part).then(|| parts.join("."))
+}
+
+impl Fexc {
+    /// Offers to add a saved module to its component when no stanza of the package's `.cabal` file lists it yet.
+    pub fn offer_new_module(&mut self, path: &Path) {
+        if self.module_offer.is_some() || !is_haskell_file(path) {
+            return;
+        }
+
+        let Some(project) = &self.project else { return };
+        let manifests = project.packages.iter()
+            .filter(|p| p.manifest.extension().is_some_and(|e| e == "cabal") && path.starts_with(&p.dir))
+            .map(|p| (p.dir.clone(), p.manifest.clone()))
+            .collect::<Vec<_>>();
+
+        for (dir, manifest) in manifests {
+            let Ok(text) = self.documents.contents(&manifest) else { continue };
+            let file = CabalFile::parse(&text);
+            let mut module = None;
+            let mut targets = vec![];
+
+            for (index, section) in file.sections.iter().enumerate() {
+                let Some(kind) = section.component_kind() else { continue };
+                let field_items = |name: &str| section.fields.iter()
+                    .filter(|f| f.name == name)
+                    .flat_map(|f| f.items())
+                    .map(|item| item.text)
+                    .collect::<Vec<_>>();
+
+                let mut source_dirs = field_items("hs-source-dirs");
+                if source_dirs.is_empty() {
+                    source_dirs.push(".".to_owned());
+                }
+
+                let main_is = field_items("main-is");
+                let Some((source_dir, name)) = source_dirs.iter()
+                    .find_map(|d| module_name(path, &dir.join(d)).map(|name| (d, name)))
+                else {
+                    continue;
+                };
+
+                let is_main = main_is.iter().any(|m| dir.join(source_dir).join(m) == path);
+                let listed = ["exposed-modules", "other-modules"].iter().any(|f| field_items(f).contains(&name));
+
+                if is_main || listed || self.cabal_form.ignored_modules.contains(&(manifest.clone(), name.clone())) {
+                    return;
+                }
+
+                targets.push(OfferTarget {
+                    section: index,
+                    title: format!("{} {}", section.kind, section.name).trim().to_owned(),
+                    is_library: kind == ComponentKind::Library,
+                });
+                module = Some(name);
+            }
+
+            if let Some(module) = module {
+                self.module_offer = Some(ModuleOffer { manifest, module, targets, selected: 0 });
+                return;
+            }
+        }
+    }
+
+    /// Adds the offered module to a list field of the chosen stanza, in the open buffer or on disk.
+    pub fn add_offered_module(&mut self, offer: &ModuleOffer, field: &str) {
+        let Some(target) = offer.targets.get(offer.selected) else { return };
+
+        let result = self.documents.contents(&offer.manifest).and_then(|old_text| {
+            let new_text = CabalFile::parse(&old_text).add_item(Some(target.section), field, &offer.module);
+            self.documents.apply(vec![FileEdit { path: offer.manifest.clone(), old_text, new_text }])
+        });
+
+        match result {
+            Ok(()) => self.project_file_saved(&offer.manifest),
+            Err(e) => self.notifications.error(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CABAL: &str = "\
+cabal-version:      3.0
+name:               parser
+version:            0.1.0.0
+-- A comment between fields
+synopsis:           Parses things
+
+common warnings
+    ghc-options: -Wall
+
+library
+    import:           warnings
+    -- Modules are kept in alphabetical order
+    exposed-modules:  Data.Parser
+                      Data.Parser.Internal
+    build-depends:    base >=4.17 && <5,
+                      -- text is needed for the input type
+                      text ^>=2.0,
+                      containers
+    hs-source-dirs:   src
+    default-language: GHC2021
+
+test-suite spec
+    type:             exitcode-stdio-1.0
+    main-is:          Spec.hs
+    build-depends:
+        , base
+        , hspec
+        , parser
+    if os(windows)
+        build-depends: Win32
+    other-modules: Data.ParserSpec Data.RenderSpec
+";
+
+    fn section(cabal: &CabalFile, kind: &str) -> Option<usize> {
+        cabal.sections.iter().position(|s| s.kind == kind)
+    }
+
+    fn items(text: &str, kind: &str, name: &str) -> Vec<String> {
+        let cabal = CabalFile::parse(text);
+        let section = section(&cabal, kind);
+        cabal.field(section, name).map(|f| f.items().into_iter().map(|i| i.text).collect()).unwrap_or_default()
+    }
+
+    /// The lines of `text` that differ from the original file.
+    fn changes(text: &str) -> Vec<(Option<&str>, Option<&str>)> {
+        let (old, new) = (CABAL.lines().collect::<Vec<_>>(), text.lines().collect::<Vec<_>>());
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+
+        let removed = &old[prefix..old.len() - suffix];
+        let added = &new[prefix..new.len() - suffix];
+        (0..removed.len().max(added.len()))
+            .map(|i| (removed.get(i).copied(), added.get(i).copied()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_stanzas_and_lists() {
+        let cabal = CabalFile::parse(CABAL);
+
+        assert_eq!(cabal.sections.iter().map(|s| (s.kind.as_str(), s.name.as_str())).collect::<Vec<_>>(),
+            [("common", "warnings"), ("library", ""), ("test-suite", "spec")]);
+        assert_eq!(cabal.field(None, "synopsis").unwrap().value(), "Parses things");
+        assert_eq!(items(CABAL, "library", "build-depends"), ["base >=4.17 && <5", "text ^>=2.0", "containers"]);
+        assert_eq!(items(CABAL, "library", "exposed-modules"), ["Data.Parser", "Data.Parser.Internal"]);
+        assert_eq!(items(CABAL, "test-suite", "build-depends"), ["base", "hspec", "parser"]);
+        assert_eq!(items(CABAL, "test-suite", "other-modules"), ["Data.ParserSpec", "Data.RenderSpec"]);
+    }
+
+    #[test]
+    fn unchanged_file_round_trips() {
+        let cabal = CabalFile::parse(CABAL);
+        assert_eq!(cabal.render(cabal.lines.clone()), CABAL);
+
+        let crlf = CABAL.replace('\n', "\r\n");
+        let cabal = CabalFile::parse(&crlf);
+        let library = section(&cabal, "library");
+        let text = cabal.add_item(library, "build-depends", "mtl");
+        assert_eq!(text.replace("\r\n", "\n"), cabal_with_mtl());
+        assert_eq!(text.matches('\n').count(), text.matches("\r\n").count());
+    }
+
+    fn cabal_with_mtl() -> String {
+        CABAL.replace("                      containers\n", "                      containers,\n                      mtl\n")
+    }
+
+    #[test]
+    fn adds_dependency_in_trailing_comma_layout() {
+        let cabal = CabalFile::parse(CABAL);
+        let text = cabal.add_item(section(&cabal, "library"), "build-depends", "mtl");
+
+        assert_eq!(text, cabal_with_mtl());
+        assert_eq!(items(&text, "library", "build-depends"), ["base >=4.17 && <5", "text ^>=2.0", "containers", "mtl"]);
+    }
+
+    #[test]
+    fn adds_dependency_in_leading_comma_layout() {
+        let cabal = CabalFile::parse(CABAL);
+        let text = cabal.add_item(section(&cabal, "test-suite"), "build-depends", "QuickCheck >=2.14");
+
+        assert_eq!(changes(&text), [(None, Some("        , QuickCheck >=2.14"))]);
+        assert_eq!(items(&text, "test-suite", "build-depends"), ["base", "hspec", "parser", "QuickCheck >=2.14"]);
+    }
+
+    #[test]
+    fn adds_module_to_aligned_list() {
+        let cabal = CabalFile::parse(CABAL);
+        let text = cabal.add_item(section(&cabal, "library"), "exposed-modules", "Data.Render");
+
+        assert_eq!(changes(&text), [(None, Some("                      Data.Render"))]);
+    }
+
+    #[test]
+    fn adds_module_to_single_line_list() {
+        let cabal = CabalFile::parse(CABAL);
+        let text = cabal.add_item(section(&cabal, "test-suite"), "other-modules", "Data.LexerSpec");
+
+        assert_eq!(changes(&text), [(
+            Some("    other-modules: Data.ParserSpec Data.RenderSpec"),
+            Some("    other-modules: Data.ParserSpec Data.RenderSpec Data.LexerSpec"),
+        )]);
+    }
+
+    #[test]
+    fn adds_missing_field_after_the_last_one() {
+        let cabal = CabalFile::parse(CABAL);
+        let text = cabal.add_item(section(&cabal, "library"), "other-modules", "Data.Parser.Lexer");
+
+        assert_eq!(changes(&text), [(None, Some("    other-modules: Data.Parser.Lexer"))]);
+        assert!(text.contains("    default-language: GHC2021\n    other-modules: Data.Parser.Lexer\n\ntest-suite spec"));
+    }
+
+    #[test]
+    fn removes_dependency_keeping_comments() {
+        let cabal = CabalFile::parse(CABAL);
+        let library = section(&cabal, "library");
+
+        let text = cabal.remove_item(library, "build-depends", 1);
+        assert_eq!(changes(&text), [(Some("                      text ^>=2.0,"), None)]);
+        assert!(text.contains("-- text is needed for the input type"));
+
+        // The last element takes the comma of the one before it along
+        let text = cabal.remove_item(library, "build-depends", 2);
+        assert_eq!(changes(&text), [
+            (Some("                      text ^>=2.0,"), Some("                      text ^>=2.0")),
+            (Some("                      containers"), None),
+        ]);
+
+        // The first element is on the key line, the next one moves up in its place
+        let text = cabal.remove_item(library, "build-depends", 0);
+        assert_eq!(items(&text, "library", "build-depends"), ["text ^>=2.0", "containers"]);
+        assert!(text.contains("-- A comment between fields") && text.contains("-- Modules are kept in alphabetical order"));
+    }
+
+    #[test]
+    fn removes_dependency_in_leading_comma_layout() {
+        let cabal = CabalFile::parse(CABAL);
+        let tests = section(&cabal, "test-suite");
+
+        let text = cabal.remove_item(tests, "build-depends", 1);
+        assert_eq!(changes(&text), [(Some("        , hspec"), None)]);
+
+        let text = cabal.remove_item(tests, "build-depends", 0);
+        assert_eq!(changes(&text), [(Some("        , base"), None)]);
+        assert_eq!(items(&text, "test-suite", "build-depends"), ["hspec", "parser"]);
+    }
+
+    #[test]
+    fn removes_modules() {
+        let cabal = CabalFile::parse(CABAL);
+
+        let text = cabal.remove_item(section(&cabal, "test-suite"), "other-modules", 0);
+        assert_eq!(changes(&text), [(
+            Some("    other-modules: Data.ParserSpec Data.RenderSpec"),
+            Some("    other-modules: Data.RenderSpec"),
+        )]);
+
+        let text = cabal.remove_item(section(&cabal, "library"), "exposed-modules", 1);
+        assert_eq!(changes(&text), [(Some("                      Data.Parser.Internal"), None)]);
+    }
+
+    #[test]
+    fn removing_the_last_element_removes_the_field() {
+        let cabal = CabalFile::parse(CABAL);
+        let library = section(&cabal, "library");
+        let text = cabal.remove_item(library, "hs-source-dirs", 0);
+
+        assert_eq!(changes(&text), [(Some("    hs-source-dirs:   src"), None)]);
+        assert!(CabalFile::parse(&text).field(library, "hs-source-dirs").is_none());
+    }
+
+    #[test]
+    fn removes_first_dependency_after_the_key() {
+        let text = "library\n  build-depends: base\n               , text\n               , mtl\n";
+        let cabal = CabalFile::parse(text);
+
+        assert_eq!(cabal.remove_item(Some(0), "build-depends", 0), "library\n  build-depends:\n                 text\n               , mtl\n");
+    }
+
+    #[test]
+    fn add_then_remove_restores_the_file() {
+        let cabal = CabalFile::parse(CABAL);
+        let tests = section(&cabal, "test-suite");
+
+        let added = CabalFile::parse(&cabal.add_item(tests, "build-depends", "mtl"));
+        assert_eq!(added.remove_item(tests, "build-depends", 3), CABAL);
+
+        let library = section(&cabal, "library");
+        let added = CabalFile::parse(&cabal.add_item(library, "build-depends", "mtl"));
+        assert_eq!(added.remove_item(library, "build-depends", 3), CABAL);
+
+        let added = CabalFile::parse(&cabal.add_item(library, "exposed-modules", "Data.Render"));
+        assert_eq!(added.remove_item(library, "exposed-modules", 2), CABAL);
+    }
+
+    #[test]
+    fn sets_values() {
+        let cabal = CabalFile::parse(CABAL);
+
+        let text = cabal.set_value(None, "version", "0.2.0.0");
+        assert_eq!(changes(&text), [(Some("version:            0.1.0.0"), Some("version:            0.2.0.0"))]);
+
+        let text = cabal.set_value(None, "description", "First paragraph.\n\nSecond paragraph.");
+        assert!(text.contains("synopsis:           Parses things\ndescription:\n  First paragraph.\n  .\n  Second paragraph.\n\ncommon warnings"));
+    }
+
+    #[test]
+    fn splits_dependencies() {
+        assert_eq!(split_dependency("base >=4.17 && <5"), ("base", ">=4.17 && <5"));
+        assert_eq!(split_dependency("text^>=2.0"), ("text", "^>=2.0"));
+        assert_eq!(split_dependency("containers"), ("containers", ""));
+    }
+}
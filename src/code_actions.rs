@@ -0,0 +1,205 @@
+use std::{
+    sync::mpsc::Receiver,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    app::Fexc,
+    document::{apply_text_edits, char_to_position, line_col_to_char},
+    hlint::{self, HlintResult, HlintSuggestion},
+    lsp,
+};
+
+/// An entry of the code actions menu.
+#[derive(Debug, Clone)]
+pub enum QuickFix {
+    Lsp(Box<lsp_types::CodeActionOrCommand>),
+    Hlint(HlintSuggestion),
+}
+
+impl QuickFix {
+    pub fn title(&self) -> String {
+        match self {
+            QuickFix::Lsp(action) => match action.as_ref() {
+                lsp_types::CodeActionOrCommand::Command(command) => command.title.clone(),
+                lsp_types::CodeActionOrCommand::CodeAction(action) => action.title.clone(),
+            },
+            QuickFix::Hlint(suggestion) => match &suggestion.to {
+                Some(to) => format!("{} ({}): {to}", suggestion.hint, suggestion.severity),
+                None => format!("{} ({})", suggestion.hint, suggestion.severity),
+            },
+        }
+    }
+}
+
+/// Document id, version and zero-based cursor line the actions were computed for.
+type ActionsKey = (u64, i32, u32);
+
+#[derive(Default)]
+pub struct CodeActions {
+    pub menu_open: bool,
+    /// Screen position of the cursor, where the menu pops up.
+    pub anchor: Option<egui::Pos2>,
+    lsp_actions: Option<(ActionsKey, Vec<lsp_types::CodeActionOrCommand>)>,
+    hlint: Option<(u64, i32, Vec<HlintSuggestion>)>,
+    hlint_job: Option<(u64, i32, Receiver<HlintResult>)>,
+    requested: Option<ActionsKey>,
+    seen: Option<(ActionsKey, Instant)>,
+}
+
+impl CodeActions {
+    /// Time the cursor has to rest on a line before actions are requested for it.
+    pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+    pub fn set_lsp(&mut self, key: ActionsKey, actions: Vec<lsp_types::CodeActionOrCommand>) {
+        self.lsp_actions = Some((key, actions));
+    }
+
+    /// The actions available on the given line, language server actions first.
+    pub fn fixes(&self, doc: u64, version: i32, line: u32) -> Vec<QuickFix> {
+        let lsp = self.lsp_actions.iter()
+            .filter(|(key, _)| *key == (doc, version, line))
+            .flat_map(|(_, actions)| actions.iter().map(|a| QuickFix::Lsp(Box::new(a.clone()))));
+
+        let hlint = self.hlint.iter()
+            .filter(|(d, v, _)| (*d, *v) == (doc, version))
+            .flat_map(|(_, _, suggestions)| suggestions.iter())
+            .filter(|s| (s.start_line..=s.end_line).contains(&(line as usize + 1)))
+            .cloned()
+            .map(QuickFix::Hlint);
+
+        lsp.chain(hlint).collect()
+    }
+}
+
+impl Fexc {
+    /// Requests code actions for the cursor line and lints the active document once the cursor settles.
+    pub fn update_code_actions(&mut self, ctx: &egui::Context) {
+        self.poll_hlint();
+
+        let doc = self.documents.active();
+        let line = char_to_position(&doc.text, doc.cursor).line;
+        let key = (doc.id, doc.version, line);
+
+        if self.code_actions.requested == Some(key) {
+            return;
+        }
+
+        match self.code_actions.seen {
+            Some((seen, since)) if seen == key => {
+                if since.elapsed() < CodeActions::DEBOUNCE {
+                    ctx.request_repaint_after(CodeActions::DEBOUNCE - since.elapsed());
+                    return;
+                }
+            }
+            _ => {
+                self.code_actions.seen = Some((key, Instant::now()));
+                ctx.request_repaint_after(CodeActions::DEBOUNCE);
+                return;
+            }
+        }
+
+        self.code_actions.requested = Some(key);
+
+        let lsp = self.languages.server_for(doc.path.as_deref(), &mut self.lsp, &mut self.language_servers);
+        if let (Some(lsp), Some(path)) = (lsp, &doc.path) {
+            let range = lsp_types::Range::new(
+                lsp_types::Position::new(line, 0),
+                lsp_types::Position::new(line + 1, 0),
+            );
+
+            let diagnostics = self.diagnostics.for_path(path)
+                .filter(|d| d.range.start.line <= line && line <= d.range.end.line)
+                .cloned()
+                .collect();
+
+            if let Err(e) = lsp.code_actions(path, doc.id, doc.version, range, diagnostics) {
+                log::error!("{e}");
+            }
+        }
+
+        let linted = self.code_actions.hlint.as_ref().map(|(d, v, _)| (*d, *v));
+        let linting = self.code_actions.hlint_job.as_ref().map(|(d, v, _)| (*d, *v));
+
+        if self.config.hlint && linted != Some((doc.id, doc.version)) && linting != Some((doc.id, doc.version)) {
+            let receiver = hlint::run(doc.text.clone(), self.current_project.clone(), ctx.clone());
+            self.code_actions.hlint_job = Some((doc.id, doc.version, receiver));
+        }
+    }
+
+    fn poll_hlint(&mut self) {
+        let Some((doc, version, receiver)) = &self.code_actions.hlint_job else { return };
+
+        if let Ok(result) = receiver.try_recv() {
+            let (doc, version) = (*doc, *version);
+            self.code_actions.hlint_job = None;
+
+            match result {
+                Ok(suggestions) => self.code_actions.hlint = Some((doc, version, suggestions)),
+                // A missing hlint is not worth a notification on every cursor move
+                Err(e) => log::warn!("{e}"),
+            }
+        }
+    }
+
+    pub fn apply_quick_fix(&mut self, fix: QuickFix) {
+        match fix {
+            QuickFix::Lsp(action) => match *action {
+                lsp_types::CodeActionOrCommand::Command(command) => self.execute_lsp_command(&command),
+                lsp_types::CodeActionOrCommand::CodeAction(action) => self.apply_code_action(action),
+            },
+            QuickFix::Hlint(suggestion) => {
+                let Some(to) = suggestion.to else { return };
+                let doc = self.documents.active_mut();
+
+                let start = line_col_to_char(&doc.text, suggestion.start_line - 1, suggestion.start_column - 1);
+                let end = line_col_to_char(&doc.text, suggestion.end_line - 1, suggestion.end_column - 1);
+
+                let edit = lsp_types::TextEdit::new(
+                    lsp_types::Range::new(char_to_position(&doc.text, start), char_to_position(&doc.text, end)),
+                    to,
+                );
+
+                let text = apply_text_edits(&doc.text, &[edit]);
+                doc.replace_preserving_cursor(text);
+            }
+        }
+    }
+
+    /// Applies the edit of a code action and runs its command, resolving the action first if it has neither.
+    pub fn apply_code_action(&mut self, action: lsp_types::CodeAction) {
+        let path = self.documents.active().path.as_deref();
+        let Some(lsp) = self.languages.server_for(path, &mut self.lsp, &mut self.language_servers) else { return };
+
+        if action.edit.is_none() && action.command.is_none() {
+            if action.data.is_some()
+                && let Err(e) = lsp.resolve_code_action(action)
+            {
+                self.notifications.error(e.to_string());
+            }
+            return;
+        }
+
+        if let Some(edit) = action.edit {
+            let result = lsp::workspace_edit_to_file_edits(&self.documents, edit)
+                .and_then(|edits| self.documents.apply(edits));
+
+            if let Err(e) = result {
+                self.notifications.error(e.to_string());
+            }
+        }
+
+        if let Some(command) = action.command {
+            self.execute_lsp_command(&command);
+        }
+    }
+
+    pub fn execute_lsp_command(&mut self, command: &lsp_types::Command) {
+        let path = self.documents.active().path.as_deref();
+        if let Some(lsp) = self.languages.server_for(path, &mut self.lsp, &mut self.language_servers)
+            && let Err(e) = lsp.execute_command(command)
+        {
+            self.notifications.error(e.to_string());
+        }
+    }
+}
@@ -0,0 +1,426 @@
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+
+use crate::{
+    app::{BottomView, Fexc, Page},
+    widgets::{
+        command_palette::CommandPaletteState, editor::CodeEditor, open_dialog::OpenState, symbol_search::SymbolSearchState,
+    },
+};
+
+/// How many recently run commands the palette keeps at the top.
+const MAX_RECENT: usize = 10;
+
+const fn shortcut(modifiers: egui::Modifiers, key: egui::Key) -> Option<egui::KeyboardShortcut> {
+    Some(egui::KeyboardShortcut::new(modifiers, key))
+}
+
+/// Parses a shortcut such as `Ctrl+Shift+P` or `Alt+F2`. `Ctrl` and `Cmd` both stand for the
+/// platform's command key.
+pub fn parse_shortcut(text: &str) -> Option<egui::KeyboardShortcut> {
+    let (modifier_names, key) = text.trim().rsplit_once('+').unwrap_or(("", text.trim()));
+    let key = egui::Key::from_name(key.trim())?;
+
+    let mut modifiers = egui::Modifiers::NONE;
+    for name in modifier_names.split('+').map(str::trim).filter(|name| !name.is_empty()) {
+        modifiers = modifiers.plus(match name.to_lowercase().as_str() {
+            "ctrl" | "cmd" | "command" => egui::Modifiers::COMMAND,
+            "alt" | "option" => egui::Modifiers::ALT,
+            "shift" => egui::Modifiers::SHIFT,
+            _ => return None,
+        });
+    }
+
+    Some(egui::KeyboardShortcut::new(modifiers, key))
+}
+
+/// The commands built into the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoreCommand {
+    CommandPalette,
+    Open,
+    Save,
+    Quit,
+    FormatDocument,
+    CodeActions,
+    SymbolSearch,
+    ToggleComment,
+    Rename,
+    ReplLoad,
+    ReplReload,
+    ReplEval,
+    RunLastTask,
+    StopTask,
+    ShowProject,
+    ShowOutline,
+    ShowSearch,
+    ShowTests,
+    ShowPlugins,
+    ShowSettings,
+    ShowTerminal,
+    ShowRepl,
+    ShowOutput,
+    ShowProblems,
+}
+
+impl CoreCommand {
+    pub const ALL: [CoreCommand; 24] = [
+        CoreCommand::CommandPalette,
+        CoreCommand::Open,
+        CoreCommand::Save,
+        CoreCommand::Quit,
+        CoreCommand::FormatDocument,
+        CoreCommand::CodeActions,
+        CoreCommand::SymbolSearch,
+        CoreCommand::ToggleComment,
+        CoreCommand::Rename,
+        CoreCommand::ReplLoad,
+        CoreCommand::ReplReload,
+        CoreCommand::ReplEval,
+        CoreCommand::RunLastTask,
+        CoreCommand::StopTask,
+        CoreCommand::ShowProject,
+        CoreCommand::ShowOutline,
+        CoreCommand::ShowSearch,
+        CoreCommand::ShowTests,
+        CoreCommand::ShowPlugins,
+        CoreCommand::ShowSettings,
+        CoreCommand::ShowTerminal,
+        CoreCommand::ShowRepl,
+        CoreCommand::ShowOutput,
+        CoreCommand::ShowProblems,
+    ];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            CoreCommand::CommandPalette => "view.command-palette",
+            CoreCommand::Open => "file.open",
+            CoreCommand::Save => "file.save",
+            CoreCommand::Quit => "file.quit",
+            CoreCommand::FormatDocument => "edit.format-document",
+            CoreCommand::CodeActions => "edit.code-actions",
+            CoreCommand::SymbolSearch => "edit.search-symbols",
+            CoreCommand::ToggleComment => "edit.toggle-comment",
+            CoreCommand::Rename => "edit.rename-symbol",
+            CoreCommand::ReplLoad => "repl.load",
+            CoreCommand::ReplReload => "repl.reload",
+            CoreCommand::ReplEval => "repl.eval",
+            CoreCommand::RunLastTask => "build.run-last-task",
+            CoreCommand::StopTask => "build.stop-task",
+            CoreCommand::ShowProject => "view.project",
+            CoreCommand::ShowOutline => "view.outline",
+            CoreCommand::ShowSearch => "view.search",
+            CoreCommand::ShowTests => "view.tests",
+            CoreCommand::ShowPlugins => "view.plugins",
+            CoreCommand::ShowSettings => "view.settings",
+            CoreCommand::ShowTerminal => "view.terminal",
+            CoreCommand::ShowRepl => "view.repl",
+            CoreCommand::ShowOutput => "view.output",
+            CoreCommand::ShowProblems => "view.problems",
+        }
+    }
+
+    /// The i18n keys of the menu the command belongs to and of its title.
+    pub fn i18n_keys(self) -> (&'static str, &'static str) {
+        match self {
+            CoreCommand::CommandPalette => ("view", "command_palette"),
+            CoreCommand::Open => ("file", "open"),
+            CoreCommand::Save => ("file", "save"),
+            CoreCommand::Quit => ("file", "quit"),
+            CoreCommand::FormatDocument => ("edit", "format_document"),
+            CoreCommand::CodeActions => ("edit", "code_actions"),
+            CoreCommand::SymbolSearch => ("edit", "go_to_symbol"),
+            CoreCommand::ToggleComment => ("edit", "toggle_comment"),
+            CoreCommand::Rename => ("edit", "rename_symbol"),
+            CoreCommand::ReplLoad => ("repl", "repl_load"),
+            CoreCommand::ReplReload => ("repl", "repl_reload"),
+            CoreCommand::ReplEval => ("repl", "repl_eval"),
+            CoreCommand::RunLastTask => ("build", "run_last_task"),
+            CoreCommand::StopTask => ("build", "stop_task"),
+            CoreCommand::ShowProject => ("view", "project"),
+            CoreCommand::ShowOutline => ("view", "outline"),
+            CoreCommand::ShowSearch => ("view", "search"),
+            CoreCommand::ShowTests => ("view", "tests"),
+            CoreCommand::ShowPlugins => ("view", "plugins"),
+            CoreCommand::ShowSettings => ("view", "settings"),
+            CoreCommand::ShowTerminal => ("view", "terminal"),
+            CoreCommand::ShowRepl => ("view", "repl"),
+            CoreCommand::ShowOutput => ("view", "output"),
+            CoreCommand::ShowProblems => ("view", "problems"),
+        }
+    }
+
+    pub fn shortcut(self) -> Option<egui::KeyboardShortcut> {
+        use egui::{Key, Modifiers};
+
+        match self {
+            CoreCommand::CommandPalette => shortcut(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::P),
+            CoreCommand::Open => shortcut(Modifiers::COMMAND, Key::O),
+            CoreCommand::Save => shortcut(Modifiers::COMMAND, Key::S),
+            CoreCommand::FormatDocument => shortcut(Modifiers::SHIFT.plus(Modifiers::ALT), Key::F),
+            CoreCommand::CodeActions => shortcut(Modifiers::COMMAND, Key::Period),
+            CoreCommand::SymbolSearch => shortcut(Modifiers::COMMAND, Key::T),
+            CoreCommand::ToggleComment => shortcut(Modifiers::COMMAND, Key::Slash),
+            CoreCommand::Rename => shortcut(Modifiers::NONE, Key::F2),
+            CoreCommand::ReplEval => shortcut(Modifiers::COMMAND, Key::Enter),
+            CoreCommand::RunLastTask => shortcut(Modifiers::COMMAND, Key::B),
+            _ => None,
+        }
+    }
+
+    /// Whether the command's shortcut only works while the code editor has focus.
+    pub fn in_editor(self) -> bool {
+        matches!(self, CoreCommand::ToggleComment | CoreCommand::Rename)
+    }
+
+    /// The keyboard shortcut formatted for menus, empty if there is none.
+    pub fn shortcut_text(self, ctx: &egui::Context) -> String {
+        self.shortcut().map(|s| ctx.format_shortcut(&s)).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandAction {
+    Core(CoreCommand),
+    /// A command registered by a plugin with [`crate::plugin::PluginContext::register_command`].
+    Plugin { plugin: String, command: String },
+}
+
+/// A title that is either looked up in the translations or shown as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandTitle {
+    I18n(&'static str),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    /// A unique id such as `file.save`, or `whitespace.trim-trailing-whitespace` for plugin commands.
+    pub id: String,
+    /// The menu the command belongs to, or the plugin that registered it.
+    pub category: CommandTitle,
+    pub title: CommandTitle,
+    pub shortcut: Option<egui::KeyboardShortcut>,
+    pub action: CommandAction,
+}
+
+impl Command {
+    pub fn core(command: CoreCommand) -> Self {
+        let (category, title) = command.i18n_keys();
+        Command {
+            id: command.id().to_owned(),
+            category: CommandTitle::I18n(category),
+            title: CommandTitle::I18n(title),
+            shortcut: command.shortcut(),
+            action: CommandAction::Core(command),
+        }
+    }
+
+    pub fn plugin(plugin: &str, command: &str, title: &str, shortcut: Option<egui::KeyboardShortcut>) -> Self {
+        Command {
+            id: format!("{plugin}.{command}"),
+            category: CommandTitle::Text(plugin.to_owned()),
+            title: CommandTitle::Text(title.to_owned()),
+            shortcut,
+            action: CommandAction::Plugin { plugin: plugin.to_owned(), command: command.to_owned() },
+        }
+    }
+
+    /// The plugin that registered the command, if any.
+    pub fn plugin_id(&self) -> Option<&str> {
+        match &self.action {
+            CommandAction::Plugin { plugin, .. } => Some(plugin),
+            CommandAction::Core(_) => None,
+        }
+    }
+}
+
+/// A shortcut a plugin bound to a command, which takes precedence over the command's own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub plugin: String,
+    pub shortcut: egui::KeyboardShortcut,
+    pub command: String,
+}
+
+/// Every command that can be run from the palette, menus or keyboard, with the recently run ones.
+pub struct Commands {
+    commands: Vec<Command>,
+    bindings: Vec<KeyBinding>,
+    /// Ids of the recently run commands, the latest first.
+    recent: Vec<String>,
+}
+
+impl Default for Commands {
+    fn default() -> Self {
+        Commands {
+            commands: CoreCommand::ALL.into_iter().map(Command::core).collect(),
+            bindings: vec![],
+            recent: vec![],
+        }
+    }
+}
+
+impl Commands {
+    pub fn iter(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Command> {
+        self.commands.get(index)
+    }
+
+    pub fn plugin_commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter().filter(|c| c.plugin_id().is_some())
+    }
+
+    /// Adds a command, replacing the one with the same id.
+    pub fn register(&mut self, command: Command) {
+        match self.commands.iter_mut().find(|c| c.id == command.id) {
+            Some(old) => *old = command,
+            None => self.commands.push(command),
+        }
+    }
+
+    /// Binds `shortcut` to the command with the id `command`, replacing an earlier binding of it.
+    pub fn bind(&mut self, binding: KeyBinding) {
+        self.bindings.retain(|b| b.shortcut != binding.shortcut);
+        self.bindings.push(binding);
+    }
+
+    /// The shortcut that runs `command`, a binding if it has one.
+    pub fn shortcut(&self, command: &Command) -> Option<egui::KeyboardShortcut> {
+        self.bindings.iter().rev()
+            .find(|b| b.command == command.id)
+            .map(|b| b.shortcut)
+            .or(command.shortcut)
+    }
+
+    pub fn remove_plugin(&mut self, plugin: &str) {
+        self.commands.retain(|c| c.plugin_id() != Some(plugin));
+        self.bindings.retain(|b| b.plugin != plugin);
+    }
+
+    pub fn recent(&self) -> &[String] {
+        &self.recent
+    }
+
+    pub fn set_recent(&mut self, recent: Vec<String>) {
+        self.recent = recent;
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    fn mark_used(&mut self, id: &str) {
+        self.recent.retain(|r| r != id);
+        self.recent.insert(0, id.to_owned());
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    /// Indices of the commands whose label fuzzy matches `query`, the recently run ones first
+    /// and the rest by score. `labels` holds the translated label of every command.
+    pub fn search(&self, query: &str, labels: &[String]) -> Vec<usize> {
+        let matcher = SkimMatcherV2::default();
+
+        let mut matches = self.commands.iter()
+            .zip(labels)
+            .enumerate()
+            .filter_map(|(i, (command, label))| {
+                let score = matcher.fuzzy_match(label, query)?;
+                let recent = self.recent.iter().position(|r| *r == command.id).unwrap_or(usize::MAX);
+                Some((recent, score, i))
+            })
+            .collect::<Vec<_>>();
+
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(&b.2)));
+        matches.into_iter().map(|(_, _, i)| i).collect()
+    }
+}
+
+impl Fexc {
+    pub fn command_title(&self, title: &CommandTitle) -> String {
+        match title {
+            CommandTitle::I18n(key) => self.i18n(key).to_owned(),
+            CommandTitle::Text(text) => text.clone(),
+        }
+    }
+
+    /// The label the palette shows and searches, e.g. `File: Save`.
+    pub fn command_label(&self, command: &Command) -> String {
+        format!("{}: {}", self.command_title(&command.category), self.command_title(&command.title))
+    }
+
+    /// Runs the commands whose keyboard shortcuts were pressed, the key bindings first. Keys typed
+    /// into the terminal are left to it, and editing commands only run while the editor has focus.
+    pub fn handle_command_shortcuts(&mut self, ctx: &egui::Context) {
+        if self.terminals.has_focus(ctx) {
+            return;
+        }
+
+        let editor_focused = ctx.memory(|m| m.has_focus(CodeEditor::id(self.documents.active())));
+        let available = |id: &str| {
+            editor_focused || !self.commands.iter().any(|c| c.id == id && matches!(c.action, CommandAction::Core(core) if core.in_editor()))
+        };
+
+        let bound = self.commands.bindings.iter()
+            .map(|b| (b.shortcut, b.command.clone()));
+        let own = self.commands.iter()
+            .filter_map(|c| Some((c.shortcut?, c.id.clone())));
+
+        let pressed = bound.chain(own)
+            .filter(|(_, id)| available(id))
+            .filter(|(shortcut, _)| ctx.input_mut(|i| i.consume_shortcut(shortcut)))
+            .map(|(_, id)| id)
+            .collect::<Vec<_>>();
+
+        for id in pressed {
+            self.run_command(&id, ctx);
+        }
+    }
+
+    /// Runs the command with the id `id` and moves it to the top of the recent commands.
+    pub fn run_command(&mut self, id: &str, ctx: &egui::Context) {
+        let Some(action) = self.commands.iter().find(|c| c.id == id).map(|c| c.action.clone()) else {
+            log::warn!("Unknown command {id}");
+            return;
+        };
+
+        // Opening the palette is not worth remembering
+        if action != CommandAction::Core(CoreCommand::CommandPalette) {
+            self.commands.mark_used(id);
+        }
+
+        match action {
+            CommandAction::Core(command) => self.run_core_command(command, ctx),
+            CommandAction::Plugin { plugin, command } => self.run_plugin_command(&plugin, &command),
+        }
+    }
+
+    fn run_core_command(&mut self, command: CoreCommand, ctx: &egui::Context) {
+        match command {
+            CoreCommand::CommandPalette => self.command_palette = Some(CommandPaletteState::default()),
+            CoreCommand::Open => self.open_dialog = Some(OpenState::default()),
+            CoreCommand::Save => self.save_active(ctx),
+            CoreCommand::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            CoreCommand::FormatDocument => self.format_active(ctx, false),
+            CoreCommand::CodeActions => self.code_actions.menu_open = !self.code_actions.menu_open,
+            CoreCommand::SymbolSearch => self.symbol_search = Some(SymbolSearchState::default()),
+            CoreCommand::ToggleComment => self.toggle_comment_active(),
+            CoreCommand::Rename => self.start_rename(),
+            CoreCommand::ReplLoad => self.repl_load_active(ctx),
+            CoreCommand::ReplReload => self.repl_reload(ctx),
+            CoreCommand::ReplEval => self.repl_eval_selection(ctx),
+            CoreCommand::RunLastTask => self.run_last_task(ctx),
+            CoreCommand::StopTask => self.tasks.stop(),
+            CoreCommand::ShowProject => self.current_page = Page::Project,
+            CoreCommand::ShowOutline => self.current_page = Page::Outline,
+            CoreCommand::ShowSearch => self.current_page = Page::Search,
+            CoreCommand::ShowTests => self.current_page = Page::Tests,
+            CoreCommand::ShowPlugins => self.current_page = Page::Plugins,
+            CoreCommand::ShowSettings => self.current_page = Page::Settings,
+            CoreCommand::ShowTerminal => self.bottom_view = BottomView::Terminal,
+            CoreCommand::ShowRepl => self.bottom_view = BottomView::Repl,
+            CoreCommand::ShowOutput => self.bottom_view = BottomView::Output,
+            CoreCommand::ShowProblems => self.bottom_view = BottomView::Problems,
+        }
+    }
+}
+
@@ -1,29 +1,37 @@
-use std::io;
+use std::{collections::BTreeMap, io, path::PathBuf};
 
 use fs_err as fs;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::i18n::Language;
+use crate::{format::Formatter, i18n::Language, plugin::permissions::Permission, tasks::Task};
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
 pub struct Config {
     pub theme: egui::Theme,
     pub language: Language,
     pub editor_font_size: u8,
+    pub lsp_command: Vec<String>,
+    pub formatter: Formatter,
+    pub format_on_save: bool,
+    pub inlay_type_hints: bool,
+    pub inlay_other_hints: bool,
+    pub code_lenses: bool,
+    pub hlint: bool,
+    pub watch_mode: bool,
+    pub tasks: Vec<Task>,
+    /// Ids of the plugins that are not loaded.
+    pub disabled_plugins: Vec<String>,
+    /// Sensitive permissions the user granted, per plugin.
+    pub plugin_permissions: BTreeMap<String, Vec<Permission>>,
+    /// Reload installed plugins when their files change.
+    pub plugin_developer_mode: bool,
 }
 
 impl Config {
     pub fn load() -> Result<Self, ConfigError> {
-        let config_dir = dirs::config_dir()
-            .ok_or(ConfigError::ConfigFolderNotSupported)?;
-
-        let config_path = config_dir.join("fexc");
-
-        fs::create_dir_all(&config_path)
-            .map_err(ConfigError::CreateDir)?;
-
-        let config_file = config_path.join("config.toml");
+        let config_file = config_dir()?.join("config.toml");
         if config_file.exists() {
             let contents = fs::read_to_string(&config_file)
                 .map_err(LoadConfigError::Io)
@@ -52,15 +60,7 @@ impl Config {
     }
 
     pub fn save(&self) -> Result<(), ConfigError> {
-        let config_dir = dirs::config_dir()
-            .ok_or(ConfigError::ConfigFolderNotSupported)?;
-
-        let config_path = config_dir.join("fexc");
-
-        fs::create_dir_all(&config_path)
-            .map_err(ConfigError::CreateDir)?;
-
-        let config_file = config_path.join("config.toml");
+        let config_file = config_dir()?.join("config.toml");
         let toml = toml::to_string(self)
             .map_err(SaveConfigError::Toml)
             .map_err(ConfigError::SaveConfig)?;
@@ -73,6 +73,18 @@ impl Config {
     }
 }
 
+/// The editor's directory in the platform's config folder, created if it does not exist yet.
+pub fn config_dir() -> Result<PathBuf, ConfigError> {
+    let config_path = dirs::config_dir()
+        .ok_or(ConfigError::ConfigFolderNotSupported)?
+        .join("fexc");
+
+    fs::create_dir_all(&config_path)
+        .map_err(ConfigError::CreateDir)?;
+
+    Ok(config_path)
+}
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("Config folder is not found/supported on this platform")]
@@ -109,6 +121,21 @@ impl Default for Config {
             language: Language::English,
             theme: egui::Theme::Dark,
             editor_font_size: 16,
+            lsp_command: vec![
+                "haskell-language-server-wrapper".to_owned(),
+                "--lsp".to_owned(),
+            ],
+            formatter: Formatter::Ormolu,
+            format_on_save: false,
+            inlay_type_hints: true,
+            inlay_other_hints: true,
+            code_lenses: true,
+            hlint: true,
+            watch_mode: false,
+            tasks: Task::defaults(),
+            disabled_plugins: vec![],
+            plugin_permissions: BTreeMap::new(),
+            plugin_developer_mode: false,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,70 @@
+use std::{collections::{BTreeMap, HashMap}, path::{Path, PathBuf}};
+
+/// Diagnostics of every file, as last published by the language server, reported by the last build
+/// and found by the last watch mode check.
+#[derive(Default)]
+pub struct Diagnostics {
+    lsp: HashMap<PathBuf, Vec<lsp_types::Diagnostic>>,
+    build: HashMap<PathBuf, Vec<lsp_types::Diagnostic>>,
+    watch: HashMap<PathBuf, Vec<lsp_types::Diagnostic>>,
+}
+
+impl Diagnostics {
+    pub fn set_lsp(&mut self, path: PathBuf, diagnostics: Vec<lsp_types::Diagnostic>) {
+        if diagnostics.is_empty() {
+            self.lsp.remove(&path);
+        } else {
+            self.lsp.insert(path, diagnostics);
+        }
+    }
+
+    /// Replaces the diagnostics of the previous build, returning the files whose diagnostics changed.
+    pub fn set_build(&mut self, diagnostics: HashMap<PathBuf, Vec<lsp_types::Diagnostic>>) -> Vec<PathBuf> {
+        let changed = changed_paths(&self.build, &diagnostics);
+        self.build = diagnostics;
+        changed
+    }
+
+    /// Replaces the diagnostics of the previous watch mode check, returning the files whose diagnostics changed.
+    pub fn set_watch(&mut self, diagnostics: HashMap<PathBuf, Vec<lsp_types::Diagnostic>>) -> Vec<PathBuf> {
+        let changed = changed_paths(&self.watch, &diagnostics);
+        self.watch = diagnostics;
+        changed
+    }
+
+    pub fn for_path(&self, path: &Path) -> impl Iterator<Item = &lsp_types::Diagnostic> {
+        self.lsp.get(path).into_iter().flatten()
+            .chain(self.build.get(path).into_iter().flatten())
+            .chain(self.watch.get(path).into_iter().flatten())
+    }
+
+    /// All diagnostics grouped by file, sorted by path.
+    pub fn by_path(&self) -> BTreeMap<&Path, Vec<&lsp_types::Diagnostic>> {
+        let mut result = BTreeMap::<&Path, Vec<&lsp_types::Diagnostic>>::new();
+
+        for (path, diagnostics) in self.lsp.iter().chain(&self.build).chain(&self.watch) {
+            result.entry(path).or_default().extend(diagnostics);
+        }
+
+        for diagnostics in result.values_mut() {
+            diagnostics.sort_by_key(|d| (d.range.start.line, d.range.start.character));
+        }
+
+        result
+    }
+
+    pub fn count(&self) -> usize {
+        self.lsp.values().chain(self.build.values()).chain(self.watch.values()).map(Vec::len).sum()
+    }
+}
+
+fn changed_paths(
+    old: &HashMap<PathBuf, Vec<lsp_types::Diagnostic>>,
+    new: &HashMap<PathBuf, Vec<lsp_types::Diagnostic>>,
+) -> Vec<PathBuf> {
+    old.keys()
+        .chain(new.keys().filter(|path| !old.contains_key(*path)))
+        .filter(|path| old.get(*path) != new.get(*path))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,488 @@
+use std::{io, path::{Path, PathBuf}};
+
+use fs_err as fs;
+use similar::{DiffTag, TextDiff};
+use thiserror::Error;
+
+use crate::languages::Edit;
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub id: u64,
+    pub path: Option<PathBuf>,
+    pub text: String,
+    pub version: i32,
+    pub cursor: usize,
+    /// The other end of the selection, equal to `cursor` when nothing is selected.
+    pub anchor: usize,
+    pub dirty: bool,
+    /// Cursor position the editor should move to on its next frame, selecting back to `anchor`.
+    pub pending_cursor: Option<usize>,
+    /// Cursor and text to push onto the editor's undo stack on its next frame.
+    pub undo_snapshot: Option<(usize, String)>,
+}
+
+impl Document {
+    pub fn untitled(text: &str) -> Self {
+        Document {
+            id: 0,
+            path: None,
+            text: text.to_owned(),
+            version: 0,
+            cursor: 0,
+            anchor: 0,
+            dirty: false,
+            pending_cursor: None,
+            undo_snapshot: None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.path.as_ref()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "untitled".to_owned())
+    }
+
+    /// Marks the document as modified after its text was edited in place.
+    pub fn touch(&mut self) {
+        self.version += 1;
+        self.dirty = true;
+    }
+
+    /// Replaces the text with `new_text` as an undoable step, keeping the cursor
+    /// on the same character by mapping it through a char-level diff.
+    pub fn replace_preserving_cursor(&mut self, new_text: String) {
+        if self.text == new_text {
+            return;
+        }
+
+        let cursor = map_char_index(&self.text, &new_text, self.cursor);
+        let old_text = std::mem::replace(&mut self.text, new_text);
+        self.undo_snapshot = Some((self.cursor, old_text));
+        self.cursor = cursor;
+        self.anchor = cursor;
+        self.pending_cursor = Some(cursor);
+        self.touch();
+    }
+
+    /// Applies an edit made outside of the editor as an undoable step.
+    pub fn apply_edit(&mut self, edit: Edit) {
+        let old_text = std::mem::replace(&mut self.text, edit.text);
+        self.undo_snapshot = Some((self.cursor, old_text));
+        self.cursor = edit.cursor;
+        self.anchor = edit.anchor;
+        self.pending_cursor = Some(edit.cursor);
+        self.touch();
+    }
+
+    /// Moves the cursor to a zero-based line and char column, scrolling the editor to it.
+    pub fn jump_to(&mut self, line: usize, column: usize) {
+        self.cursor = line_col_to_char(&self.text, line, column);
+        self.anchor = self.cursor;
+        self.pending_cursor = Some(self.cursor);
+    }
+
+    /// The selected text, or `None` if the selection is empty.
+    pub fn selection(&self) -> Option<String> {
+        let (start, end) = (self.cursor.min(self.anchor), self.cursor.max(self.anchor));
+
+        (start != end).then(|| self.text.chars().skip(start).take(end - start).collect())
+    }
+
+    /// The line the cursor is on, without its line break.
+    pub fn cursor_line(&self) -> &str {
+        let line = char_to_position(&self.text, self.cursor).line as usize;
+        self.text.lines().nth(line).unwrap_or_default()
+    }
+
+    pub fn save(&mut self) -> Result<(), DocumentError> {
+        let path = self.path.as_ref()
+            .ok_or(DocumentError::Untitled)?;
+
+        write_atomic(path, &self.text)?;
+        self.dirty = false;
+
+        Ok(())
+    }
+}
+
+/// A set of changes to a single file, computed before being applied.
+#[derive(Debug, Clone)]
+pub struct FileEdit {
+    pub path: PathBuf,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+pub struct Documents {
+    docs: Vec<Document>,
+    active: usize,
+    next_id: u64,
+}
+
+impl Default for Documents {
+    fn default() -> Self {
+        Documents::new(Document::untitled(""))
+    }
+}
+
+impl Documents {
+    pub fn new(doc: Document) -> Self {
+        Documents {
+            docs: vec![Document { id: 0, ..doc }],
+            active: 0,
+            next_id: 1,
+        }
+    }
+
+    /// Makes the document for `path` active, loading it from disk if it is not open yet.
+    pub fn open(&mut self, path: &Path) -> Result<&mut Document, DocumentError> {
+        match self.docs.iter().position(|d| d.path.as_deref() == Some(path)) {
+            Some(i) => self.active = i,
+            None => {
+                let text = fs::read_to_string(path)
+                    .map_err(DocumentError::Read)?;
+
+                self.docs.push(Document {
+                    id: self.next_id,
+                    path: Some(path.to_owned()),
+                    ..Document::untitled(&text)
+                });
+
+                self.next_id += 1;
+                self.active = self.docs.len() - 1;
+            }
+        }
+
+        Ok(&mut self.docs[self.active])
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn set_active(&mut self, index: usize) {
+        if index < self.docs.len() {
+            self.active = index;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Document> {
+        self.docs.iter()
+    }
+
+    pub fn active(&self) -> &Document {
+        &self.docs[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Document {
+        &mut self.docs[self.active]
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Document> {
+        self.docs.iter().find(|d| d.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut Document> {
+        self.docs.iter_mut().find(|d| d.id == id)
+    }
+
+    pub fn find(&self, path: &Path) -> Option<&Document> {
+        self.docs.iter().find(|d| d.path.as_deref() == Some(path))
+    }
+
+    pub fn find_mut(&mut self, path: &Path) -> Option<&mut Document> {
+        self.docs.iter_mut().find(|d| d.path.as_deref() == Some(path))
+    }
+
+    /// Returns the current contents of `path`, preferring the open buffer over the file on disk.
+    pub fn contents(&self, path: &Path) -> Result<String, DocumentError> {
+        match self.find(path) {
+            Some(doc) => Ok(doc.text.clone()),
+            None => fs::read_to_string(path).map_err(DocumentError::Read),
+        }
+    }
+
+    /// Applies precomputed edits: open buffers are updated in place, closed files are written to disk.
+    /// Nothing is changed if any of the files no longer has the text the edits were computed from.
+    pub fn apply(&mut self, edits: Vec<FileEdit>) -> Result<(), DocumentError> {
+        for edit in &edits {
+            if self.contents(&edit.path)? != edit.old_text {
+                return Err(DocumentError::Changed(edit.path.clone()));
+            }
+        }
+
+        for edit in edits {
+            match self.find_mut(&edit.path) {
+                Some(doc) => doc.replace_preserving_cursor(edit.new_text),
+                None => write_atomic(&edit.path, &edit.new_text)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it over the original,
+/// so the file is never left half-written.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), DocumentError> {
+    let file_name = path.file_name()
+        .ok_or_else(|| DocumentError::InvalidPath(path.to_owned()))?;
+
+    let tmp_path = path.with_file_name(format!(".{}.fexc-tmp", file_name.to_string_lossy()));
+
+    fs::write(&tmp_path, contents)
+        .map_err(DocumentError::Write)?;
+
+    fs::rename(&tmp_path, path)
+        .map_err(DocumentError::Write)
+}
+
+/// Converts an LSP position (UTF-16 based) into a byte offset, clamped to the text bounds.
+pub fn position_to_byte(text: &str, pos: lsp_types::Position) -> usize {
+    let mut offset = 0;
+
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        if i == pos.line as usize {
+            let mut col = 0;
+            for (byte, ch) in line.char_indices() {
+                if col >= pos.character as usize || ch == '\n' {
+                    return offset + byte;
+                }
+                col += ch.len_utf16();
+            }
+            return offset + line.len();
+        }
+        offset += line.len();
+    }
+
+    text.len()
+}
+
+/// Converts a zero-based line and char column into a char index, clamping the column to the line length.
+pub fn line_col_to_char(text: &str, line: usize, column: usize) -> usize {
+    let line_start = text.split_inclusive('\n')
+        .take(line)
+        .map(|l| l.chars().count())
+        .sum::<usize>();
+
+    let line_len = text.lines()
+        .nth(line)
+        .map(|l| l.chars().count())
+        .unwrap_or(0);
+
+    line_start + column.min(line_len)
+}
+
+/// Converts a char index (as used by egui cursors) into an LSP position.
+pub fn char_to_position(text: &str, char_idx: usize) -> lsp_types::Position {
+    let mut line = 0;
+    let mut character = 0;
+
+    for ch in text.chars().take(char_idx) {
+        if ch == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += ch.len_utf16() as u32;
+        }
+    }
+
+    lsp_types::Position::new(line, character)
+}
+
+/// Applies LSP text edits to `text`. Edits refer to the original text, so they are applied back to front;
+/// insertions at the same position keep their order, and edits overlapping an earlier one are skipped.
+pub fn apply_text_edits(text: &str, edits: &[lsp_types::TextEdit]) -> String {
+    let mut ranges = edits.iter()
+        .map(|e| {
+            let start = position_to_byte(text, e.range.start);
+            (start, position_to_byte(text, e.range.end).max(start), e.new_text.as_str())
+        })
+        .collect::<Vec<_>>();
+
+    ranges.sort_by_key(|(start, end, _)| (*start, *end));
+
+    let mut end_of_last = 0;
+    ranges.retain(|(start, end, _)| {
+        let overlaps = *start < end_of_last;
+        if overlaps {
+            log::warn!("Skipping text edit overlapping another one at byte {start}");
+        } else {
+            end_of_last = *end;
+        }
+        !overlaps
+    });
+
+    let mut result = text.to_owned();
+    for (start, end, new_text) in ranges.into_iter().rev() {
+        result.replace_range(start..end, new_text);
+    }
+
+    result
+}
+
+/// Maps a char index in `old` to the same character in `new` through a char-level diff.
+pub fn map_char_index(old: &str, new: &str, index: usize) -> usize {
+    let diff = TextDiff::from_chars(old, new);
+
+    diff.ops().iter()
+        .find(|op| op.old_range().contains(&index))
+        .map(|op| match op.tag() {
+            DiffTag::Equal => op.new_range().start + (index - op.old_range().start),
+            _ => op.new_range().start,
+        })
+        .unwrap_or_else(|| new.chars().count())
+}
+
+/// Returns the identifier under the given char index, if any.
+pub fn word_at(text: &str, char_idx: usize) -> Option<String> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+    let chars = text.chars().collect::<Vec<_>>();
+
+    let mut start = char_idx.min(chars.len());
+    while start > 0 && is_ident(chars[start - 1]) {
+        start -= 1;
+    }
+
+    let mut end = char_idx.min(chars.len());
+    while end < chars.len() && is_ident(chars[end]) {
+        end += 1;
+    }
+
+    (start < end).then(|| chars[start..end].iter().collect())
+}
+
+#[derive(Debug, Error)]
+pub enum DocumentError {
+    #[error("Failed to read file: {0}")]
+    Read(io::Error),
+    #[error("Failed to write file: {0}")]
+    Write(io::Error),
+    #[error("Invalid file path: {0}")]
+    InvalidPath(PathBuf),
+    #[error("Document has no file path")]
+    Untitled,
+    #[error("{0} changed after the edit was prepared, try again")]
+    Changed(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> lsp_types::TextEdit {
+        lsp_types::TextEdit {
+            range: lsp_types::Range::new(lsp_types::Position::new(start.0, start.1), lsp_types::Position::new(end.0, end.1)),
+            new_text: new_text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn positions_count_utf16_columns() {
+        let text = "ab\naé😀b\n";
+        let at = |line, character| position_to_byte(text, lsp_types::Position::new(line, character));
+
+        assert_eq!(at(0, 1), 1);
+        assert_eq!(at(1, 2), 6);
+        assert_eq!(at(1, 4), 10);
+        assert_eq!(&text[at(1, 4)..], "b\n");
+        // Past the end of a line stops before its newline, past the last line at the end
+        assert_eq!(at(0, 10), 2);
+        assert_eq!(at(5, 0), text.len());
+    }
+
+    #[test]
+    fn text_edits_are_applied_against_the_original_text() {
+        let edits = [
+            text_edit((1, 0), (1, 3), "baz"),
+            text_edit((0, 0), (0, 3), "foo"),
+        ];
+
+        assert_eq!(apply_text_edits("abc\nxyz", &edits), "foo\nbaz");
+    }
+
+    #[test]
+    fn adjacent_text_edits_keep_their_order() {
+        let edits = [
+            text_edit((0, 2), (0, 2), "("),
+            text_edit((0, 2), (0, 2), "x"),
+            text_edit((0, 2), (0, 3), "y)"),
+            text_edit((0, 0), (0, 2), "f"),
+        ];
+
+        assert_eq!(apply_text_edits("a b c", &edits), "f(xy) c");
+    }
+
+    #[test]
+    fn overlapping_text_edits_are_skipped() {
+        let edits = [
+            text_edit((0, 0), (0, 3), "one"),
+            text_edit((0, 2), (0, 5), "two"),
+        ];
+
+        assert_eq!(apply_text_edits("abcdef", &edits), "onedef");
+    }
+
+    #[test]
+    fn text_edits_replace_emoji_by_utf16_range() {
+        let edits = [text_edit((0, 1), (0, 3), "!")];
+
+        assert_eq!(apply_text_edits("a😀b", &edits), "a!b");
+    }
+
+    #[test]
+    fn char_indices_follow_the_text_through_edits() {
+        assert_eq!(map_char_index("main = x", "main = pure x", 7), 12);
+        assert_eq!(map_char_index("x = 1", "-- c\nx = 1", 0), 5);
+        assert_eq!(map_char_index("abcdef", "abf", 3), 2);
+        assert_eq!(map_char_index("abc", "abcd", 3), 4);
+    }
+
+    #[test]
+    fn words_include_primes_and_underscores() {
+        let text = "go x' = foo_bar + 1";
+
+        assert_eq!(word_at(text, 4).as_deref(), Some("x'"));
+        assert_eq!(word_at(text, 12).as_deref(), Some("foo_bar"));
+        assert_eq!(word_at(text, 15).as_deref(), Some("foo_bar"));
+        assert_eq!(word_at(text, 16), None);
+        assert_eq!(word_at(text, 100).as_deref(), Some("1"));
+        assert_eq!(word_at("", 0), None);
+    }
+
+    #[test]
+    fn edits_are_only_applied_if_no_file_changed() {
+        let dir = std::env::temp_dir().join(format!("fexc-apply-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (open, closed) = (dir.join("Open.hs"), dir.join("Closed.hs"));
+        fs::write(&open, "x = 1").unwrap();
+        fs::write(&closed, "y = x").unwrap();
+
+        let mut docs = Documents::default();
+        docs.open(&open).unwrap();
+        let edits = || vec![
+            FileEdit { path: open.clone(), old_text: "x = 1".into(), new_text: "z = 1".into() },
+            FileEdit { path: closed.clone(), old_text: "y = x".into(), new_text: "y = z".into() },
+        ];
+
+        // Typing into the open buffer after the edit was computed
+        docs.find_mut(&open).unwrap().text = "x = 2".into();
+        assert!(matches!(docs.apply(edits()), Err(DocumentError::Changed(path)) if path == open));
+        assert_eq!(fs::read_to_string(&closed).unwrap(), "y = x");
+
+        // Changing the closed file on disk
+        docs.find_mut(&open).unwrap().text = "x = 1".into();
+        fs::write(&closed, "y = x + 1").unwrap();
+        assert!(matches!(docs.apply(edits()), Err(DocumentError::Changed(path)) if path == closed));
+        assert_eq!(docs.find(&open).unwrap().text, "x = 1");
+
+        fs::write(&closed, "y = x").unwrap();
+        docs.apply(edits()).unwrap();
+        assert_eq!(docs.find(&open).unwrap().text, "z = 1");
+        assert_eq!(fs::read_to_string(&closed).unwrap(), "y = z");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
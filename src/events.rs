@@ -0,0 +1,316 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{app::Fexc, config::Config};
+
+/// Something that happened in the editor, published on the [`EventBus`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    DocumentOpened { doc: u64, path: Option<PathBuf> },
+    DocumentChanged { doc: u64, path: Option<PathBuf> },
+    DocumentSaved { path: PathBuf },
+    DocumentClosed { doc: u64, path: Option<PathBuf> },
+    /// The cursor of the active document moved to the char index `cursor`.
+    CursorMoved { doc: u64, path: Option<PathBuf>, cursor: usize },
+    ProjectOpened { root: PathBuf },
+    BuildFinished { task: String, success: bool },
+    DiagnosticsUpdated { path: PathBuf },
+    ConfigChanged,
+    ThemeChanged { theme: egui::Theme },
+}
+
+/// The kind of an [`Event`], named in kebab-case such as `document-saved` in scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventKind {
+    DocumentOpened,
+    DocumentChanged,
+    DocumentSaved,
+    DocumentClosed,
+    CursorMoved,
+    ProjectOpened,
+    BuildFinished,
+    DiagnosticsUpdated,
+    ConfigChanged,
+    ThemeChanged,
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::DocumentOpened { .. } => EventKind::DocumentOpened,
+            Event::DocumentChanged { .. } => EventKind::DocumentChanged,
+            Event::DocumentSaved { .. } => EventKind::DocumentSaved,
+            Event::DocumentClosed { .. } => EventKind::DocumentClosed,
+            Event::CursorMoved { .. } => EventKind::CursorMoved,
+            Event::ProjectOpened { .. } => EventKind::ProjectOpened,
+            Event::BuildFinished { .. } => EventKind::BuildFinished,
+            Event::DiagnosticsUpdated { .. } => EventKind::DiagnosticsUpdated,
+            Event::ConfigChanged => EventKind::ConfigChanged,
+            Event::ThemeChanged { .. } => EventKind::ThemeChanged,
+        }
+    }
+
+    /// The file the event is about, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Event::DocumentOpened { path, .. }
+            | Event::DocumentChanged { path, .. }
+            | Event::DocumentClosed { path, .. }
+            | Event::CursorMoved { path, .. } => path.as_deref(),
+            Event::DocumentSaved { path } | Event::DiagnosticsUpdated { path } => Some(path),
+            Event::ProjectOpened { root } => Some(root),
+            Event::BuildFinished { .. } | Event::ConfigChanged | Event::ThemeChanged { .. } => None,
+        }
+    }
+}
+
+/// Which events a subscriber receives.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    /// The kinds to receive, or all of them if empty.
+    pub kinds: Vec<EventKind>,
+    /// Only events about files matching this glob, e.g. `src/**/*.hs`. A pattern without `/`
+    /// is matched against the file name, other patterns against the path relative to the project.
+    pub glob: Option<String>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &Event, root: Option<&Path>) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&event.kind()) {
+            return false;
+        }
+
+        let Some(glob) = &self.glob else { return true };
+        let Some(path) = event.path() else { return false };
+
+        if glob.contains('/') {
+            let path = root.and_then(|root| path.strip_prefix(root).ok()).unwrap_or(path);
+            let segments = path.iter().map(|s| s.to_string_lossy()).collect::<Vec<_>>();
+            let segments = segments.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
+            let pattern = glob.trim_start_matches('/').split('/').collect::<Vec<_>>();
+
+            glob_segments(&pattern, &segments)
+        } else {
+            path.file_name().is_some_and(|name| wildcard(glob, &name.to_string_lossy()))
+        }
+    }
+}
+
+/// Matches path segments against pattern segments, where `**` stands for any number of segments.
+fn glob_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| glob_segments(rest, &path[skip..])),
+        Some((first, rest)) => path.split_first()
+            .is_some_and(|(segment, path)| wildcard(first, segment) && glob_segments(rest, path)),
+    }
+}
+
+/// Matches one path segment against a pattern with `*` and `?`.
+fn wildcard(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*` if the rest does not match
+    let mut star = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some('?') => {
+                p += 1;
+                t += 1;
+            }
+            Some(c) if *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Publish/subscribe channel for editor events. Every subscriber gets its own unbounded
+/// channel, so publishing never waits for a subscriber, however slow it is.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Vec<(EventFilter, mpsc::Sender<Event>)>,
+    /// The project root globs are relative to.
+    root: Option<PathBuf>,
+    /// Path, version and cursor of each open document as of the last frame.
+    documents: HashMap<u64, (Option<PathBuf>, i32, usize)>,
+    /// The theme as of the last [`Event::ThemeChanged`], or the one the editor started with.
+    theme: Option<egui::Theme>,
+}
+
+impl EventBus {
+    pub fn new(theme: egui::Theme) -> Self {
+        EventBus { theme: Some(theme), ..Default::default() }
+    }
+
+    pub fn subscribe(&mut self, filter: EventFilter) -> mpsc::Receiver<Event> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push((filter, sender));
+        receiver
+    }
+
+    /// Sends the event to every matching subscriber, dropping those that went away.
+    pub fn publish(&mut self, event: Event) {
+        if let Event::ProjectOpened { root } = &event {
+            self.root = Some(root.clone());
+        }
+
+        let root = self.root.as_deref();
+        self.subscribers.retain(|(filter, sender)| {
+            !filter.matches(&event, root) || sender.send(event.clone()).is_ok()
+        });
+    }
+
+    /// Publishes [`Event::ConfigChanged`] after the settings were changed, and
+    /// [`Event::ThemeChanged`] if that changed the theme.
+    pub fn config_changed(&mut self, config: &Config) {
+        self.publish(Event::ConfigChanged);
+
+        if self.theme != Some(config.theme) {
+            self.theme = Some(config.theme);
+            self.publish(Event::ThemeChanged { theme: config.theme });
+        }
+    }
+}
+
+impl Fexc {
+    /// Publishes the document and cursor changes since the last frame.
+    pub fn update_events(&mut self) {
+        let active = self.documents.active().id;
+        let mut events = vec![];
+
+        for doc in self.documents.iter() {
+            let state = (doc.path.clone(), doc.version, doc.cursor);
+            let (id, path) = (doc.id, doc.path.clone());
+
+            match self.events.documents.insert(doc.id, state) {
+                None => events.push(Event::DocumentOpened { doc: id, path }),
+                Some((_, version, cursor)) => {
+                    if version != doc.version {
+                        events.push(Event::DocumentChanged { doc: id, path: path.clone() });
+                    }
+                    if cursor != doc.cursor && id == active {
+                        events.push(Event::CursorMoved { doc: id, path, cursor: doc.cursor });
+                    }
+                }
+            }
+        }
+
+        let closed = self.events.documents.keys()
+            .filter(|id| self.documents.get(**id).is_none())
+            .copied()
+            .collect::<Vec<_>>();
+        for doc in closed {
+            if let Some((path, _, _)) = self.events.documents.remove(&doc) {
+                events.push(Event::DocumentClosed { doc, path });
+            }
+        }
+
+        for event in events {
+            self.events.publish(event);
+        }
+    }
+
+    /// Saves the settings and publishes that they changed.
+    pub fn save_config(&mut self) {
+        self.config.save().unwrap_or_else(|e| {
+            log::error!("Failed to save config: {e}");
+        });
+        self.events.config_changed(&self.config);
+    }
+
+    /// Lets the core subsystems react to the events they subscribed to in [`Fexc::new`].
+    pub fn handle_core_events(&mut self) {
+        let Some(receiver) = &self.core_events else { return };
+
+        for event in receiver.try_iter().collect::<Vec<_>>() {
+            match event {
+                Event::DocumentSaved { path } => {
+                    if let Some(doc) = self.documents.iter().find(|d| d.path.as_ref() == Some(&path)) {
+                        self.symbol_index.update_file(&path, &doc.text);
+                    }
+                    self.project_file_saved(&path);
+                    self.offer_new_module(&path);
+                    self.watch_file_saved(&path);
+                }
+                Event::DocumentClosed { path: Some(path), .. } => {
+                    let servers = self.lsp.iter_mut().chain(self.language_servers.values_mut().flatten());
+                    for lsp in servers {
+                        lsp.close_document(&path).unwrap_or_else(|e| {
+                            log::error!("{e}");
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The events the core subsystems react to in [`Fexc::handle_core_events`].
+pub fn core_filter() -> EventFilter {
+    EventFilter { kinds: vec![EventKind::DocumentSaved, EventKind::DocumentClosed], glob: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_changes_are_published_once() {
+        let mut bus = EventBus::new(egui::Theme::Dark);
+        let receiver = bus.subscribe(EventFilter::default());
+        let mut config = Config { theme: egui::Theme::Dark, ..Default::default() };
+
+        bus.config_changed(&config);
+        config.theme = egui::Theme::Light;
+        bus.config_changed(&config);
+        bus.config_changed(&config);
+
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [
+            Event::ConfigChanged,
+            Event::ConfigChanged,
+            Event::ThemeChanged { theme: egui::Theme::Light },
+            Event::ConfigChanged,
+        ]);
+    }
+
+    #[test]
+    fn filters_match_kinds_and_globs() {
+        let root = Path::new("/project");
+        let saved = |path: &str| Event::DocumentSaved { path: PathBuf::from(path) };
+        let filter = |kinds: Vec<EventKind>, glob: Option<&str>| EventFilter { kinds, glob: glob.map(str::to_owned) };
+
+        assert!(filter(vec![], None).matches(&Event::ConfigChanged, Some(root)));
+        assert!(!filter(vec![EventKind::DocumentSaved], None).matches(&Event::ConfigChanged, Some(root)));
+        assert!(filter(vec![], Some("*.hs")).matches(&saved("/project/src/Main.hs"), Some(root)));
+        assert!(!filter(vec![], Some("*.hs")).matches(&saved("/project/app.cabal"), Some(root)));
+        assert!(filter(vec![], Some("src/**/*.hs")).matches(&saved("/project/src/A/B.hs"), Some(root)));
+        assert!(!filter(vec![], Some("src/**/*.hs")).matches(&saved("/project/test/Spec.hs"), Some(root)));
+        assert!(!filter(vec![], Some("*.hs")).matches(&Event::ConfigChanged, Some(root)));
+    }
+}
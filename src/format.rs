@@ -0,0 +1,233 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{app::Fexc, document::apply_text_edits, events::Event, process};
+
+const TAB_SIZE: u32 = 2;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Formatter {
+    #[serde(rename = "ormolu")]
+    Ormolu,
+    #[serde(rename = "fourmolu")]
+    Fourmolu,
+    #[serde(rename = "stylish-haskell")]
+    StylishHaskell,
+    #[serde(rename = "lsp")]
+    Lsp,
+}
+
+impl Formatter {
+    pub const ALL: [Formatter; 4] = [
+        Formatter::Ormolu,
+        Formatter::Fourmolu,
+        Formatter::StylishHaskell,
+        Formatter::Lsp,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Formatter::Ormolu => "ormolu",
+            Formatter::Fourmolu => "fourmolu",
+            Formatter::StylishHaskell => "stylish-haskell",
+            Formatter::Lsp => "LSP",
+        }
+    }
+
+    /// The external program to pipe the buffer through, or `None` if formatting is done by the language server.
+    /// All of them read the module from stdin and print the result to stdout when given no files.
+    pub fn program(self) -> Option<&'static str> {
+        match self {
+            Formatter::Ormolu => Some("ormolu"),
+            Formatter::Fourmolu => Some("fourmolu"),
+            Formatter::StylishHaskell => Some("stylish-haskell"),
+            Formatter::Lsp => None,
+        }
+    }
+}
+
+/// A formatting request that has been sent off and is waiting to be applied to its document.
+pub struct FormatJob {
+    pub doc: u64,
+    pub version: i32,
+    pub save: bool,
+    pub receiver: Option<Receiver<Result<String, FormatError>>>,
+}
+
+/// Pipes `text` through `program` on a background thread.
+pub fn run_external(
+    program: &'static str,
+    text: String,
+    cwd: Option<PathBuf>,
+    ctx: egui::Context,
+) -> Receiver<Result<String, FormatError>> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let result = run_blocking(program, text, cwd);
+        if sender.send(result).is_ok() {
+            ctx.request_repaint();
+        }
+    });
+
+    receiver
+}
+
+fn run_blocking(program: &str, text: String, cwd: Option<PathBuf>) -> Result<String, FormatError> {
+    let output = process::pipe(program, &[], text, cwd.as_deref())
+        .map_err(|e| FormatError::Spawn(program.to_owned(), e))?;
+
+    if !output.status.success() {
+        return Err(FormatError::Failed(
+            program.to_owned(),
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+
+    String::from_utf8(output.stdout)
+        .map_err(|_| FormatError::InvalidOutput(program.to_owned()))
+}
+
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error("Failed to run formatter `{0}`: {1}")]
+    Spawn(String, io::Error),
+    #[error("Formatter stopped unexpectedly")]
+    Disconnected,
+    #[error("Formatter `{0}` failed: {1}")]
+    Failed(String, String),
+    #[error("Formatter `{0}` produced invalid UTF-8")]
+    InvalidOutput(String),
+    #[error("Formatting with the language server requires a saved file and a running server")]
+    LspUnavailable,
+    #[error("Language server failed to format the document: {0}")]
+    Lsp(String),
+}
+
+impl Fexc {
+    /// Starts formatting the active document with the configured formatter.
+    /// With `save`, the document is written to disk once formatting finishes (or fails).
+    pub fn format_active(&mut self, ctx: &egui::Context, save: bool) {
+        let doc = self.documents.active();
+        let mut job = FormatJob {
+            doc: doc.id,
+            version: doc.version,
+            save,
+            receiver: None,
+        };
+
+        match self.config.formatter.program() {
+            Some(program) => {
+                job.receiver = Some(run_external(program, doc.text.clone(), self.current_project.clone(), ctx.clone()));
+            }
+            None => {
+                let lsp = self.languages.server_for(doc.path.as_deref(), &mut self.lsp, &mut self.language_servers);
+                let sent = match (lsp, &doc.path) {
+                    (Some(lsp), Some(path)) => lsp.formatting(path, doc.id, TAB_SIZE)
+                        .inspect_err(|e| log::error!("{e}"))
+                        .is_ok(),
+                    _ => false,
+                };
+
+                if !sent {
+                    self.finish_format(job, Err(FormatError::LspUnavailable));
+                    return;
+                }
+            }
+        }
+
+        self.format_jobs.push(job);
+    }
+
+    /// Saves the active document, formatting it first if format-on-save is enabled.
+    pub fn save_active(&mut self, ctx: &egui::Context) {
+        if self.config.format_on_save {
+            self.format_active(ctx, true);
+        } else {
+            self.save_document(self.documents.active().id);
+        }
+    }
+
+    pub fn save_document(&mut self, id: u64) {
+        let Some(doc) = self.documents.get_mut(id) else { return };
+
+        match doc.save() {
+            Ok(()) => {
+                if let Some(path) = doc.path.clone() {
+                    self.events.publish(Event::DocumentSaved { path });
+                }
+            }
+            Err(e) => self.notifications.error(e.to_string()),
+        }
+    }
+
+    /// Collects finished external formatter runs.
+    pub fn poll_format_jobs(&mut self) {
+        let mut i = 0;
+
+        while i < self.format_jobs.len() {
+            let result = match &self.format_jobs[i].receiver {
+                Some(receiver) => match receiver.try_recv() {
+                    Ok(result) => result,
+                    Err(mpsc::TryRecvError::Empty) => {
+                        i += 1;
+                        continue;
+                    }
+                    Err(mpsc::TryRecvError::Disconnected) => Err(FormatError::Disconnected),
+                },
+                None => {
+                    i += 1;
+                    continue;
+                }
+            };
+
+            let job = self.format_jobs.remove(i);
+            self.finish_format(job, result);
+        }
+    }
+
+    /// Applies the result of a language server formatting request.
+    pub fn finish_lsp_format(&mut self, doc: u64, result: Result<Vec<lsp_types::TextEdit>, String>) {
+        let Some(i) = self.format_jobs.iter().position(|j| j.doc == doc && j.receiver.is_none()) else { return };
+        let job = self.format_jobs.remove(i);
+
+        let result = result
+            .map_err(FormatError::Lsp)
+            .map(|edits| {
+                self.documents.get(doc)
+                    .map(|d| apply_text_edits(&d.text, &edits))
+                    .unwrap_or_default()
+            });
+
+        self.finish_format(job, result);
+    }
+
+    fn finish_format(&mut self, job: FormatJob, result: Result<String, FormatError>) {
+        let Some(doc) = self.documents.get(job.doc) else { return };
+        let outdated = doc.version != job.version;
+
+        match result {
+            // Edits made while the formatter was running win over the formatted text
+            Ok(_) if outdated => {
+                self.notifications.error(self.i18n("format_outdated").to_owned());
+            }
+            Ok(text) => {
+                if let Some(doc) = self.documents.get_mut(job.doc) {
+                    doc.replace_preserving_cursor(text);
+                }
+            }
+            Err(e) => self.notifications.error(e.to_string()),
+        }
+
+        if job.save {
+            self.save_document(job.doc);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+use lsp_types::DiagnosticSeverity;
+
+use crate::project::Package;
+
+/// A `file:line:col` location at the start of a line of compiler output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location<'a> {
+    pub path: &'a str,
+    /// Zero-based line and column.
+    pub start: (usize, usize),
+    /// Zero-based, exclusive end; equal to `start` if GHC gave no span.
+    pub end: (usize, usize),
+    /// Text after the location, e.g. ` error: [GHC-88464]`.
+    pub rest: &'a str,
+}
+
+/// An error or warning reported by GHC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GhcMessage {
+    pub path: PathBuf,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub severity: DiagnosticSeverity,
+    /// The diagnostic code, e.g. `GHC-88464`.
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl GhcMessage {
+    pub fn to_diagnostic(&self) -> lsp_types::Diagnostic {
+        let position = |(line, column): (usize, usize)| lsp_types::Position::new(line as u32, column as u32);
+
+        lsp_types::Diagnostic {
+            range: lsp_types::Range::new(position(self.start), position(self.end)),
+            severity: Some(self.severity),
+            code: self.code.clone().map(lsp_types::NumberOrString::String),
+            source: Some("ghc".to_owned()),
+            message: self.message.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Groups messages into diagnostics per file.
+pub fn to_diagnostics(messages: &[GhcMessage]) -> HashMap<PathBuf, Vec<lsp_types::Diagnostic>> {
+    let mut diagnostics = HashMap::<PathBuf, Vec<lsp_types::Diagnostic>>::new();
+
+    for message in messages {
+        diagnostics.entry(message.path.clone()).or_default().push(message.to_diagnostic());
+    }
+
+    diagnostics
+}
+
+/// Parses the location GHC prints in front of its messages. Supports `path:L:C`, `path:L:C1-C2`
+/// and `path:(L1,C1)-(L2,C2)`, followed by a colon.
+pub fn parse_location(line: &str) -> Option<Location<'_>> {
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    line.match_indices(':')
+        .filter(|(i, _)| *i > 0)
+        .find_map(|(i, _)| {
+            let (start, end, rest) = parse_span(&line[i + 1..])?;
+            Some(Location { path: &line[..i], start, end, rest })
+        })
+}
+
+type Span<'a> = ((usize, usize), (usize, usize), &'a str);
+
+fn parse_span(text: &str) -> Option<Span<'_>> {
+    fn number(text: &str) -> Option<(usize, &str)> {
+        let digits = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+        let value = text[..digits].parse::<usize>().ok().filter(|&v| v > 0)?;
+        Some((value, &text[digits..]))
+    }
+
+    if let Some(text) = text.strip_prefix('(') {
+        let (l1, text) = number(text)?;
+        let (c1, text) = number(text.strip_prefix(',')?)?;
+        let (l2, text) = number(text.strip_prefix(")-(")?)?;
+        let (c2, text) = number(text.strip_prefix(',')?)?;
+        let rest = text.strip_prefix("):")?;
+        return Some(((l1 - 1, c1 - 1), (l2 - 1, c2), rest));
+    }
+
+    let (line, text) = number(text)?;
+    let (column, text) = number(text.strip_prefix(':')?)?;
+
+    if let Some(text) = text.strip_prefix('-') {
+        let (end_column, text) = number(text)?;
+        let rest = text.strip_prefix(':')?;
+        Some(((line - 1, column - 1), (line - 1, end_column), rest))
+    } else {
+        let rest = text.strip_prefix(':')?;
+        Some(((line - 1, column - 1), (line - 1, column - 1), rest))
+    }
+}
+
+/// Collects the errors and warnings from GHC's output.
+///
+/// Relative paths are resolved against the package that contains them, since cabal builds each
+/// package in its own directory, or against `root` if none does.
+pub fn parse_messages(output: &str, root: &Path, packages: &[Package]) -> Vec<GhcMessage> {
+    let lines = output.lines().collect::<Vec<_>>();
+    let mut messages = vec![];
+    let mut building = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+
+        if let Some(name) = building_package(line) {
+            building = packages.iter().find(|p| p.name == name);
+            continue;
+        }
+
+        let Some(location) = parse_location(line) else { continue };
+        let rest = location.rest.trim_start();
+
+        let (severity, rest) = if let Some(rest) = strip_prefix_ignore_case(rest, "error") {
+            (DiagnosticSeverity::ERROR, rest)
+        } else if let Some(rest) = strip_prefix_ignore_case(rest, "warning") {
+            (DiagnosticSeverity::WARNING, rest)
+        } else {
+            continue;
+        };
+
+        let mut rest = rest.trim_start_matches(':').trim();
+        let mut code = None;
+
+        // Bracketed tags such as "[GHC-88464]" or "[-Wunused-imports]"
+        while let Some(tag) = rest.strip_prefix('[') {
+            let Some(close) = tag.find(']') else { break };
+            if tag[..close].starts_with("GHC-") {
+                code = Some(tag[..close].to_owned());
+            }
+            rest = tag[close + 1..].trim_start();
+        }
+
+        let mut body = vec![];
+        if !rest.is_empty() {
+            body.push(rest.to_owned());
+        }
+
+        // The indented body ends at the first blank or unindented line; source excerpts are skipped
+        while let Some(line) = lines.get(i) {
+            if is_excerpt_line(line) {
+                i += 1;
+            } else if line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+                body.push(dedent(line, 4).to_owned());
+                i += 1;
+            } else {
+                break;
+            }
+        }
+
+        let path = Path::new(location.path);
+        let path = path.strip_prefix("./").unwrap_or(path);
+
+        messages.push(GhcMessage {
+            path: resolve(path, root, packages, building),
+            start: location.start,
+            end: location.end,
+            severity,
+            code,
+            message: body.join("\n"),
+        });
+    }
+
+    messages
+}
+
+/// The package name in cabal's `Building library for foo-0.1.0.0..` or `Preprocessing ...` lines.
+fn building_package(line: &str) -> Option<&str> {
+    if !line.starts_with("Building ") && !line.starts_with("Preprocessing ") {
+        return None;
+    }
+
+    let (_, id) = line.split_once(" for ")?;
+    let id = id.split_whitespace().next()?.trim_end_matches('.');
+
+    match id.rsplit_once('-') {
+        Some((name, version)) if version.starts_with(|c: char| c.is_ascii_digit()) => Some(name),
+        _ => Some(id),
+    }
+}
+
+/// Picks the package being built, then any package, then the root, by where the file exists.
+fn resolve(path: &Path, root: &Path, packages: &[Package], building: Option<&Package>) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_owned();
+    }
+
+    let fallback = building.map_or(root, |p| &p.dir);
+
+    building.into_iter()
+        .chain(packages)
+        .map(|p| p.dir.as_path())
+        .chain([root])
+        .map(|dir| dir.join(path))
+        .find(|candidate| candidate.is_file())
+        .unwrap_or_else(|| fallback.join(path))
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    text.get(..prefix.len())
+        .filter(|start| start.eq_ignore_ascii_case(prefix))
+        .map(|_| &text[prefix.len()..])
+}
+
+/// Lines of the `12 | code` / `   |     ^^^` excerpt GHC prints below a message.
+fn is_excerpt_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with('|')
+        || trimmed.split_once(" |").is_some_and(|(number, _)| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn dedent(line: &str, width: usize) -> &str {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    &line[indent.min(width)..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+[1 of 3] Compiling Bar              ( src/Bar.hs, dist/Bar.o )
+src/Foo.hs:12:5: error: [GHC-88464]
+    Variable not in scope: foo :: Int -> Int
+    Suggested fix: Perhaps use `for' (imported from Prelude)
+   |
+12 |     foo 1
+   |     ^^^
+
+src/Bar.hs:3:1: warning: [GHC-66111] [-Wunused-imports]
+    The import of `Data.List' is redundant
+      except perhaps to import instances from `Data.List'
+    To import instances alone, use: import Data.List()
+  |
+3 | import Data.List
+  | ^^^^^^^^^^^^^^^^
+
+app/Main.hs:(5,1)-(7,20): error: [GHC-83865]
+    • Couldn't match expected type ‘IO ()’ with actual type ‘Int’
+    • In the expression: 42
+Linking dist/main ...
+./src/Baz.hs:8:3-10: Warning: [-Wunused-matches]
+    Defined but not used: ‘x’
+Old.hs:1:7: error: parse error on input ‘=’
+";
+
+    fn messages() -> Vec<GhcMessage> {
+        parse_messages(OUTPUT, Path::new("/project"), &[])
+    }
+
+    #[test]
+    fn finds_every_message() {
+        let paths = messages().into_iter().map(|m| m.path).collect::<Vec<_>>();
+        assert_eq!(paths, [
+            Path::new("/project/src/Foo.hs"),
+            Path::new("/project/src/Bar.hs"),
+            Path::new("/project/app/Main.hs"),
+            Path::new("/project/src/Baz.hs"),
+            Path::new("/project/Old.hs"),
+        ]);
+    }
+
+    #[test]
+    fn collects_multi_line_bodies_without_excerpts() {
+        let messages = messages();
+        assert_eq!(messages[0].message, "Variable not in scope: foo :: Int -> Int\nSuggested fix: Perhaps use `for' (imported from Prelude)");
+        assert_eq!(messages[1].message, "The import of `Data.List' is redundant\n  except perhaps to import instances from `Data.List'\nTo import instances alone, use: import Data.List()");
+        assert_eq!(messages[2].message, "• Couldn't match expected type ‘IO ()’ with actual type ‘Int’\n• In the expression: 42");
+        assert_eq!(messages[4].message, "parse error on input ‘=’");
+    }
+
+    #[test]
+    fn reads_ghc_codes_and_skips_flags() {
+        let codes = messages().into_iter().map(|m| m.code).collect::<Vec<_>>();
+        assert_eq!(codes, [Some("GHC-88464".to_owned()), Some("GHC-66111".to_owned()), Some("GHC-83865".to_owned()), None, None]);
+    }
+
+    #[test]
+    fn tells_warnings_from_errors() {
+        let severities = messages().into_iter().map(|m| m.severity).collect::<Vec<_>>();
+        assert_eq!(severities, [
+            DiagnosticSeverity::ERROR,
+            DiagnosticSeverity::WARNING,
+            DiagnosticSeverity::ERROR,
+            DiagnosticSeverity::WARNING,
+            DiagnosticSeverity::ERROR,
+        ]);
+    }
+
+    #[test]
+    fn converts_spans_to_zero_based_ranges() {
+        let spans = messages().into_iter().map(|m| (m.start, m.end)).collect::<Vec<_>>();
+        assert_eq!(spans, [((11, 4), (11, 4)), ((2, 0), (2, 0)), ((4, 0), (6, 20)), ((7, 2), (7, 10)), ((0, 6), (0, 6))]);
+    }
+
+    #[test]
+    fn parses_locations() {
+        let location = parse_location(r"C:\src\Foo.hs:2:3: error:").unwrap();
+        assert_eq!((location.path, location.start, location.rest), (r"C:\src\Foo.hs", (1, 2), " error:"));
+
+        assert_eq!(parse_location("    src/Foo.hs:2:3: error:"), None);
+        assert_eq!(parse_location("src/Foo.hs:0:3: error:"), None);
+        assert_eq!(parse_location("Linking dist/main ..."), None);
+    }
+
+    #[test]
+    fn resolves_paths_against_the_owning_package() {
+        let root = std::env::temp_dir().join(format!("fexc-ghc-{}", std::process::id()));
+        let package = |name: &str| Package {
+            name: name.to_owned(),
+            version: Some("0.1.0.0".to_owned()),
+            dir: root.join(name),
+            manifest: root.join(name).join(format!("{name}.cabal")),
+            components: vec![],
+        };
+        let packages = [package("core"), package("app")];
+
+        for (dir, file) in [("core", "src/Core.hs"), ("app", "src/Main.hs"), ("core", "src/Shared.hs"), ("app", "src/Shared.hs")] {
+            let path = root.join(dir).join(file);
+            fs_err::create_dir_all(path.parent().unwrap()).unwrap();
+            fs_err::write(path, "").unwrap();
+        }
+
+        let output = "\
+src/Core.hs:1:1: error: one
+Building executable 'app' for app-0.1.0.0..
+src/Main.hs:1:1: error: two
+src/Shared.hs:1:1: error: three
+src/Missing.hs:1:1: error: four
+";
+        let paths = parse_messages(output, &root, &packages).into_iter().map(|m| m.path).collect::<Vec<_>>();
+
+        assert_eq!(paths, [
+            root.join("core/src/Core.hs"),
+            root.join("app/src/Main.hs"),
+            root.join("app/src/Shared.hs"),
+            root.join("app/src/Missing.hs"),
+        ]);
+
+        fs_err::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn reads_package_names_from_cabal_progress() {
+        assert_eq!(building_package("Building library for my-lib-0.1.0.0.."), Some("my-lib"));
+        assert_eq!(building_package("Preprocessing test suite 'spec' for core-1.2.."), Some("core"));
+        assert_eq!(building_package("Building  core"), None);
+        assert_eq!(building_package("src/Foo.hs:1:1: error: for x"), None);
+    }
+}
@@ -0,0 +1,339 @@
+/// A top-level declaration found in a Haskell module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    /// Type signature for functions, the full head for types, classes and instances.
+    pub detail: Option<String>,
+    /// Zero-based line of the first occurrence.
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeclarationKind {
+    Function,
+    Data,
+    TypeSynonym,
+    Class,
+    Instance,
+    TypeFamily,
+}
+
+impl DeclarationKind {
+    pub const ALL: [DeclarationKind; 6] = [
+        DeclarationKind::Function,
+        DeclarationKind::Data,
+        DeclarationKind::TypeSynonym,
+        DeclarationKind::Class,
+        DeclarationKind::Instance,
+        DeclarationKind::TypeFamily,
+    ];
+
+    /// The i18n key of the kind's name.
+    pub fn i18n_key(self) -> &'static str {
+        match self {
+            DeclarationKind::Function => "kind_function",
+            DeclarationKind::Data => "kind_data",
+            DeclarationKind::TypeSynonym => "kind_type",
+            DeclarationKind::Class => "kind_class",
+            DeclarationKind::Instance => "kind_instance",
+            DeclarationKind::TypeFamily => "kind_type_family",
+        }
+    }
+}
+
+/// Scans a module for its top-level declarations.
+///
+/// This is a line-based approximation of the Haskell layout rule: only lines starting
+/// at column 0 begin a declaration, indented lines continue the previous one.
+pub fn declarations(text: &str) -> Vec<Declaration> {
+    let mut result: Vec<Declaration> = vec![];
+    let mut comment_depth = 0usize;
+
+    let lines = text.lines().collect::<Vec<_>>();
+
+    for (i, raw_line) in lines.iter().enumerate() {
+        let line = strip_comments(raw_line, &mut comment_depth);
+
+        if line.is_empty() || line.starts_with(char::is_whitespace) {
+            continue;
+        }
+
+        // Signatures and heads may continue on indented lines, which may have comments of their own
+        let mut continuation_depth = comment_depth;
+        let full = std::iter::once(line.trim_end().to_owned())
+            .chain(
+                lines[i + 1..].iter()
+                    .take_while(|l| l.starts_with(char::is_whitespace) || l.is_empty())
+                    .map(|l| strip_comments(l, &mut continuation_depth).trim().to_owned())
+                    .filter(|l| !l.is_empty())
+            )
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        for decl in parse_declaration(&full, i) {
+            if let Some(existing) = result.iter_mut().find(|d| d.name == decl.name && d.kind == decl.kind) {
+                if existing.detail.is_none() {
+                    existing.detail = decl.detail;
+                }
+            } else {
+                result.push(decl);
+            }
+        }
+    }
+
+    result
+}
+
+fn parse_declaration(line: &str, line_number: usize) -> Vec<Declaration> {
+    let decl = |name: &str, kind, detail: Option<&str>| Declaration {
+        name: name.to_owned(),
+        kind,
+        detail: detail.map(|d| d.trim().to_owned()),
+        line: line_number,
+    };
+
+    let mut words = line.split_whitespace();
+    let Some(first) = words.next() else { return vec![] };
+
+    match first {
+        "module" | "import" | "deriving" | "infix" | "infixl" | "infixr" | "foreign" => vec![],
+        "data" | "newtype" => {
+            let rest = line[first.len()..].trim();
+            if let Some(family) = rest.strip_prefix("family ") {
+                type_name(family).map(|n| vec![decl(&n, DeclarationKind::TypeFamily, Some(line))]).unwrap_or_default()
+            } else if let Some(instance) = rest.strip_prefix("instance ") {
+                vec![decl(head_before_where(instance), DeclarationKind::Instance, Some(line))]
+            } else {
+                type_name(rest).map(|n| vec![decl(&n, DeclarationKind::Data, Some(head_before_eq(line)))]).unwrap_or_default()
+            }
+        }
+        "type" => {
+            let rest = line[first.len()..].trim();
+            if let Some(family) = rest.strip_prefix("family ") {
+                type_name(family).map(|n| vec![decl(&n, DeclarationKind::TypeFamily, Some(head_before_where(line)))]).unwrap_or_default()
+            } else if let Some(instance) = rest.strip_prefix("instance ") {
+                vec![decl(head_before_eq(instance), DeclarationKind::Instance, Some(line))]
+            } else {
+                type_name(rest).map(|n| vec![decl(&n, DeclarationKind::TypeSynonym, Some(line))]).unwrap_or_default()
+            }
+        }
+        "class" => {
+            let head = head_before_where(&line[first.len()..]);
+            type_name(after_context(head))
+                .map(|n| vec![decl(&n, DeclarationKind::Class, Some(head_before_where(line)))])
+                .unwrap_or_default()
+        }
+        "instance" => {
+            let head = head_before_where(&line[first.len()..]);
+            vec![decl(after_context(head).trim(), DeclarationKind::Instance, Some(head_before_where(line)))]
+        }
+        _ => parse_binding(line)
+            .into_iter()
+            .map(|(name, detail)| decl(&name, DeclarationKind::Function, detail.as_deref()))
+            .collect(),
+    }
+}
+
+/// Parses `f, g :: sig` and `f x y = ...` into names with an optional signature.
+fn parse_binding(line: &str) -> Vec<(String, Option<String>)> {
+    if let Some((names, sig)) = split_top_level(line, "::") {
+        return names.split(',')
+            .map(|n| n.trim())
+            .filter(|n| is_var_name(n))
+            .map(|n| (n.trim_matches(|c| c == '(' || c == ')').to_owned(), Some(sig.to_owned())))
+            .collect();
+    }
+
+    let Some((lhs, _)) = split_top_level(line, "=")
+        .or_else(|| split_top_level(line, "|")) else { return vec![] };
+
+    let mut tokens = lhs.split_whitespace();
+    let first = tokens.next().unwrap_or_default();
+
+    // Infix definitions such as "x <+> y = ..." or "a `plus` b = ..."
+    let name = match tokens.next() {
+        Some(op) if is_var_name(&format!("({op})")) => return vec![(op.to_owned(), None)],
+        Some(op) if op.len() > 2 && op.starts_with('`') && op.ends_with('`') => return vec![(op.trim_matches('`').to_owned(), None)],
+        _ => first,
+    };
+
+    if is_var_name(name) {
+        vec![(name.trim_matches(|c| c == '(' || c == ')').to_owned(), None)]
+    } else {
+        vec![]
+    }
+}
+
+fn is_var_name(name: &str) -> bool {
+    if let Some(op) = name.strip_prefix('(').and_then(|n| n.strip_suffix(')')) {
+        return !op.is_empty() && op.chars().all(|c| "!#$%&*+./<=>?@\\^|-~:".contains(c));
+    }
+
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_lowercase() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '\'')
+        && !matches!(name, "let" | "where" | "in" | "do" | "case" | "of" | "if" | "then" | "else" | "_")
+}
+
+/// Splits at the first `sep` that is not part of a longer operator, e.g. `=` but not `==` or `=>`.
+fn split_top_level<'a>(line: &'a str, sep: &str) -> Option<(&'a str, &'a str)> {
+    let is_symbol = |c: char| "!#$%&*+./<=>?@\\^|-~:".contains(c);
+
+    line.match_indices(sep)
+        .find(|(i, _)| {
+            let before = line[..*i].chars().next_back();
+            let after = line[i + sep.len()..].chars().next();
+            !before.is_some_and(is_symbol) && !after.is_some_and(is_symbol)
+        })
+        .map(|(i, _)| (&line[..i], &line[i + sep.len()..]))
+}
+
+fn type_name(rest: &str) -> Option<String> {
+    let name = after_context(rest)
+        .split(|c: char| c.is_whitespace() || c == '=')
+        .find(|w| !w.is_empty())?;
+
+    name.starts_with(|c: char| c.is_uppercase() || c == '(')
+        .then(|| name.to_owned())
+}
+
+fn after_context(head: &str) -> &str {
+    split_top_level(head, "=>").map(|(_, rest)| rest).unwrap_or(head).trim()
+}
+
+fn head_before_where(line: &str) -> &str {
+    line.split(" where").next().unwrap_or(line).trim()
+}
+
+fn head_before_eq(line: &str) -> &str {
+    split_top_level(line, "=").map(|(head, _)| head).unwrap_or(line).trim()
+}
+
+/// Removes line and (possibly nested, multi-line) block comments, tracking the nesting across calls.
+fn strip_comments(line: &str, depth: &mut usize) -> String {
+    let mut result = String::new();
+    let mut chars = line.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if *depth > 0 {
+            match (c, chars.peek()) {
+                ('{', Some('-')) => {
+                    chars.next();
+                    *depth += 1;
+                }
+                ('-', Some('}')) => {
+                    chars.next();
+                    *depth -= 1;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = !in_string;
+                result.push(c);
+            }
+            ('{', Some('-')) if !in_string => {
+                chars.next();
+                *depth += 1;
+            }
+            ('-', Some('-')) if !in_string && !result.ends_with(|c: char| "!#$%&*+./<=>?@\\^|~:".contains(c)) => break,
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(text: &str) -> Vec<(String, DeclarationKind, Option<String>, usize)> {
+        declarations(text).into_iter()
+            .map(|d| (d.name, d.kind, d.detail, d.line))
+            .collect()
+    }
+
+    fn decl(name: &str, kind: DeclarationKind, detail: &str, line: usize) -> (String, DeclarationKind, Option<String>, usize) {
+        (name.to_owned(), kind, Some(detail.to_owned()), line)
+    }
+
+    #[test]
+    fn finds_types_classes_and_instances() {
+        let text = "\
+module Shapes (Shape (..)) where
+
+import Data.List (sortOn)
+
+data Shape = Circle Double | Square Double
+  deriving (Show, Eq)
+
+newtype Area = Area Double
+
+type Name = String
+
+class Show a => Pretty a where
+  pretty :: a -> String
+
+instance Pretty Shape where
+  pretty = show
+";
+
+        assert_eq!(outline(text), [
+            decl("Shape", DeclarationKind::Data, "data Shape", 4),
+            decl("Area", DeclarationKind::Data, "newtype Area", 7),
+            decl("Name", DeclarationKind::TypeSynonym, "type Name = String", 9),
+            decl("Pretty", DeclarationKind::Class, "class Show a => Pretty a", 11),
+            decl("Pretty Shape", DeclarationKind::Instance, "instance Pretty Shape", 14),
+        ]);
+    }
+
+    #[test]
+    fn joins_multi_line_signatures_with_their_definition() {
+        let text = "\
+area
+  :: Shape
+  -> Double
+area (Circle r) = pi * r * r
+area (Square a) = a * a
+";
+
+        assert_eq!(outline(text), [decl("area", DeclarationKind::Function, "Shape -> Double", 0)]);
+    }
+
+    #[test]
+    fn finds_operators_in_parentheses() {
+        let text = "\
+(<+>) :: Shape -> Shape -> Double
+a <+> b = area a + area b
+
+infixl 6 <+>
+";
+
+        assert_eq!(outline(text), [decl("<+>", DeclarationKind::Function, "Shape -> Shape -> Double", 0)]);
+    }
+
+    #[test]
+    fn comments_stay_out_of_signatures() {
+        let text = "\
+-- | Scales a shape.
+scale
+  :: Double -- ^ the factor
+  {- a block
+     comment -}
+  -> Shape -- ^ the shape
+  -> Shape
+scale k (Circle r) = Circle (k * r) -- not a declaration
+{-
+hidden :: Int
+-}
+";
+
+        assert_eq!(outline(text), [decl("scale", DeclarationKind::Function, "Double -> Shape -> Shape", 1)]);
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use crate::app::Fexc;
+
+/// Inlay hints and code lenses of the active document, as last reported by the language server.
+#[derive(Default)]
+pub struct Hints {
+    pub inlay: Vec<lsp_types::InlayHint>,
+    pub lenses: Vec<lsp_types::CodeLens>,
+    /// Indices of the lenses `codeLens/resolve` was sent for.
+    resolving: HashSet<usize>,
+    /// Document id and version the hints belong to.
+    pub source: Option<(u64, i32)>,
+    requested: Option<(u64, i32)>,
+    seen: Option<(u64, i32, Instant)>,
+}
+
+impl Hints {
+    /// Time the document has to stay unchanged before hints are requested again.
+    pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+    pub fn set_inlay(&mut self, doc: u64, version: i32, hints: Vec<lsp_types::InlayHint>) {
+        self.reset_if_outdated(doc, version);
+        self.inlay = hints;
+    }
+
+    pub fn set_lenses(&mut self, doc: u64, version: i32, lenses: Vec<lsp_types::CodeLens>) {
+        self.reset_if_outdated(doc, version);
+        self.lenses = lenses;
+        self.resolving.clear();
+    }
+
+    /// Replaces a lens with its resolved version, unless the lenses were replaced in the meantime.
+    pub fn set_resolved_lens(&mut self, doc: u64, version: i32, index: usize, lens: lsp_types::CodeLens) {
+        if self.source != Some((doc, version)) {
+            return;
+        }
+
+        if let Some(old) = self.lenses.get_mut(index)
+            && old.range == lens.range
+        {
+            *old = lens;
+        }
+    }
+
+    fn reset_if_outdated(&mut self, doc: u64, version: i32) {
+        if self.source != Some((doc, version)) {
+            self.source = Some((doc, version));
+            self.inlay.clear();
+            self.lenses.clear();
+            self.resolving.clear();
+        }
+    }
+}
+
+impl Fexc {
+    /// Requests hints for the active document once it has not been edited for a moment.
+    pub fn update_hints(&mut self, ctx: &egui::Context) {
+        if !self.config.inlay_type_hints && !self.config.inlay_other_hints && !self.config.code_lenses {
+            return;
+        }
+
+        let doc = self.documents.active();
+        let key = (doc.id, doc.version);
+
+        if self.hints.requested == Some(key) {
+            return;
+        }
+
+        let lsp = self.languages.server_for(doc.path.as_deref(), &mut self.lsp, &mut self.language_servers);
+        let (Some(lsp), Some(path)) = (lsp, &doc.path) else { return };
+
+        match self.hints.seen {
+            Some((id, version, since)) if (id, version) == key => {
+                if since.elapsed() < Hints::DEBOUNCE {
+                    ctx.request_repaint_after(Hints::DEBOUNCE - since.elapsed());
+                    return;
+                }
+            }
+            _ => {
+                self.hints.seen = Some((key.0, key.1, Instant::now()));
+                ctx.request_repaint_after(Hints::DEBOUNCE);
+                return;
+            }
+        }
+
+        self.hints.requested = Some(key);
+
+        let whole_document = lsp_types::Range::new(
+            lsp_types::Position::new(0, 0),
+            lsp_types::Position::new(doc.text.lines().count() as u32 + 1, 0),
+        );
+
+        let result = lsp.inlay_hints(path, doc.id, doc.version, whole_document)
+            .and_then(|_| lsp.code_lens(path, doc.id, doc.version));
+
+        if let Err(e) = result {
+            log::error!("{e}");
+        }
+    }
+
+    /// Asks the language server to fill in the commands of the given lenses, each only once.
+    pub fn resolve_code_lenses(&mut self, indices: Vec<usize>) {
+        let Some((doc, version)) = self.hints.source else { return };
+        let path = self.documents.active().path.as_deref();
+        let Some(lsp) = self.languages.server_for(path, &mut self.lsp, &mut self.language_servers) else { return };
+
+        for index in indices {
+            let Some(lens) = self.hints.lenses.get(index) else { continue };
+            if !self.hints.resolving.insert(index) {
+                continue;
+            }
+
+            if let Err(e) = lsp.resolve_code_lens(lens.clone(), doc, version, index) {
+                log::error!("{e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lens(line: u32, title: Option<&str>) -> lsp_types::CodeLens {
+        lsp_types::CodeLens {
+            range: lsp_types::Range::new(lsp_types::Position::new(line, 0), lsp_types::Position::new(line, 4)),
+            command: title.map(|title| lsp_types::Command::new(title.to_owned(), "eval".to_owned(), None)),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn resolved_lenses_replace_their_placeholder() {
+        let mut hints = Hints::default();
+        hints.set_lenses(1, 2, vec![lens(0, None), lens(3, None)]);
+
+        hints.set_resolved_lens(1, 2, 1, lens(3, Some("Evaluate...")));
+        assert_eq!(hints.lenses[1].command.as_ref().map(|c| c.title.as_str()), Some("Evaluate..."));
+        assert!(hints.lenses[0].command.is_none());
+    }
+
+    #[test]
+    fn outdated_resolved_lenses_are_dropped() {
+        let mut hints = Hints::default();
+        hints.set_lenses(1, 2, vec![lens(0, None)]);
+
+        hints.set_resolved_lens(1, 1, 0, lens(0, Some("old version")));
+        hints.set_resolved_lens(1, 2, 0, lens(5, Some("other lens")));
+        hints.set_resolved_lens(1, 2, 7, lens(0, Some("missing")));
+        assert!(hints.lenses[0].command.is_none());
+    }
+}
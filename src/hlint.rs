@@ -0,0 +1,52 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::process;
+
+/// A single hint from `hlint --json`. Lines and columns are one-based, the end column is exclusive.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HlintSuggestion {
+    pub hint: String,
+    pub severity: String,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub to: Option<String>,
+}
+
+pub type HlintResult = Result<Vec<HlintSuggestion>, HlintError>;
+
+/// Lints `text` on a background thread.
+pub fn run(text: String, cwd: Option<PathBuf>, ctx: egui::Context) -> Receiver<HlintResult> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        // hlint exits with a failure status whenever it finds hints, so only the output matters
+        let result = process::pipe("hlint", &["--json", "-"], text, cwd.as_deref())
+            .map_err(HlintError::Spawn)
+            .and_then(|output| serde_json::from_slice(&output.stdout).map_err(HlintError::Json));
+
+        if sender.send(result).is_ok() {
+            ctx.request_repaint();
+        }
+    });
+
+    receiver
+}
+
+#[derive(Debug, Error)]
+pub enum HlintError {
+    #[error("Failed to run hlint: {0}")]
+    Spawn(io::Error),
+    #[error("Failed to parse hlint output: {0}")]
+    Json(serde_json::Error),
+}
@@ -0,0 +1,532 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::Arc,
+};
+
+use egui_extras::syntax_highlighting::SyntectSettings;
+use fs_err as fs;
+use serde::Deserialize;
+use syntect::{highlighting::ThemeSet, parsing::{SyntaxDefinition, SyntaxSet}};
+
+use crate::{
+    app::Fexc,
+    document::map_char_index,
+    lsp::{LspClient, LspEvent},
+};
+
+/// How the editor treats the files of one language, as declared under `[[languages]]` in a plugin manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LanguageDef {
+    pub name: String,
+    /// File extensions without the dot.
+    pub extensions: Vec<String>,
+    /// A `.sublime-syntax` file, relative to the plugin directory.
+    #[serde(default)]
+    pub grammar: Option<String>,
+    #[serde(default)]
+    pub line_comment: Option<String>,
+    #[serde(default)]
+    pub block_comment: Option<(String, String)>,
+    /// Pairs the editor closes automatically, e.g. `["(", ")"]`.
+    #[serde(default)]
+    pub brackets: Vec<(char, char)>,
+    #[serde(default = "default_indent_width")]
+    pub indent_width: usize,
+    /// Whether Enter keeps the indentation of the line, going deeper after an `indent_after` token.
+    #[serde(default)]
+    pub auto_indent: bool,
+    /// Line endings after which the next line is indented one level deeper, e.g. `where` or `=`.
+    #[serde(default)]
+    pub indent_after: Vec<String>,
+    /// Whether Tab inserts spaces up to the next indentation level.
+    #[serde(default)]
+    pub indent_with_spaces: bool,
+    /// The language server to start for the project when a file of the language is open.
+    #[serde(default)]
+    pub lsp_command: Vec<String>,
+}
+
+fn default_indent_width() -> usize {
+    2
+}
+
+/// The result of an editing key: the new text and selection as char indices.
+pub struct Edit {
+    pub text: String,
+    pub cursor: usize,
+    pub anchor: usize,
+}
+
+impl LanguageDef {
+    /// The built-in Haskell support, which only adds comment toggling to the plain text edit.
+    fn haskell() -> Self {
+        LanguageDef {
+            name: "Haskell".to_owned(),
+            extensions: vec!["hs".to_owned(), "lhs".to_owned()],
+            line_comment: Some("--".to_owned()),
+            block_comment: Some(("{-".to_owned(), "-}".to_owned())),
+            ..LanguageDef::plain_text()
+        }
+    }
+
+    /// Files of no known language, edited without any help.
+    fn plain_text() -> Self {
+        LanguageDef {
+            name: "Plain Text".to_owned(),
+            extensions: vec![],
+            grammar: None,
+            line_comment: None,
+            block_comment: None,
+            brackets: vec![],
+            indent_width: default_indent_width(),
+            auto_indent: false,
+            indent_after: vec![],
+            indent_with_spaces: false,
+            lsp_command: vec![],
+        }
+    }
+
+    /// Breaks the line, keeping the indentation and going one level deeper after an `indent_after` token.
+    /// `None` if the language does not indent automatically.
+    pub fn newline(&self, text: &str, start: usize, end: usize) -> Option<Edit> {
+        if !self.auto_indent {
+            return None;
+        }
+
+        let chars = text.chars().collect::<Vec<_>>();
+        let line_start = line_start(&chars, start);
+        let before = chars[line_start..start].iter().collect::<String>();
+
+        let mut indent = before.chars().take_while(|c| *c == ' ' || *c == '\t').collect::<String>();
+        if self.indent_after.iter().any(|token| ends_with_token(before.trim_end(), token)) {
+            indent.push_str(&" ".repeat(self.indent_width));
+        }
+
+        Some(replace(&chars, start, end, &format!("\n{indent}")))
+    }
+
+    /// Inserts spaces up to the next indentation level instead of a tab, if the language indents with spaces.
+    pub fn indent(&self, text: &str, cursor: usize) -> Option<Edit> {
+        if !self.indent_with_spaces {
+            return None;
+        }
+
+        let chars = text.chars().collect::<Vec<_>>();
+        let column = cursor - line_start(&chars, cursor);
+        let width = self.indent_width.max(1);
+
+        Some(replace(&chars, cursor, cursor, &" ".repeat(width - column % width)))
+    }
+
+    /// Closes brackets as they are opened, wraps the selection in them and steps over typed closing ones.
+    pub fn type_char(&self, text: &str, start: usize, end: usize, c: char) -> Option<Edit> {
+        let chars = text.chars().collect::<Vec<_>>();
+        let next = chars.get(end).copied();
+
+        if start == end && next == Some(c) && self.brackets.iter().any(|(_, close)| *close == c) {
+            return Some(Edit { text: text.to_owned(), cursor: end + 1, anchor: end + 1 });
+        }
+
+        let (_, close) = self.brackets.iter().find(|(open, _)| *open == c)?;
+
+        if start != end {
+            let selected = chars[start..end].iter().collect::<String>();
+            let mut edit = replace(&chars, start, end, &format!("{c}{selected}{close}"));
+            edit.anchor = start + 1;
+            edit.cursor = end + 1;
+            return Some(edit);
+        }
+
+        let closes = next.is_none_or(|n| n.is_whitespace() || self.brackets.iter().any(|(_, close)| *close == n));
+        if !closes {
+            return None;
+        }
+
+        let mut edit = replace(&chars, start, end, &format!("{c}{close}"));
+        edit.cursor = start + 1;
+        edit.anchor = start + 1;
+        Some(edit)
+    }
+
+    /// Comments out the selected lines, or uncomments them if they all are comments.
+    pub fn toggle_comment(&self, text: &str, cursor: usize, anchor: usize) -> Option<Edit> {
+        let (open, close) = match (&self.line_comment, &self.block_comment) {
+            (Some(line), _) => (line.as_str(), ""),
+            (None, Some((open, close))) => (open.as_str(), close.as_str()),
+            (None, None) => return None,
+        };
+
+        let first = text.chars().take(cursor.min(anchor)).filter(|c| *c == '\n').count();
+        let mut last = text.chars().take(cursor.max(anchor)).filter(|c| *c == '\n').count();
+        // A selection ending at the start of a line does not include that line
+        if cursor != anchor && text.chars().nth(cursor.max(anchor) - 1) == Some('\n') {
+            last -= 1;
+        }
+
+        let mut lines = text.split('\n').map(str::to_owned).collect::<Vec<_>>();
+        let selected = first..=last.min(lines.len() - 1);
+
+        let commented = |line: &str| {
+            let line = line.trim();
+            line.len() >= open.len() + close.len() && line.starts_with(open) && line.ends_with(close)
+        };
+        let code_lines = lines[selected.clone()].iter().filter(|l| !l.trim().is_empty());
+        let uncomment = code_lines.clone().all(|l| commented(l));
+        let column = code_lines.map(|l| l.len() - l.trim_start().len()).min()?;
+
+        for line in &mut lines[selected] {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if uncomment {
+                let trimmed = line.trim_end();
+                let indent = trimmed.len() - trimmed.trim_start().len();
+                let body = &trimmed[indent + open.len()..trimmed.len() - close.len()];
+                let body = body.strip_prefix(' ').unwrap_or(body);
+                let body = if close.is_empty() { body } else { body.strip_suffix(' ').unwrap_or(body) };
+                *line = format!("{}{body}", &trimmed[..indent]);
+            } else if close.is_empty() {
+                line.insert_str(column, &format!("{open} "));
+            } else {
+                *line = format!("{}{open} {} {close}", &line[..column], line[column..].trim_end());
+            }
+        }
+
+        let new_text = lines.join("\n");
+        Some(Edit {
+            cursor: map_char_index(text, &new_text, cursor),
+            anchor: map_char_index(text, &new_text, anchor),
+            text: new_text,
+        })
+    }
+}
+
+fn line_start(chars: &[char], index: usize) -> usize {
+    chars[..index].iter().rposition(|c| *c == '\n').map_or(0, |i| i + 1)
+}
+
+/// Whether `text` ends with `token` as a whole word or operator, so `=` does not match `==`.
+fn ends_with_token(text: &str, token: &str) -> bool {
+    let Some(rest) = text.strip_suffix(token) else { return false };
+    let Some(first) = token.chars().next() else { return false };
+
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+    let is_operator = |c: char| c.is_ascii_punctuation() && !"()[]{},;`\"'_".contains(c);
+
+    !rest.ends_with(|c: char| if is_word(first) { is_word(c) } else { is_operator(c) })
+}
+
+fn replace(chars: &[char], start: usize, end: usize, insert: &str) -> Edit {
+    let text = chars[..start].iter().copied()
+        .chain(insert.chars())
+        .chain(chars[end..].iter().copied())
+        .collect();
+    let cursor = start + insert.chars().count();
+
+    Edit { text, cursor, anchor: cursor }
+}
+
+pub struct Language {
+    pub def: LanguageDef,
+    /// The plugin that contributed the language, `None` for the built-in Haskell support.
+    pub plugin: Option<String>,
+    /// The syntect syntax the editor highlights with, by name or extension.
+    pub syntax: String,
+    grammar: Option<SyntaxDefinition>,
+}
+
+/// The languages the editor knows: Haskell, plain text and those contributed by plugins.
+pub struct Languages {
+    haskell: Language,
+    plain_text: Language,
+    contributed: Vec<Language>,
+    /// Syntect's default syntaxes plus the plugin grammars, or `None` if there are no plugin grammars.
+    syntect: Option<Arc<SyntectSettings>>,
+}
+
+impl Default for Languages {
+    fn default() -> Self {
+        Languages {
+            haskell: Language { def: LanguageDef::haskell(), plugin: None, syntax: "hs".to_owned(), grammar: None },
+            plain_text: Language { def: LanguageDef::plain_text(), plugin: None, syntax: "txt".to_owned(), grammar: None },
+            contributed: vec![],
+            syntect: None,
+        }
+    }
+}
+
+impl Languages {
+    /// The language of a file, by extension. Untitled documents are Haskell scratch buffers,
+    /// files of unknown languages plain text.
+    pub fn for_path(&self, path: Option<&Path>) -> &Language {
+        let Some(path) = path else { return &self.haskell };
+        let extension = path.extension().map(|e| e.to_string_lossy()).unwrap_or_default();
+
+        self.contributed.iter().chain([&self.haskell])
+            .find(|l| l.def.extensions.iter().any(|e| *e == extension))
+            .unwrap_or(&self.plain_text)
+    }
+
+    pub fn is_haskell(&self, path: Option<&Path>) -> bool {
+        std::ptr::eq(self.for_path(path), &self.haskell)
+    }
+
+    /// The language server of a file: the Haskell server for Haskell files, the server of the
+    /// plugin language for the others, and none for plain text.
+    pub fn server_for<'a>(
+        &self,
+        path: Option<&Path>,
+        haskell: &'a mut Option<LspClient>,
+        servers: &'a mut HashMap<String, Option<LspClient>>,
+    ) -> Option<&'a mut LspClient> {
+        let language = self.for_path(path);
+
+        match &language.plugin {
+            Some(_) => servers.get_mut(&language.def.name)?.as_mut(),
+            None if std::ptr::eq(language, &self.haskell) => haskell.as_mut(),
+            None => None,
+        }
+    }
+
+    pub fn contributed(&self) -> impl Iterator<Item = &Language> {
+        self.contributed.iter()
+    }
+
+    pub fn syntect(&self) -> Option<&Arc<SyntectSettings>> {
+        self.syntect.as_ref()
+    }
+
+    /// Adds the languages of a plugin installed in `dir`, returning the grammars that failed to load.
+    pub fn add_plugin(&mut self, plugin: &str, dir: &Path, defs: &[LanguageDef]) -> Vec<String> {
+        let mut errors = vec![];
+
+        for def in defs {
+            let grammar = def.grammar.as_ref().and_then(|file| {
+                let grammar = fs::read_to_string(dir.join(file))
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| SyntaxDefinition::load_from_str(&text, true, None).map_err(|e| e.to_string()));
+
+                grammar.inspect_err(|e| errors.push(format!("{}: {e}", def.name))).ok()
+            });
+
+            let syntax = match &grammar {
+                Some(grammar) => grammar.name.clone(),
+                None => def.extensions.first().cloned().unwrap_or_default(),
+            };
+
+            self.contributed.push(Language { def: def.clone(), plugin: Some(plugin.to_owned()), syntax, grammar });
+        }
+
+        self.rebuild_syntaxes();
+        errors
+    }
+
+    pub fn remove_plugin(&mut self, plugin: &str) {
+        let count = self.contributed.len();
+        self.contributed.retain(|l| l.plugin.as_deref() != Some(plugin));
+
+        if self.contributed.len() != count {
+            self.rebuild_syntaxes();
+        }
+    }
+
+    fn rebuild_syntaxes(&mut self) {
+        let grammars = self.contributed.iter().filter_map(|l| l.grammar.clone()).collect::<Vec<_>>();
+        if grammars.is_empty() {
+            self.syntect = None;
+            return;
+        }
+
+        let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+        for grammar in grammars {
+            builder.add(grammar);
+        }
+
+        self.syntect = Some(Arc::new(SyntectSettings {
+            ps: builder.build(),
+            ts: ThemeSet::load_defaults(),
+        }));
+    }
+}
+
+impl Fexc {
+    /// Comments out the selected lines of the active document, or uncomments them if they all are.
+    pub fn toggle_comment_active(&mut self) {
+        let doc = self.documents.active_mut();
+        let language = self.languages.for_path(doc.path.as_deref());
+
+        if let Some(edit) = language.def.toggle_comment(&doc.text, doc.cursor, doc.anchor) {
+            doc.apply_edit(edit);
+        }
+    }
+
+    /// Starts the language servers of plugin languages that have open files and handles their messages.
+    pub fn update_language_servers(&mut self, ctx: &egui::Context) {
+        let Some(root) = self.current_project.clone() else { return };
+        let languages = &self.languages;
+
+        self.language_servers.retain(|name, _| languages.contributed().any(|l| l.def.name == *name));
+
+        for language in languages.contributed().filter(|l| !l.def.lsp_command.is_empty()) {
+            let name = &language.def.name;
+            let open = self.documents.iter().any(|d| languages.for_path(d.path.as_deref()).def.name == *name);
+            if !open || self.language_servers.contains_key(name) {
+                continue;
+            }
+
+            let server = LspClient::start(&language.def.lsp_command, &root, &name.to_lowercase(), ctx.clone())
+                .inspect_err(|e| self.notifications.error(format!("{name}: {e}")))
+                .ok();
+            self.language_servers.insert(name.clone(), server);
+        }
+
+        let mut events = vec![];
+        for (name, server) in &mut self.language_servers {
+            let Some(server) = server else { continue };
+
+            let docs = self.documents.iter().filter(|d| languages.for_path(d.path.as_deref()).def.name == *name);
+            server.sync_documents(docs).unwrap_or_else(|e| {
+                log::error!("{e}");
+            });
+
+            events.extend(server.poll().into_iter().map(|event| match event {
+                LspEvent::Error(e) => LspEvent::Error(format!("{name}: {e}")),
+                event => event,
+            }));
+        }
+
+        for event in events {
+            self.handle_lsp_event(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A plugin language with every editing helper turned on.
+    fn elm() -> LanguageDef {
+        LanguageDef {
+            name: "Elm".to_owned(),
+            extensions: vec!["elm".to_owned()],
+            line_comment: Some("--".to_owned()),
+            brackets: vec![('(', ')'), ('[', ']'), ('"', '"')],
+            indent_width: 4,
+            auto_indent: true,
+            indent_after: vec!["=".to_owned(), "of".to_owned()],
+            indent_with_spaces: true,
+            ..LanguageDef::plain_text()
+        }
+    }
+
+    #[test]
+    fn unknown_files_are_plain_text() {
+        let mut languages = Languages::default();
+        languages.add_plugin("elm", Path::new("/plugins/elm"), &[elm()]);
+
+        let name = |path: Option<&str>| languages.for_path(path.map(Path::new)).def.name.clone();
+        assert_eq!(name(Some("/p/src/Main.hs")), "Haskell");
+        assert_eq!(name(Some("/p/Setup.lhs")), "Haskell");
+        assert_eq!(name(Some("/p/src/Main.elm")), "Elm");
+        assert_eq!(name(Some("/p/README.md")), "Plain Text");
+        assert_eq!(name(Some("/p/Makefile")), "Plain Text");
+        assert_eq!(name(None), "Haskell");
+    }
+
+    #[test]
+    fn files_go_to_the_server_of_their_language() {
+        let mut languages = Languages::default();
+        languages.add_plugin("elm", Path::new("/plugins/elm"), &[elm()]);
+
+        let (mut haskell, mut servers) = (None, HashMap::from([("Elm".to_owned(), None)]));
+        for path in ["/p/Main.hs", "/p/Main.elm", "/p/notes.txt"] {
+            assert!(languages.server_for(Some(Path::new(path)), &mut haskell, &mut servers).is_none());
+        }
+        assert!(languages.is_haskell(Some(Path::new("/p/Main.hs"))));
+        assert!(!languages.is_haskell(Some(Path::new("/p/Main.elm"))));
+        assert!(!languages.is_haskell(Some(Path::new("/p/notes.txt"))));
+    }
+
+    #[test]
+    fn haskell_editing_is_left_to_the_text_edit() {
+        let haskell = LanguageDef::haskell();
+
+        assert!(haskell.newline("main = do", 9, 9).is_none());
+        assert!(haskell.indent("main", 4).is_none());
+        assert!(haskell.type_char("f ", 2, 2, '(').is_none());
+        assert!(haskell.toggle_comment("main = pure ()", 0, 0).is_some());
+    }
+
+    #[test]
+    fn newline_keeps_and_deepens_indentation() {
+        let elm = elm();
+
+        let edit = elm.newline("    x = 1", 9, 9).unwrap();
+        assert_eq!((edit.text.as_str(), edit.cursor), ("    x = 1\n    ", 14));
+
+        let edit = elm.newline("  x =", 5, 5).unwrap();
+        assert_eq!((edit.text.as_str(), edit.cursor), ("  x =\n      ", 12));
+
+        let edit = elm.newline("  case x of", 11, 11).unwrap();
+        assert_eq!(edit.text, "  case x of\n      ");
+
+        // `==` is not `=`, and the selection is replaced
+        let edit = elm.newline("  a == b", 6, 8).unwrap();
+        assert_eq!((edit.text.as_str(), edit.cursor), ("  a ==\n  ", 9));
+    }
+
+    #[test]
+    fn tab_indents_to_the_next_level() {
+        let edit = elm().indent("ab", 2).unwrap();
+
+        assert_eq!((edit.text.as_str(), edit.cursor), ("ab  ", 4));
+    }
+
+    #[test]
+    fn brackets_are_closed_wrapped_and_stepped_over() {
+        let elm = elm();
+
+        let edit = elm.type_char("f ", 2, 2, '(').unwrap();
+        assert_eq!((edit.text.as_str(), edit.cursor), ("f ()", 3));
+
+        let edit = elm.type_char("f x", 2, 3, '[').unwrap();
+        assert_eq!((edit.text.as_str(), edit.anchor, edit.cursor), ("f [x]", 3, 4));
+
+        let edit = elm.type_char("f ()", 3, 3, ')').unwrap();
+        assert_eq!((edit.text.as_str(), edit.cursor), ("f ()", 4));
+
+        // Nothing is closed right before a word
+        assert!(elm.type_char("f x", 2, 2, '(').is_none());
+        assert!(elm.type_char("f ", 2, 2, 'x').is_none());
+    }
+
+    #[test]
+    fn comments_are_toggled_on_the_selected_lines() {
+        let elm = elm();
+        let text = "a =\n    b\n\n    c";
+
+        let edit = elm.toggle_comment(text, 0, text.chars().count()).unwrap();
+        assert_eq!(edit.text, "-- a =\n--     b\n\n--     c");
+
+        let edit = elm.toggle_comment(&edit.text, 0, edit.text.chars().count()).unwrap();
+        assert_eq!(edit.text, text);
+
+        // A selection ending at the start of a line leaves that line alone
+        let edit = elm.toggle_comment(text, 4, 10).unwrap();
+        assert_eq!(edit.text, "a =\n    -- b\n\n    c");
+    }
+
+    #[test]
+    fn block_comments_wrap_each_line() {
+        let haskell = LanguageDef { line_comment: None, ..LanguageDef::haskell() };
+
+        let edit = haskell.toggle_comment("  main = pure ()", 5, 5).unwrap();
+        assert_eq!((edit.text.as_str(), edit.cursor), ("  {- main = pure () -}", 8));
+
+        let edit = haskell.toggle_comment(&edit.text, 0, 0).unwrap();
+        assert_eq!(edit.text, "  main = pure ()");
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// A `path:line[:col]` file location found in a line of text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// Char range of the location in the line.
+    pub range: Range<usize>,
+    pub path: String,
+    /// Zero-based line and column.
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Finds file locations such as `src/Foo.hs:12:5`, `grep -n` matches like `app/Main.hs:3:main = ...`
+/// or GHC spans like `Foo.hs:(3,1)-(4,10)`.
+pub fn find(text: &str) -> Vec<Link> {
+    let is_delimiter = |c: char| c.is_whitespace() || "\"'`<>[]{}|".contains(c);
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut links = vec![];
+    let mut start = 0;
+
+    while start < chars.len() {
+        if is_delimiter(chars[start]) {
+            start += 1;
+            continue;
+        }
+
+        let end = chars[start..].iter()
+            .position(|&c| is_delimiter(c))
+            .map_or(chars.len(), |len| start + len);
+
+        if let Some(link) = parse_token(&chars[start..end], start) {
+            links.push(link);
+        }
+
+        start = end;
+    }
+
+    links
+}
+
+fn parse_token(token: &[char], offset: usize) -> Option<Link> {
+    let leading = token.iter().take_while(|&&c| c == '(').count();
+    let token = &token[leading..];
+
+    // The path ends at the first colon after something file-like, e.g. not at the drive in `C:\`
+    let (i, path) = token.iter().enumerate()
+        .filter(|&(i, &c)| c == ':' && i > 0)
+        .map(|(i, _)| (i, token[..i].iter().collect::<String>()))
+        .find(|(_, path)| is_plausible_path(path))?;
+
+    // `grep -n` output continues with the matched text right after `path:line:`
+    let (line, column, len) = parse_position(&token[i + 1..])?;
+    let start = offset + leading;
+
+    Some(Link {
+        range: start..start + i + 1 + len,
+        path,
+        line: line - 1,
+        column: column.saturating_sub(1),
+    })
+}
+
+/// Parses `L`, `L:C`, `L:C1-C2` or `(L1,C1)-(L2,C2)`, returning the line, column and length of the position.
+fn parse_position(text: &[char]) -> Option<(usize, usize, usize)> {
+    fn number(text: &[char], at: usize) -> Option<(usize, usize)> {
+        let digits = text.get(at..)?.iter().take_while(|c| c.is_ascii_digit()).count();
+        let value = text[at..at + digits].iter().collect::<String>().parse().ok().filter(|&v| v > 0)?;
+        Some((value, at + digits))
+    }
+
+    let expect = |at: usize, c: char| (text.get(at) == Some(&c)).then_some(at + 1);
+
+    if text.first() == Some(&'(') {
+        let (line, at) = number(text, 1)?;
+        let (column, at) = number(text, expect(at, ',')?)?;
+        let at = expect(at, ')')?;
+        let end = (|| {
+            let (_, at) = number(text, expect(expect(at, '-')?, '(')?)?;
+            let (_, at) = number(text, expect(at, ',')?)?;
+            expect(at, ')')
+        })();
+        return Some((line, column, end.unwrap_or(at)));
+    }
+
+    let (line, at) = number(text, 0)?;
+
+    match expect(at, ':').and_then(|at| number(text, at)) {
+        Some((column, at)) => {
+            let end = expect(at, '-').and_then(|at| number(text, at)).map_or(at, |(_, end)| end);
+            Some((line, column, end))
+        }
+        None => Some((line, 1, at)),
+    }
+}
+
+/// Rules out times, URLs, host names with ports and version numbers.
+fn is_plausible_path(path: &str) -> bool {
+    let has_extension = Path::new(path).extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.starts_with(|c: char| c.is_ascii_alphabetic()));
+
+    !path.contains("://") && (has_extension || path.contains('/') && !path.ends_with('/'))
+}
+
+/// Resolves a linked path against the first base directory it exists in, or the first base.
+pub fn resolve(path: &str, bases: &[&Path]) -> PathBuf {
+    let path = Path::new(path);
+
+    if path.is_absolute() {
+        return path.to_owned();
+    }
+
+    bases.iter()
+        .map(|base| base.join(path))
+        .find(|candidate| candidate.is_file())
+        .or_else(|| bases.first().map(|base| base.join(path)))
+        .unwrap_or_else(|| path.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(text: &str) -> Link {
+        let links = find(text);
+        assert_eq!(links.len(), 1, "{text:?} gave {links:?}");
+        links.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn path_line_column() {
+        let text = "see src/Foo.hs:12:5 for details";
+        let found = link(text);
+
+        assert_eq!(found.path, "src/Foo.hs");
+        assert_eq!((found.line, found.column), (11, 4));
+        assert_eq!(text.chars().skip(found.range.start).take(found.range.len()).collect::<String>(), "src/Foo.hs:12:5");
+    }
+
+    #[test]
+    fn path_line_only() {
+        let found = link("Main.hs:3");
+
+        assert_eq!(found.path, "Main.hs");
+        assert_eq!((found.line, found.column), (2, 0));
+        assert_eq!(found.range, 0..9);
+    }
+
+    #[test]
+    fn column_range() {
+        let found = link("./src/Baz.hs:8:3-10: Warning: [-Wunused-matches]");
+
+        assert_eq!(found.path, "./src/Baz.hs");
+        assert_eq!((found.line, found.column), (7, 2));
+        assert_eq!(found.range, 0..19);
+    }
+
+    #[test]
+    fn ghc_span() {
+        let found = link("app/Main.hs:(5,1)-(7,20): error: [GHC-83865]");
+
+        assert_eq!(found.path, "app/Main.hs");
+        assert_eq!((found.line, found.column), (4, 0));
+        assert_eq!(found.range, 0..24);
+    }
+
+    #[test]
+    fn grep_output() {
+        let found = link("app/Main.hs:3:main = putStrLn \"hi\"");
+
+        assert_eq!(found.path, "app/Main.hs");
+        assert_eq!((found.line, found.column), (2, 0));
+        assert_eq!(found.range, 0..13);
+    }
+
+    #[test]
+    fn several_links_with_delimiters() {
+        let links = find("\"src/A.hs:1:2\" and (src/B.hs:3:4)");
+
+        assert_eq!(links.iter().map(|l| l.path.as_str()).collect::<Vec<_>>(), ["src/A.hs", "src/B.hs"]);
+        assert_eq!(links[0].range, 1..13);
+        assert_eq!(links[1].range, 20..32);
+    }
+
+    #[test]
+    fn non_ascii_text_uses_char_ranges() {
+        let found = link("помилка: src/Модуль.hs:2:1");
+
+        assert_eq!(found.path, "src/Модуль.hs");
+        assert_eq!(found.range, 9..26);
+    }
+
+    #[test]
+    fn windows_drive_is_part_of_the_path() {
+        let found = link(r"C:\project\src\Foo.hs:4:7: error:");
+
+        assert_eq!(found.path, r"C:\project\src\Foo.hs");
+        assert_eq!((found.line, found.column), (3, 6));
+    }
+
+    #[test]
+    fn urls_are_not_links() {
+        assert!(find("https://hackage.haskell.org:443/package/base").is_empty());
+        assert!(find("see http://localhost:8080/index.html").is_empty());
+    }
+
+    #[test]
+    fn times_and_versions_are_not_links() {
+        assert!(find("Build finished at 12:30:45").is_empty());
+        assert!(find("took 0:01:02.5").is_empty());
+        assert!(find("localhost:8080 ghc-9.6.3:12").is_empty());
+    }
+
+    #[test]
+    fn zero_line_is_rejected() {
+        assert!(find("src/Foo.hs:0:1").is_empty());
+    }
+
+    #[test]
+    fn resolve_prefers_existing_file() {
+        let dir = std::env::temp_dir().join(format!("fexc-links-{}", std::process::id()));
+        let (first, second) = (dir.join("a"), dir.join("b"));
+        fs_err::create_dir_all(&first).unwrap();
+        fs_err::create_dir_all(second.join("src")).unwrap();
+        fs_err::write(second.join("src/Foo.hs"), "").unwrap();
+
+        assert_eq!(resolve("src/Foo.hs", &[&first, &second]), second.join("src/Foo.hs"));
+        assert_eq!(resolve("src/Bar.hs", &[&first, &second]), first.join("src/Bar.hs"));
+        assert_eq!(resolve("/abs/Foo.hs", &[&first]), PathBuf::from("/abs/Foo.hs"));
+
+        fs_err::remove_dir_all(&dir).unwrap();
+    }
+}
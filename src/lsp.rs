@@ -0,0 +1,610 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use lsp_types::{
+    notification::{self, Notification},
+    request::{self, Request},
+    OneOf, Uri,
+};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::document::{apply_text_edits, Document, DocumentError, Documents, FileEdit};
+
+pub const LANGUAGE_ID: &str = "haskell";
+
+/// A response from the language server, already matched to the request that caused it.
+pub enum LspEvent {
+    Rename(lsp_types::WorkspaceEdit),
+    Formatting {
+        doc: u64,
+        result: Result<Vec<lsp_types::TextEdit>, String>,
+    },
+    InlayHints {
+        doc: u64,
+        version: i32,
+        hints: Vec<lsp_types::InlayHint>,
+    },
+    CodeLenses {
+        doc: u64,
+        version: i32,
+        lenses: Vec<lsp_types::CodeLens>,
+    },
+    ResolvedCodeLens {
+        doc: u64,
+        version: i32,
+        index: usize,
+        lens: lsp_types::CodeLens,
+    },
+    CodeActions {
+        doc: u64,
+        version: i32,
+        line: u32,
+        actions: Vec<lsp_types::CodeActionOrCommand>,
+    },
+    ResolvedCodeAction(Box<lsp_types::CodeAction>),
+    Diagnostics {
+        path: PathBuf,
+        diagnostics: Vec<lsp_types::Diagnostic>,
+    },
+    /// The server asked the client to apply an edit, e.g. after executing a command.
+    ApplyEdit(lsp_types::WorkspaceEdit),
+    Error(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Pending {
+    Initialize,
+    Rename,
+    Formatting(u64),
+    InlayHints(u64, i32),
+    CodeLens(u64, i32),
+    ResolveCodeLens(u64, i32, usize),
+    CodeActions(u64, i32, u32),
+    ResolveCodeAction,
+    ExecuteCommand,
+}
+
+pub struct LspClient {
+    child: Child,
+    stdin: ChildStdin,
+    receiver: Receiver<Value>,
+    next_id: i64,
+    pending: HashMap<i64, Pending>,
+    queue: Vec<Value>,
+    initialized: bool,
+    synced: HashMap<PathBuf, i32>,
+    language_id: String,
+}
+
+impl LspClient {
+    pub fn start(command: &[String], root: &Path, language_id: &str, ctx: egui::Context) -> Result<Self, LspError> {
+        let (program, args) = command.split_first()
+            .ok_or(LspError::EmptyCommand)?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(LspError::Spawn)?;
+
+        let stdin = child.stdin.take().ok_or(LspError::NoPipe)?;
+        let stdout = child.stdout.take().ok_or(LspError::NoPipe)?;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || read_messages(stdout, sender, ctx));
+
+        let mut client = LspClient {
+            child,
+            stdin,
+            receiver,
+            next_id: 0,
+            pending: HashMap::new(),
+            queue: vec![],
+            initialized: false,
+            synced: HashMap::new(),
+            language_id: language_id.to_owned(),
+        };
+
+        let root_uri = path_to_uri(root)
+            .ok_or_else(|| LspError::InvalidPath(root.to_owned()))?;
+
+        let params = lsp_types::InitializeParams {
+            process_id: Some(std::process::id()),
+            capabilities: lsp_types::ClientCapabilities {
+                workspace: Some(lsp_types::WorkspaceClientCapabilities {
+                    workspace_edit: Some(lsp_types::WorkspaceEditClientCapabilities {
+                        document_changes: Some(true),
+                        ..Default::default()
+                    }),
+                    apply_edit: Some(true),
+                    ..Default::default()
+                }),
+                text_document: Some(lsp_types::TextDocumentClientCapabilities {
+                    inlay_hint: Some(Default::default()),
+                    code_lens: Some(Default::default()),
+                    code_action: Some(lsp_types::CodeActionClientCapabilities {
+                        code_action_literal_support: Some(lsp_types::CodeActionLiteralSupport {
+                            code_action_kind: lsp_types::CodeActionKindLiteralSupport {
+                                value_set: vec![
+                                    lsp_types::CodeActionKind::QUICKFIX.as_str().to_owned(),
+                                    lsp_types::CodeActionKind::REFACTOR.as_str().to_owned(),
+                                    lsp_types::CodeActionKind::SOURCE.as_str().to_owned(),
+                                ],
+                            },
+                        }),
+                        resolve_support: Some(lsp_types::CodeActionCapabilityResolveSupport {
+                            properties: vec!["edit".to_owned()],
+                        }),
+                        ..Default::default()
+                    }),
+                    publish_diagnostics: Some(Default::default()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            workspace_folders: Some(vec![lsp_types::WorkspaceFolder {
+                uri: root_uri,
+                name: root.file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            }]),
+            ..Default::default()
+        };
+
+        client.request::<request::Initialize>(params, Pending::Initialize)?;
+
+        Ok(client)
+    }
+
+    /// Sends `didOpen`/`didChange` notifications for every document whose version the server has not seen yet.
+    pub fn sync_documents<'a>(&mut self, docs: impl IntoIterator<Item = &'a Document>) -> Result<(), LspError> {
+        for doc in docs {
+            let Some(path) = &doc.path else { continue };
+            let Some(uri) = path_to_uri(path) else { continue };
+
+            match self.synced.get(path) {
+                None => {
+                    self.notify::<notification::DidOpenTextDocument>(lsp_types::DidOpenTextDocumentParams {
+                        text_document: lsp_types::TextDocumentItem::new(uri, self.language_id.clone(), doc.version, doc.text.clone()),
+                    })?;
+                }
+                Some(&version) if version != doc.version => {
+                    self.notify::<notification::DidChangeTextDocument>(lsp_types::DidChangeTextDocumentParams {
+                        text_document: lsp_types::VersionedTextDocumentIdentifier::new(uri, doc.version),
+                        content_changes: vec![lsp_types::TextDocumentContentChangeEvent {
+                            range: None,
+                            range_length: None,
+                            text: doc.text.clone(),
+                        }],
+                    })?;
+                }
+                Some(_) => continue,
+            }
+
+            self.synced.insert(path.clone(), doc.version);
+        }
+
+        Ok(())
+    }
+
+    /// Tells the server a document was closed; it is opened again if it is synced later.
+    pub fn close_document(&mut self, path: &Path) -> Result<(), LspError> {
+        if self.synced.remove(path).is_none() {
+            return Ok(());
+        }
+
+        self.notify::<notification::DidCloseTextDocument>(lsp_types::DidCloseTextDocumentParams {
+            text_document: text_document(path)?,
+        })
+    }
+
+    pub fn rename(&mut self, path: &Path, position: lsp_types::Position, new_name: &str) -> Result<(), LspError> {
+        let params = lsp_types::RenameParams {
+            text_document_position: text_document_position(path, position)?,
+            new_name: new_name.to_owned(),
+            work_done_progress_params: Default::default(),
+        };
+
+        self.request::<request::Rename>(params, Pending::Rename)
+    }
+
+    pub fn formatting(&mut self, path: &Path, doc: u64, tab_size: u32) -> Result<(), LspError> {
+        let params = lsp_types::DocumentFormattingParams {
+            text_document: text_document(path)?,
+            options: lsp_types::FormattingOptions {
+                tab_size,
+                insert_spaces: true,
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+        };
+
+        self.request::<request::Formatting>(params, Pending::Formatting(doc))
+    }
+
+    pub fn inlay_hints(&mut self, path: &Path, doc: u64, version: i32, range: lsp_types::Range) -> Result<(), LspError> {
+        let params = lsp_types::InlayHintParams {
+            text_document: text_document(path)?,
+            range,
+            work_done_progress_params: Default::default(),
+        };
+
+        self.request::<request::InlayHintRequest>(params, Pending::InlayHints(doc, version))
+    }
+
+    pub fn code_lens(&mut self, path: &Path, doc: u64, version: i32) -> Result<(), LspError> {
+        let params = lsp_types::CodeLensParams {
+            text_document: text_document(path)?,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        self.request::<request::CodeLensRequest>(params, Pending::CodeLens(doc, version))
+    }
+
+    pub fn resolve_code_lens(&mut self, lens: lsp_types::CodeLens, doc: u64, version: i32, index: usize) -> Result<(), LspError> {
+        self.request::<request::CodeLensResolve>(lens, Pending::ResolveCodeLens(doc, version, index))
+    }
+
+    pub fn code_actions(
+        &mut self,
+        path: &Path,
+        doc: u64,
+        version: i32,
+        range: lsp_types::Range,
+        diagnostics: Vec<lsp_types::Diagnostic>,
+    ) -> Result<(), LspError> {
+        let params = lsp_types::CodeActionParams {
+            text_document: text_document(path)?,
+            range,
+            context: lsp_types::CodeActionContext {
+                diagnostics,
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        self.request::<request::CodeActionRequest>(params, Pending::CodeActions(doc, version, range.start.line))
+    }
+
+    pub fn resolve_code_action(&mut self, action: lsp_types::CodeAction) -> Result<(), LspError> {
+        self.request::<request::CodeActionResolveRequest>(action, Pending::ResolveCodeAction)
+    }
+
+    pub fn execute_command(&mut self, command: &lsp_types::Command) -> Result<(), LspError> {
+        let params = lsp_types::ExecuteCommandParams {
+            command: command.command.clone(),
+            arguments: command.arguments.clone().unwrap_or_default(),
+            work_done_progress_params: Default::default(),
+        };
+
+        self.request::<request::ExecuteCommand>(params, Pending::ExecuteCommand)
+    }
+
+    /// Drains the messages received since the last call, answering server requests along the way.
+    pub fn poll(&mut self) -> Vec<LspEvent> {
+        let mut events = vec![];
+
+        while let Ok(message) = self.receiver.try_recv() {
+            let id = message.get("id").cloned();
+            let method = message.get("method").and_then(Value::as_str);
+
+            match (id, method) {
+                (Some(id), Some(request::ApplyWorkspaceEdit::METHOD)) => {
+                    let params = message.get("params").cloned().unwrap_or(Value::Null);
+                    let applied = match serde_json::from_value::<lsp_types::ApplyWorkspaceEditParams>(params) {
+                        Ok(params) => {
+                            events.push(LspEvent::ApplyEdit(params.edit));
+                            true
+                        }
+                        Err(e) => {
+                            log::warn!("Invalid workspace edit from language server: {e}");
+                            false
+                        }
+                    };
+
+                    self.write(&json!({ "jsonrpc": "2.0", "id": id, "result": { "applied": applied } }))
+                        .unwrap_or_else(|e| log::error!("{e}"));
+                }
+                // Other requests from the server (progress, configuration, registration) are acknowledged with an empty result
+                (Some(id), Some(_)) => {
+                    self.write(&json!({ "jsonrpc": "2.0", "id": id, "result": null }))
+                        .unwrap_or_else(|e| log::error!("{e}"));
+                }
+                (Some(id), None) => {
+                    let Some(pending) = id.as_i64().and_then(|id| self.pending.remove(&id)) else { continue };
+
+                    if let Some(error) = message.get("error") {
+                        let text = error.get("message").and_then(Value::as_str).unwrap_or_default();
+                        events.push(match pending {
+                            Pending::Formatting(doc) => LspEvent::Formatting { doc, result: Err(text.to_owned()) },
+                            _ => LspEvent::Error(format!("{pending:?}: {text}")),
+                        });
+                        continue;
+                    }
+
+                    let result = message.get("result").cloned().unwrap_or(Value::Null);
+                    match self.handle_response(pending, result) {
+                        Ok(Some(event)) => events.push(event),
+                        Ok(None) => {}
+                        Err(e) => events.push(LspEvent::Error(e.to_string())),
+                    }
+                }
+                (None, Some(notification::PublishDiagnostics::METHOD)) => {
+                    let params = message.get("params").cloned().unwrap_or(Value::Null);
+                    match serde_json::from_value::<lsp_types::PublishDiagnosticsParams>(params) {
+                        Ok(params) => {
+                            if let Some(path) = uri_to_path(&params.uri) {
+                                events.push(LspEvent::Diagnostics { path, diagnostics: params.diagnostics });
+                            }
+                        }
+                        Err(e) => log::warn!("Invalid diagnostics from language server: {e}"),
+                    }
+                }
+                (None, _) => {}
+            }
+        }
+
+        events
+    }
+
+    fn handle_response(&mut self, pending: Pending, result: Value) -> Result<Option<LspEvent>, LspError> {
+        match pending {
+            Pending::Initialize => {
+                self.initialized = true;
+                self.notify::<notification::Initialized>(lsp_types::InitializedParams {})?;
+
+                for message in std::mem::take(&mut self.queue) {
+                    self.write(&message)?;
+                }
+
+                Ok(None)
+            }
+            Pending::Rename => {
+                let edit: Option<lsp_types::WorkspaceEdit> = serde_json::from_value(result)
+                    .map_err(LspError::Json)?;
+
+                Ok(Some(LspEvent::Rename(edit.unwrap_or_default())))
+            }
+            Pending::Formatting(doc) => {
+                let edits: Option<Vec<lsp_types::TextEdit>> = serde_json::from_value(result)
+                    .map_err(LspError::Json)?;
+
+                Ok(Some(LspEvent::Formatting { doc, result: Ok(edits.unwrap_or_default()) }))
+            }
+            Pending::InlayHints(doc, version) => {
+                let hints: Option<Vec<lsp_types::InlayHint>> = serde_json::from_value(result)
+                    .map_err(LspError::Json)?;
+
+                Ok(Some(LspEvent::InlayHints { doc, version, hints: hints.unwrap_or_default() }))
+            }
+            Pending::CodeLens(doc, version) => {
+                let lenses: Option<Vec<lsp_types::CodeLens>> = serde_json::from_value(result)
+                    .map_err(LspError::Json)?;
+
+                Ok(Some(LspEvent::CodeLenses { doc, version, lenses: lenses.unwrap_or_default() }))
+            }
+            Pending::ResolveCodeLens(doc, version, index) => {
+                let lens = serde_json::from_value(result)
+                    .map_err(LspError::Json)?;
+
+                Ok(Some(LspEvent::ResolvedCodeLens { doc, version, index, lens }))
+            }
+            Pending::CodeActions(doc, version, line) => {
+                let actions: Option<Vec<lsp_types::CodeActionOrCommand>> = serde_json::from_value(result)
+                    .map_err(LspError::Json)?;
+
+                Ok(Some(LspEvent::CodeActions { doc, version, line, actions: actions.unwrap_or_default() }))
+            }
+            Pending::ResolveCodeAction => {
+                let action = serde_json::from_value(result)
+                    .map_err(LspError::Json)?;
+
+                Ok(Some(LspEvent::ResolvedCodeAction(Box::new(action))))
+            }
+            Pending::ExecuteCommand => Ok(None),
+        }
+    }
+
+    fn request<R: Request>(&mut self, params: R::Params, pending: Pending) -> Result<(), LspError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.insert(id, pending);
+
+        let message = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": R::METHOD,
+            "params": serde_json::to_value(params).map_err(LspError::Json)?,
+        });
+
+        if self.initialized || matches!(pending, Pending::Initialize) {
+            self.write(&message)
+        } else {
+            self.queue.push(message);
+            Ok(())
+        }
+    }
+
+    fn notify<N: Notification>(&mut self, params: N::Params) -> Result<(), LspError> {
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": N::METHOD,
+            "params": serde_json::to_value(params).map_err(LspError::Json)?,
+        });
+
+        if self.initialized || N::METHOD == notification::Initialized::METHOD {
+            self.write(&message)
+        } else {
+            self.queue.push(message);
+            Ok(())
+        }
+    }
+
+    fn write(&mut self, message: &Value) -> Result<(), LspError> {
+        let body = message.to_string();
+
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{body}", body.len())
+            .and_then(|_| self.stdin.flush())
+            .map_err(LspError::Write)
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        // Wait for the killed server so it does not linger as a zombie
+        if let Err(e) = self.child.kill().and_then(|_| self.child.wait()) {
+            log::warn!("Failed to stop language server: {e}");
+        }
+    }
+}
+
+fn read_messages(stdout: ChildStdout, sender: Sender<Value>, ctx: egui::Context) {
+    let mut reader = BufReader::new(stdout);
+
+    loop {
+        let mut content_length = None;
+
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+
+            let line = line.trim();
+            if line.is_empty() {
+                break;
+            }
+
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+
+        let Some(len) = content_length else { continue };
+
+        let mut body = vec![0; len];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+
+        match serde_json::from_slice(&body) {
+            Ok(message) => {
+                if sender.send(message).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+            Err(e) => log::warn!("Invalid message from language server: {e}"),
+        }
+    }
+}
+
+fn text_document(path: &Path) -> Result<lsp_types::TextDocumentIdentifier, LspError> {
+    path_to_uri(path)
+        .map(lsp_types::TextDocumentIdentifier::new)
+        .ok_or_else(|| LspError::InvalidPath(path.to_owned()))
+}
+
+fn text_document_position(path: &Path, position: lsp_types::Position) -> Result<lsp_types::TextDocumentPositionParams, LspError> {
+    Ok(lsp_types::TextDocumentPositionParams::new(text_document(path)?, position))
+}
+
+pub fn path_to_uri(path: &Path) -> Option<Uri> {
+    url::Url::from_file_path(path).ok()?
+        .as_str()
+        .parse()
+        .ok()
+}
+
+pub fn uri_to_path(uri: &Uri) -> Option<PathBuf> {
+    url::Url::parse(uri.as_str()).ok()?
+        .to_file_path()
+        .ok()
+}
+
+/// Resolves a workspace edit against the current buffers, producing the full old and new text of every affected file.
+pub fn workspace_edit_to_file_edits(
+    docs: &Documents,
+    edit: lsp_types::WorkspaceEdit,
+) -> Result<Vec<FileEdit>, DocumentError> {
+    let mut per_file: BTreeMap<PathBuf, Vec<lsp_types::TextEdit>> = BTreeMap::new();
+
+    let mut add = |uri: &Uri, edits: Vec<lsp_types::TextEdit>| {
+        match uri_to_path(uri) {
+            Some(path) => per_file.entry(path).or_default().extend(edits),
+            None => log::warn!("Skipping edit for non-file URI {}", uri.as_str()),
+        }
+    };
+
+    let unannotated = |edits: Vec<OneOf<lsp_types::TextEdit, lsp_types::AnnotatedTextEdit>>| {
+        edits.into_iter()
+            .map(|e| match e {
+                OneOf::Left(edit) => edit,
+                OneOf::Right(annotated) => annotated.text_edit,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    match edit.document_changes {
+        Some(lsp_types::DocumentChanges::Edits(edits)) => {
+            for edit in edits {
+                add(&edit.text_document.uri, unannotated(edit.edits));
+            }
+        }
+        Some(lsp_types::DocumentChanges::Operations(ops)) => {
+            for op in ops {
+                match op {
+                    lsp_types::DocumentChangeOperation::Edit(edit) => {
+                        add(&edit.text_document.uri, unannotated(edit.edits));
+                    }
+                    lsp_types::DocumentChangeOperation::Op(op) => {
+                        log::warn!("Unsupported resource operation: {op:?}");
+                    }
+                }
+            }
+        }
+        None => {
+            for (uri, edits) in edit.changes.unwrap_or_default() {
+                add(&uri, edits);
+            }
+        }
+    }
+
+    per_file.into_iter()
+        .map(|(path, edits)| {
+            let old_text = docs.contents(&path)?;
+            let new_text = apply_text_edits(&old_text, &edits);
+
+            Ok(FileEdit { path, old_text, new_text })
+        })
+        .collect()
+}
+
+#[derive(Debug, Error)]
+pub enum LspError {
+    #[error("Language server command is empty")]
+    EmptyCommand,
+    #[error("Failed to start language server: {0}")]
+    Spawn(io::Error),
+    #[error("Language server pipes are not available")]
+    NoPipe,
+    #[error("Failed to write to language server: {0}")]
+    Write(io::Error),
+    #[error("Invalid language server message: {0}")]
+    Json(serde_json::Error),
+    #[error("Path cannot be converted to URI: {0}")]
+    InvalidPath(PathBuf),
+}
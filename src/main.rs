@@ -1,10 +1,35 @@
 #![deny(clippy::disallowed_methods)]
 
 mod app;
+mod cabal;
+mod code_actions;
+mod commands;
 mod config;
+mod diagnostics;
+mod document;
+mod events;
 mod fonts;
+mod format;
+mod ghc;
+mod haskell;
+mod hints;
+mod hlint;
 mod i18n;
+mod languages;
+mod links;
+mod lsp;
+mod notifications;
 mod plugin;
+mod process;
+mod project;
+mod repl;
+mod session;
+mod snippets;
+mod symbol_index;
+mod tasks;
+mod terminal;
+mod testing;
+mod watch;
 mod widgets;
 
 use app::Fexc;
@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Error,
+}
+
+pub struct Notification {
+    pub level: Level,
+    pub message: String,
+    pub expires: Instant,
+}
+
+#[derive(Default)]
+pub struct Notifications(Vec<Notification>);
+
+impl Notifications {
+    pub const LIFETIME: Duration = Duration::from_secs(8);
+
+    pub fn info(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        log::info!("{message}");
+        self.push(Level::Info, message);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        log::error!("{message}");
+        self.push(Level::Error, message);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Notification> {
+        self.0.iter()
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.0.remove(index);
+    }
+
+    /// Drops expired notifications, returning how long until the next one expires.
+    pub fn prune(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        self.0.retain(|n| n.expires > now);
+        self.0.iter().map(|n| n.expires - now).min()
+    }
+
+    fn push(&mut self, level: Level, message: String) {
+        self.0.push(Notification {
+            level,
+            message,
+            expires: Instant::now() + Self::LIFETIME,
+        });
+    }
+}
@@ -1,3 +0,0 @@
-pub trait Plugin: Send + Sync {
-
-}
@@ -0,0 +1,150 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use thiserror::Error;
+
+use super::manifest::{InstalledPlugin, ManifestError, PluginManifest, MANIFEST_FILE};
+
+#[derive(Debug, Error)]
+pub enum InstallError {
+    #[error("Data folder is not found/supported on this platform")]
+    DataFolderNotSupported,
+    #[error("{0}")]
+    Manifest(ManifestError),
+    #[error("Plugin {0} is already installed")]
+    AlreadyInstalled(String),
+    #[error("Failed to copy plugin: {0}")]
+    Copy(io::Error),
+    #[error("Failed to remove plugin: {0}")]
+    Remove(io::Error),
+}
+
+/// Where third-party plugins are installed, one directory per plugin.
+pub fn plugins_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("fexc").join("plugins"))
+}
+
+fn is_component(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "wasm") && path.is_file()
+}
+
+/// The subdirectories of the plugins directory that have a manifest, and the loose `.wasm`
+/// components in it, which run without permissions.
+pub fn discover() -> Vec<InstalledPlugin> {
+    let Some(Ok(entries)) = plugins_dir().map(fs::read_dir) else { return vec![] };
+
+    let mut paths = entries.flatten()
+        .map(|e| e.path())
+        .filter(|path| path.join(MANIFEST_FILE).is_file() || is_component(path))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths.into_iter()
+        .map(|path| {
+            let manifest = if path.is_dir() { PluginManifest::load(&path) } else { PluginManifest::for_component(&path) };
+            InstalledPlugin { manifest: manifest.map_err(|e| e.to_string()), path }
+        })
+        .collect()
+}
+
+/// Copies a plugin folder, given as the folder or its `plugin.toml`, or a loose `.wasm` component
+/// into the plugins directory.
+pub fn install(source: &Path) -> Result<PluginManifest, InstallError> {
+    if is_component(source) {
+        return install_component(source);
+    }
+
+    let source = if source.is_file() { source.parent().unwrap_or(source) } else { source };
+    let manifest = PluginManifest::load(source)
+        .map_err(InstallError::Manifest)?;
+
+    let target = plugins_dir()
+        .ok_or(InstallError::DataFolderNotSupported)?
+        .join(&manifest.name);
+
+    if target.exists() {
+        return Err(InstallError::AlreadyInstalled(manifest.name));
+    }
+
+    copy_dir(source, &target)
+        .map_err(InstallError::Copy)?;
+
+    Ok(manifest)
+}
+
+fn install_component(source: &Path) -> Result<PluginManifest, InstallError> {
+    let manifest = PluginManifest::for_component(source)
+        .map_err(InstallError::Manifest)?;
+
+    let dir = plugins_dir()
+        .ok_or(InstallError::DataFolderNotSupported)?;
+    let target = dir.join(format!("{}.wasm", manifest.name));
+
+    if target.exists() || dir.join(&manifest.name).exists() {
+        return Err(InstallError::AlreadyInstalled(manifest.name));
+    }
+
+    fs::create_dir_all(&dir)
+        .and_then(|()| fs::copy(source, target))
+        .map_err(InstallError::Copy)?;
+
+    Ok(manifest)
+}
+
+/// Removes a plugin's directory or its loose `.wasm` file.
+pub fn uninstall(path: &Path) -> Result<(), InstallError> {
+    let removed = if path.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+    removed.map_err(InstallError::Remove)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let kind = entry.file_type()?;
+
+        // Links are skipped, following them could copy files from anywhere into the plugin
+        if kind.is_symlink() {
+            continue;
+        }
+
+        if kind.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_skips_links() {
+        let root = std::env::temp_dir().join(format!("fexc-install-{}", std::process::id()));
+        let (from, to) = (root.join("from"), root.join("to"));
+        fs::create_dir_all(from.join("grammars")).unwrap();
+        fs::write(from.join(MANIFEST_FILE), "").unwrap();
+        fs::write(from.join("grammars/elm.sublime-syntax"), "").unwrap();
+        fs::write(root.join("secret"), "").unwrap();
+        fs_err::os::unix::fs::symlink(root.join("secret"), from.join("secret")).unwrap();
+        fs_err::os::unix::fs::symlink(&root, from.join("root")).unwrap();
+
+        copy_dir(&from, &to).unwrap();
+
+        assert!(to.join(MANIFEST_FILE).is_file());
+        assert!(to.join("grammars/elm.sublime-syntax").is_file());
+        assert!(!to.join("secret").exists());
+        assert!(!to.join("root").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::languages::LanguageDef;
+
+use super::{
+    builtin_plugins,
+    permissions::Permission,
+    ui::{UiNode, ViewSlot},
+    API_VERSION,
+};
+
+pub const MANIFEST_FILE: &str = "plugin.toml";
+
+/// The `plugin.toml` every installed plugin ships with.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub description: String,
+    /// The plugin API version the plugin was written against.
+    pub api_version: u32,
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
+    #[serde(default)]
+    pub commands: Vec<ManifestCommand>,
+    #[serde(default)]
+    pub views: Vec<ManifestView>,
+    #[serde(default)]
+    pub languages: Vec<LanguageDef>,
+}
+
+/// A command the plugin contributes, listed so it is known before the plugin runs.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ManifestCommand {
+    pub id: String,
+    pub title: String,
+    /// A shortcut such as `Ctrl+Alt+T` that runs the command.
+    #[serde(default)]
+    pub shortcut: Option<String>,
+}
+
+/// A view with fixed content, shown while the plugin is enabled.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ManifestView {
+    pub id: String,
+    pub slot: ViewSlot,
+    #[serde(default)]
+    pub icon: String,
+    pub title: String,
+    pub content: UiNode,
+}
+
+#[derive(Debug, Error)]
+pub enum ManifestError {
+    #[error("Failed to read plugin manifest: {0}")]
+    Read(io::Error),
+    #[error("Invalid plugin manifest: {0}")]
+    Toml(toml::de::Error),
+    #[error("Invalid plugin name {0:?}: use letters, digits, `-` and `_`")]
+    InvalidName(String),
+    #[error("Plugin name {0:?} is reserved for a built-in plugin")]
+    Reserved(String),
+}
+
+impl PluginManifest {
+    pub fn load(dir: &Path) -> Result<Self, ManifestError> {
+        let text = fs::read_to_string(dir.join(MANIFEST_FILE))
+            .map_err(ManifestError::Read)?;
+
+        let manifest: PluginManifest = toml::from_str(&text)
+            .map_err(ManifestError::Toml)?;
+
+        validate_name(manifest)
+    }
+
+    /// The manifest of a loose `.wasm` component, named after the file and without any permissions.
+    pub fn for_component(path: &Path) -> Result<Self, ManifestError> {
+        validate_name(PluginManifest {
+            name: path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+            version: String::new(),
+            author: String::new(),
+            description: String::new(),
+            api_version: API_VERSION,
+            permissions: vec![],
+            commands: vec![],
+            views: vec![],
+            languages: vec![],
+        })
+    }
+
+    /// Why the plugin cannot run with this version of the editor, if it cannot.
+    pub fn compatibility_error(&self) -> Option<String> {
+        (self.api_version != API_VERSION).then(|| {
+            format!("requires plugin API version {}, but this editor provides version {API_VERSION}", self.api_version)
+        })
+    }
+}
+
+// The name doubles as the directory the plugin is installed to and as its id, so it must not
+// shadow a built-in plugin
+fn validate_name(manifest: PluginManifest) -> Result<PluginManifest, ManifestError> {
+    let valid_name = !manifest.name.is_empty()
+        && manifest.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if !valid_name {
+        Err(ManifestError::InvalidName(manifest.name))
+    } else if builtin_plugins().iter().any(|plugin| plugin.id() == manifest.name) {
+        Err(ManifestError::Reserved(manifest.name))
+    } else {
+        Ok(manifest)
+    }
+}
+
+/// A plugin in the plugins directory, whether its manifest could be read or not.
+#[derive(Debug)]
+pub struct InstalledPlugin {
+    /// The plugin's directory, or the file of a loose `.wasm` component.
+    pub path: PathBuf,
+    pub manifest: Result<PluginManifest, String>,
+}
+
+impl InstalledPlugin {
+    /// The manifest name, or the file name if the manifest is broken.
+    pub fn id(&self) -> String {
+        match &self.manifest {
+            Ok(manifest) => manifest.name.clone(),
+            Err(_) => self.path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+        }
+    }
+
+    /// The WebAssembly component to run, if the plugin has one.
+    pub fn code(&self) -> PathBuf {
+        if self.path.is_dir() { self.path.join("plugin.wasm") } else { self.path.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn component_names_are_validated() {
+        assert_eq!(PluginManifest::for_component(Path::new("/plugins/lint.wasm")).unwrap().name, "lint");
+        assert!(matches!(
+            PluginManifest::for_component(Path::new("/plugins/my lint.wasm")),
+            Err(ManifestError::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    fn built_in_ids_are_reserved() {
+        let result = PluginManifest::for_component(Path::new("/plugins/whitespace.wasm"));
+
+        assert!(matches!(result, Err(ManifestError::Reserved(name)) if name == "whitespace"));
+    }
+}
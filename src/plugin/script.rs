@@ -0,0 +1,608 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use fs_err as fs;
+use mlua::{Function, HookTriggers, Lua, RegistryKey, Scope, Table, Value};
+use thiserror::Error;
+
+use crate::{
+    commands::parse_shortcut,
+    config,
+    document::Document,
+    events::{Event, EventFilter, EventKind},
+};
+
+use super::{
+    HOOK_TIMEOUT, MEMORY_LIMIT, Plugin, PluginContext,
+    ui::{UiNode, ViewSlot},
+};
+
+/// The id the user's init script is listed under on the Plugins page.
+pub const INIT_SCRIPT_ID: &str = "init.lua";
+
+/// How many Lua instructions run between checks of the time limit.
+const INSTRUCTIONS_PER_CHECK: u32 = 10_000;
+
+/// Sets up the `fexc` table, whose functions call the ones the editor puts into `host` during each hook.
+const PRELUDE: &str = r#"
+local host, names = ...
+fexc = {}
+for _, name in ipairs(names) do
+    fexc[name] = function(...) return host[name](...) end
+end
+"#;
+
+/// The functions of the `fexc` table.
+const API: &[&str] = &[
+    "log", "info", "error",
+    "project_root", "active_document", "document",
+    "read_file", "open_file", "edit_document", "edit_file",
+    "run_process", "write_to_terminal",
+    "settings", "update_settings",
+    "command", "bind", "snippet", "on",
+    "set_view", "remove_view", "on_action",
+];
+
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    #[error("Failed to read script: {0}")]
+    Read(io::Error),
+    #[error("{0}")]
+    Lua(String),
+}
+
+/// The user's Lua script run on startup, `init.lua` in the config directory.
+pub fn init_script_path() -> Option<PathBuf> {
+    config::config_dir().ok().map(|dir| dir.join(INIT_SCRIPT_ID))
+}
+
+/// Reads a Lua script and runs it as a plugin with the id [`INIT_SCRIPT_ID`].
+pub fn load(path: &Path) -> Result<Box<dyn Plugin>, ScriptError> {
+    let source = fs::read_to_string(path)
+        .map_err(ScriptError::Read)?;
+
+    LuaPlugin::new(INIT_SCRIPT_ID, &source)
+        .map(|plugin| Box::new(plugin) as Box<dyn Plugin>)
+        .map_err(|e| ScriptError::Lua(describe(&e)))
+}
+
+/// The Lua functions a script registered through the `fexc` table.
+#[derive(Default)]
+struct Callbacks {
+    /// Command functions by command id.
+    commands: HashMap<String, RegistryKey>,
+    listeners: Vec<(EventFilter, RegistryKey)>,
+    /// Functions receiving the button clicks of a view, by view id.
+    actions: HashMap<String, RegistryKey>,
+}
+
+/// What the host functions work with while a hook runs.
+struct Host<'a, 'cx> {
+    cx: &'a mut PluginContext<'cx>,
+    callbacks: &'a mut Callbacks,
+    deadline: &'a Mutex<Instant>,
+}
+
+/// A plugin written in Lua. Scripts use the editor through the `fexc` table:
+///
+/// - `log`, `info` and `error` write to the plugin's log, the latter two also notify the user.
+/// - `command(id, title, run, key)` adds a command, run by the optional shortcut `key` such as
+///   `Ctrl+Alt+T`, `bind(key, command)` binds a shortcut to a command id or a function, and
+///   `snippet(prefix, body)` adds a snippet.
+/// - `on(kinds, listener, glob)` calls `listener` with events such as `document-saved`.
+/// - The rest mirror [`PluginContext`]: documents, files, processes, the terminal, settings and views.
+///
+/// Errors are reported in the plugin's log rather than unloading it.
+pub struct LuaPlugin {
+    id: String,
+    lua: Lua,
+    /// The compiled script until it is run when the plugin is loaded.
+    script: Option<RegistryKey>,
+    /// The table the `fexc` functions call into.
+    host: RegistryKey,
+    callbacks: Callbacks,
+    /// When the running hook has to finish, moved forward by the time the editor spends on its calls.
+    deadline: Arc<Mutex<Instant>>,
+}
+
+impl LuaPlugin {
+    pub fn new(id: &str, source: &str) -> mlua::Result<Self> {
+        let lua = Lua::new();
+        lua.set_memory_limit(MEMORY_LIMIT)?;
+
+        let deadline = Arc::new(Mutex::new(Instant::now()));
+        let hook_deadline = deadline.clone();
+        lua.set_hook(HookTriggers::new().every_nth_instruction(INSTRUCTIONS_PER_CHECK), move |_, _| {
+            if Instant::now() > *hook_deadline.lock().unwrap_or_else(|e| e.into_inner()) {
+                return Err(mlua::Error::RuntimeError(
+                    format!("the script did not finish within {} ms", HOOK_TIMEOUT.as_millis())
+                ));
+            }
+            Ok(())
+        });
+
+        let host = lua.create_table()?;
+        lua.load(PRELUDE).set_name("=prelude").call::<_, ()>((host.clone(), API.to_vec()))?;
+
+        let script = lua.load(source).set_name(format!("@{id}")).into_function()?;
+
+        Ok(LuaPlugin {
+            id: id.to_owned(),
+            script: Some(lua.create_registry_value(script)?),
+            host: lua.create_registry_value(host)?,
+            lua,
+            callbacks: Callbacks::default(),
+            deadline,
+        })
+    }
+
+    /// Runs `hook` with the `fexc` functions bound to `cx`, logging the error it fails with.
+    fn hook(&mut self, cx: &mut PluginContext, hook: impl FnOnce(&Lua, &RefCell<Host>) -> mlua::Result<()>) {
+        *self.deadline.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now() + HOOK_TIMEOUT;
+
+        let lua = &self.lua;
+        let state = RefCell::new(Host { cx, callbacks: &mut self.callbacks, deadline: &self.deadline });
+
+        let result = lua.scope(|scope| {
+            let host = lua.registry_value::<Table>(&self.host)?;
+            register_host(scope, &host, &state, &self.id)?;
+            hook(lua, &state)
+        });
+
+        if let Err(e) = result {
+            state.into_inner().cx.error(describe(&e));
+        }
+        lua.expire_registry_values();
+    }
+}
+
+impl Plugin for LuaPlugin {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn on_load(&mut self, cx: &mut PluginContext) {
+        let Some(script) = self.script.take() else { return };
+        self.hook(cx, |lua, _| lua.registry_value::<Function>(&script)?.call(()));
+    }
+
+    fn run_command(&mut self, cx: &mut PluginContext, command: &str) {
+        self.hook(cx, |lua, state| {
+            let Some(run) = callback(lua, &state.borrow().callbacks.commands, command)? else { return Ok(()) };
+            run.call(())
+        });
+    }
+
+    fn on_ui_action(&mut self, cx: &mut PluginContext, view: &str, action: &str) {
+        self.hook(cx, |lua, state| {
+            let Some(on_action) = callback(lua, &state.borrow().callbacks.actions, view)? else { return Ok(()) };
+            on_action.call(action)
+        });
+    }
+
+    fn on_event(&mut self, cx: &mut PluginContext, event: &Event) {
+        self.hook(cx, |lua, state| {
+            let listeners = {
+                let state = state.borrow();
+                let root = state.cx.project_root();
+
+                state.callbacks.listeners.iter()
+                    .filter(|(filter, _)| filter.matches(event, root))
+                    .map(|(_, key)| lua.registry_value::<Function>(key))
+                    .collect::<mlua::Result<Vec<_>>>()?
+            };
+
+            for listener in listeners {
+                listener.call::<_, ()>(event_table(lua, event)?)?;
+            }
+            Ok(())
+        });
+    }
+}
+
+fn callback<'lua>(lua: &'lua Lua, callbacks: &HashMap<String, RegistryKey>, id: &str) -> mlua::Result<Option<Function<'lua>>> {
+    callbacks.get(id).map(|key| lua.registry_value(key)).transpose()
+}
+
+/// Runs a host function, not counting the time it takes against the hook's time limit.
+fn host_call<R>(state: &RefCell<Host>, f: impl FnOnce(&mut Host) -> Result<R, String>) -> mlua::Result<R> {
+    let start = Instant::now();
+    let mut host = state.borrow_mut();
+    let result = f(&mut host);
+    *host.deadline.lock().unwrap_or_else(|e| e.into_inner()) += start.elapsed();
+
+    result.map_err(mlua::Error::RuntimeError)
+}
+
+/// Fills `host` with the functions behind the `fexc` table, bound to the running hook.
+fn register_host<'lua, 'scope>(
+    scope: &Scope<'lua, 'scope>,
+    host: &Table<'lua>,
+    state: &'scope RefCell<Host>,
+    plugin: &'scope str,
+) -> mlua::Result<()> {
+    host.set("log", scope.create_function(|_, message: String| {
+        host_call(state, |host| {
+            host.cx.log(&message);
+            Ok(())
+        })
+    })?)?;
+    host.set("info", scope.create_function(|_, message: String| {
+        host_call(state, |host| {
+            host.cx.info(message);
+            Ok(())
+        })
+    })?)?;
+    host.set("error", scope.create_function(|_, message: String| {
+        host_call(state, |host| {
+            host.cx.error(message);
+            Ok(())
+        })
+    })?)?;
+
+    host.set("project_root", scope.create_function(|_, ()| {
+        host_call(state, |host| Ok(host.cx.project_root().map(|root| root.to_string_lossy().into_owned())))
+    })?)?;
+    host.set("active_document", scope.create_function(|lua, ()| {
+        let doc = host_call(state, |host| host.cx.active_document().cloned().map_err(|e| e.to_string()))?;
+        document_table(lua, &doc)
+    })?)?;
+    host.set("document", scope.create_function(|lua, id: u64| {
+        let doc = host_call(state, |host| host.cx.document(id).map(Option::<&Document>::cloned).map_err(|e| e.to_string()))?;
+        doc.map(|doc| document_table(lua, &doc)).transpose()
+    })?)?;
+
+    host.set("read_file", scope.create_function(|_, path: String| {
+        host_call(state, |host| host.cx.read(Path::new(&path)).map_err(|e| e.to_string()))
+    })?)?;
+    host.set("open_file", scope.create_function(|_, path: String| {
+        host_call(state, |host| host.cx.open(Path::new(&path)).map_err(|e| e.to_string()))
+    })?)?;
+    host.set("edit_document", scope.create_function(|_, (id, text): (u64, String)| {
+        host_call(state, |host| host.cx.edit(id, text).map_err(|e| e.to_string()))
+    })?)?;
+    host.set("edit_file", scope.create_function(|_, (path, text): (String, String)| {
+        host_call(state, |host| host.cx.edit_file(Path::new(&path), text).map_err(|e| e.to_string()))
+    })?)?;
+
+    host.set("run_process", scope.create_function(
+        |lua, (program, args, input, cwd): (String, Option<Vec<String>>, Option<String>, Option<String>)| {
+            let args = args.unwrap_or_default();
+            let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+            let output = host_call(state, |host| {
+                host.cx.run_process(&program, &args, input.unwrap_or_default(), cwd.as_deref().map(Path::new))
+                    .map_err(|e| e.to_string())
+            })?;
+
+            let table = lua.create_table()?;
+            table.set("status", output.status.code())?;
+            table.set("stdout", String::from_utf8_lossy(&output.stdout).into_owned())?;
+            table.set("stderr", String::from_utf8_lossy(&output.stderr).into_owned())?;
+            Ok(table)
+        },
+    )?)?;
+    host.set("write_to_terminal", scope.create_function(|_, text: String| {
+        host_call(state, |host| host.cx.write_to_terminal(&text).map_err(|e| e.to_string()))
+    })?)?;
+
+    host.set("settings", scope.create_function(|lua, ()| {
+        let settings = host_call(state, |host| serde_json::to_value(host.cx.config()).map_err(|e| e.to_string()))?;
+        to_lua(lua, &settings)
+    })?)?;
+    host.set("update_settings", scope.create_function(|_, patch: Table| {
+        let patch = toml::to_string(&to_json(Value::Table(patch))?)
+            .map_err(|e| mlua::Error::RuntimeError(format!("Invalid settings: {e}")))?;
+        host_call(state, |host| host.cx.update_settings(&patch).map_err(|e| e.to_string()))
+    })?)?;
+
+    host.set("command", scope.create_function(|lua, (id, title, run, key): (String, String, Function, Option<String>)| {
+        let shortcut = key.map(|key| parse_shortcut(&key).ok_or_else(|| mlua::Error::RuntimeError(format!("Invalid shortcut {key:?}"))))
+            .transpose()?;
+        let run = lua.create_registry_value(run)?;
+        host_call(state, |host| {
+            host.cx.register_command(&id, &title, shortcut);
+            host.callbacks.commands.insert(id, run);
+            Ok(())
+        })
+    })?)?;
+    host.set("bind", scope.create_function(move |lua, (key, command): (String, Value)| {
+        let shortcut = parse_shortcut(&key)
+            .ok_or_else(|| mlua::Error::RuntimeError(format!("Invalid shortcut {key:?}")))?;
+
+        let command = match command {
+            Value::String(id) => id.to_str()?.to_owned(),
+            Value::Function(run) => {
+                let id = format!("key.{key}");
+                let run = lua.create_registry_value(run)?;
+                host_call(state, |host| {
+                    host.cx.register_command(&id, &key, None);
+                    host.callbacks.commands.insert(id.clone(), run);
+                    Ok(())
+                })?;
+                format!("{plugin}.{id}")
+            }
+            _ => return Err(mlua::Error::RuntimeError("bind expects a command id or a function".to_owned())),
+        };
+
+        host_call(state, |host| {
+            host.cx.bind_key(shortcut, &command);
+            Ok(())
+        })
+    })?)?;
+    host.set("snippet", scope.create_function(|_, (prefix, body): (String, String)| {
+        host_call(state, |host| {
+            host.cx.register_snippet(&prefix, &body);
+            Ok(())
+        })
+    })?)?;
+    host.set("on", scope.create_function(|lua, (kinds, listener, glob): (Value, Function, Option<String>)| {
+        let kinds = match kinds {
+            Value::String(kind) => vec![event_kind(kind.to_str()?)?],
+            Value::Table(kinds) => kinds.sequence_values::<mlua::String>()
+                .map(|kind| event_kind(kind?.to_str()?))
+                .collect::<mlua::Result<_>>()?,
+            _ => vec![],
+        };
+        let filter = EventFilter { kinds, glob };
+        let listener = lua.create_registry_value(listener)?;
+
+        host_call(state, |host| {
+            host.cx.subscribe(filter.clone());
+            host.callbacks.listeners.push((filter, listener));
+            Ok(())
+        })
+    })?)?;
+
+    host.set("set_view", scope.create_function(
+        |_, (slot, id, icon, title, content): (String, String, String, String, Value)| {
+            let slot = serde_json::from_value::<ViewSlot>(slot.into())
+                .map_err(|e| mlua::Error::RuntimeError(format!("Invalid view slot: {e}")))?;
+            let content = serde_json::from_value::<UiNode>(to_json(content)?)
+                .map_err(|e| mlua::Error::RuntimeError(format!("Invalid view content: {e}")))?;
+
+            host_call(state, |host| {
+                host.cx.set_view(slot, &id, &icon, &title, content);
+                Ok(())
+            })
+        },
+    )?)?;
+    host.set("remove_view", scope.create_function(|_, id: String| {
+        host_call(state, |host| {
+            host.cx.remove_view(&id);
+            host.callbacks.actions.remove(&id);
+            Ok(())
+        })
+    })?)?;
+    host.set("on_action", scope.create_function(|lua, (view, on_action): (String, Function)| {
+        let on_action = lua.create_registry_value(on_action)?;
+        host_call(state, |host| {
+            host.callbacks.actions.insert(view, on_action);
+            Ok(())
+        })
+    })?)?;
+
+    Ok(())
+}
+
+fn event_kind(name: &str) -> mlua::Result<EventKind> {
+    serde_json::from_value(name.into())
+        .map_err(|_| mlua::Error::RuntimeError(format!("Unknown event {name:?}")))
+}
+
+fn document_table<'lua>(lua: &'lua Lua, doc: &Document) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set("id", doc.id)?;
+    table.set("path", doc.path.as_ref().map(|path| path.to_string_lossy().into_owned()))?;
+    table.set("text", doc.text.as_str())?;
+    table.set("version", doc.version)?;
+    table.set("cursor", doc.cursor)?;
+    Ok(table)
+}
+
+/// The event as a table with its `kind` and fields, e.g. `{ kind = "document-saved", path = "..." }`.
+fn event_table<'lua>(lua: &'lua Lua, event: &Event) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set("kind", serde_json::to_value(event.kind()).ok().and_then(|kind| kind.as_str().map(str::to_owned)))?;
+    table.set("path", event.path().map(|path| path.to_string_lossy().into_owned()))?;
+
+    match event {
+        Event::DocumentOpened { doc, .. } | Event::DocumentChanged { doc, .. } | Event::DocumentClosed { doc, .. } => {
+            table.set("doc", *doc)?;
+        }
+        Event::CursorMoved { doc, cursor, .. } => {
+            table.set("doc", *doc)?;
+            table.set("cursor", *cursor)?;
+        }
+        Event::BuildFinished { task, success } => {
+            table.set("task", task.as_str())?;
+            table.set("success", *success)?;
+        }
+        Event::ThemeChanged { theme } => {
+            table.set("theme", match theme {
+                egui::Theme::Dark => "dark",
+                egui::Theme::Light => "light",
+            })?;
+        }
+        Event::DocumentSaved { .. } | Event::ProjectOpened { .. } | Event::DiagnosticsUpdated { .. } | Event::ConfigChanged => {}
+    }
+
+    Ok(table)
+}
+
+/// Converts a Lua value to JSON; tables with only the keys `1..n` become arrays.
+fn to_json(value: Value) -> mlua::Result<serde_json::Value> {
+    Ok(match value {
+        Value::Nil => serde_json::Value::Null,
+        Value::Boolean(b) => b.into(),
+        Value::Integer(i) => i.into(),
+        Value::Number(n) => n.into(),
+        Value::String(s) => s.to_str()?.into(),
+        Value::Table(table) => {
+            let len = table.raw_len();
+            if len > 0 && table.clone().pairs::<Value, Value>().count() == len {
+                table.sequence_values::<Value>()
+                    .map(|value| to_json(value?))
+                    .collect::<mlua::Result<_>>()?
+            } else {
+                table.pairs::<String, Value>()
+                    .map(|pair| pair.and_then(|(key, value)| Ok((key, to_json(value)?))))
+                    .collect::<mlua::Result<serde_json::Map<_, _>>>()?
+                    .into()
+            }
+        }
+        value => return Err(mlua::Error::RuntimeError(format!("A {} cannot be passed to the editor", value.type_name()))),
+    })
+}
+
+fn to_lua<'lua>(lua: &'lua Lua, value: &serde_json::Value) -> mlua::Result<Value<'lua>> {
+    Ok(match value {
+        serde_json::Value::Null => Value::Nil,
+        serde_json::Value::Bool(b) => Value::Boolean(*b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Number(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::String(lua.create_string(s)?),
+        serde_json::Value::Array(values) => Value::Table(lua.create_sequence_from(
+            values.iter().map(|value| to_lua(lua, value)).collect::<mlua::Result<Vec<_>>>()?
+        )?),
+        serde_json::Value::Object(map) => Value::Table(lua.create_table_from(
+            map.iter().map(|(key, value)| Ok((key.as_str(), to_lua(lua, value)?))).collect::<mlua::Result<Vec<_>>>()?
+        )?),
+    })
+}
+
+/// The message of a Lua error, without the stack traceback.
+fn describe(error: &mlua::Error) -> String {
+    match error {
+        mlua::Error::CallbackError { cause, .. } => describe(cause),
+        mlua::Error::RuntimeError(message) | mlua::Error::SyntaxError { message, .. } => {
+            message.split("\nstack traceback:").next().unwrap_or(message).to_owned()
+        }
+        mlua::Error::MemoryError(_) => "the script ran out of memory".to_owned(),
+        error => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::tests::TestEditor;
+
+    fn plugin(source: &str) -> LuaPlugin {
+        LuaPlugin::new(INIT_SCRIPT_ID, source).unwrap()
+    }
+
+    #[test]
+    fn commands_run_their_function() {
+        let mut editor = TestEditor::default();
+        let mut plugin = plugin(r#"
+            fexc.command("greet", "Greet", function() fexc.log("hello from " .. fexc.active_document().text) end)
+        "#);
+
+        plugin.on_load(&mut editor.context(INIT_SCRIPT_ID, None));
+        assert!(editor.commands.iter().any(|c| c.id == "init.lua.greet"));
+
+        plugin.run_command(&mut editor.context(INIT_SCRIPT_ID, None), "greet");
+        assert_eq!(editor.log, [format!("hello from {}", editor.documents.active().text)]);
+    }
+
+    #[test]
+    fn keys_and_snippets_are_registered() {
+        let mut editor = TestEditor::default();
+        let mut plugin = plugin(r#"
+            fexc.bind("Ctrl+Shift+K", "file.save")
+            fexc.bind("Alt+F2", function() fexc.log("pressed") end)
+            fexc.command("greet", "Greet", function() end, "Ctrl+G")
+            fexc.snippet("main", "main = $0")
+        "#);
+
+        plugin.on_load(&mut editor.context(INIT_SCRIPT_ID, None));
+
+        let shortcut = |id: &str| editor.commands.iter().find(|c| c.id == id).and_then(|c| editor.commands.shortcut(c));
+        assert_eq!(shortcut("file.save"), parse_shortcut("Ctrl+Shift+K"));
+        assert_eq!(shortcut("init.lua.key.Alt+F2"), parse_shortcut("Alt+F2"));
+        assert_eq!(shortcut("init.lua.greet"), parse_shortcut("Ctrl+G"));
+        assert_eq!(editor.snippets.expand("main", 4).unwrap().text, "main = ");
+
+        plugin.run_command(&mut editor.context(INIT_SCRIPT_ID, None), "key.Alt+F2");
+        assert_eq!(editor.log, ["pressed"]);
+    }
+
+    #[test]
+    fn listeners_receive_matching_events() {
+        let mut editor = TestEditor::default();
+        let mut plugin = plugin(r#"
+            fexc.on("document-saved", function(event) fexc.log(event.kind .. " " .. event.path) end, "*.hs")
+            fexc.on({}, function(event) fexc.log("any " .. event.kind) end)
+        "#);
+
+        plugin.on_load(&mut editor.context(INIT_SCRIPT_ID, None));
+        assert_eq!(editor.subscriptions.len(), 2);
+
+        for path in ["/p/Main.hs", "/p/app.cabal"] {
+            plugin.on_event(&mut editor.context(INIT_SCRIPT_ID, None), &Event::DocumentSaved { path: path.into() });
+        }
+        plugin.on_event(&mut editor.context(INIT_SCRIPT_ID, None), &Event::ConfigChanged);
+
+        assert_eq!(editor.log, [
+            "document-saved /p/Main.hs",
+            "any document-saved",
+            "any document-saved",
+            "any config-changed",
+        ]);
+    }
+
+    #[test]
+    fn errors_are_logged() {
+        let mut editor = TestEditor::default();
+        let mut plugin = plugin(r#"
+            fexc.command("fail", "Fail", function() error("no luck") end)
+            fexc.command("deny", "Deny", function() fexc.read_file("/etc/passwd") end)
+            fexc.command("caught", "Caught", function() fexc.log(tostring(pcall(fexc.bind, "Hyper+X", "file.save"))) end)
+        "#);
+
+        plugin.on_load(&mut editor.context(INIT_SCRIPT_ID, None));
+        for command in ["fail", "deny", "caught"] {
+            plugin.run_command(&mut editor.context(INIT_SCRIPT_ID, Some(&[])), command);
+        }
+
+        assert_eq!(editor.log.len(), 3);
+        assert_eq!(editor.log[0], "init.lua:2: no luck");
+        assert!(editor.log[1].contains("ReadFiles"), "{}", editor.log[1]);
+        assert_eq!(editor.log[2], "false");
+    }
+
+    #[test]
+    fn scripts_are_stopped_after_the_time_limit() {
+        let mut editor = TestEditor::default();
+        let mut plugin = plugin(r#"
+            fexc.command("loop", "Loop", function() while true do end end)
+            fexc.command("ok", "Ok", function() fexc.log("still running") end)
+        "#);
+
+        plugin.on_load(&mut editor.context(INIT_SCRIPT_ID, None));
+        let start = Instant::now();
+        plugin.run_command(&mut editor.context(INIT_SCRIPT_ID, None), "loop");
+        assert!(start.elapsed() < HOOK_TIMEOUT * 5);
+        plugin.run_command(&mut editor.context(INIT_SCRIPT_ID, None), "ok");
+
+        assert_eq!(editor.log, [
+            format!("the script did not finish within {} ms", HOOK_TIMEOUT.as_millis()),
+            "still running".to_owned(),
+        ]);
+    }
+
+    #[test]
+    fn syntax_errors_fail_to_load() {
+        let error = LuaPlugin::new(INIT_SCRIPT_ID, "fexc.log(").err().unwrap();
+
+        assert!(describe(&error).starts_with("init.lua:1:"), "{}", describe(&error));
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+/// A piece of plugin UI. Plugins describe what to show and the host renders it with egui,
+/// reporting clicks back through [`super::Plugin::on_ui_action`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum UiNode {
+    Label { text: String },
+    Heading { text: String },
+    /// Monospace text, e.g. code or paths.
+    Code { text: String },
+    /// De-emphasized text.
+    Hint { text: String },
+    Error { text: String },
+    /// A button that sends `action` to the plugin when clicked.
+    Button { label: String, action: String },
+    Separator,
+    Spinner,
+    Horizontal { children: Vec<UiNode> },
+    Vertical { children: Vec<UiNode> },
+    Collapsing { title: String, children: Vec<UiNode> },
+    /// A scrollable column, for content that can grow long.
+    Scroll { children: Vec<UiNode> },
+}
+
+/// Where a plugin view is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ViewSlot {
+    /// A sidebar page with a toolbar button.
+    Sidebar,
+    /// A tab of the bottom panel.
+    BottomPanel,
+    /// An item on the right of the status bar.
+    StatusBar,
+}
+
+/// A view registered by a plugin.
+#[derive(Debug, Clone)]
+pub struct PluginView {
+    /// Identifies the view in [`crate::app::Page`] and [`crate::app::BottomView`] across frames.
+    pub key: u64,
+    pub plugin: String,
+    pub id: String,
+    pub slot: ViewSlot,
+    /// A phosphor icon glyph.
+    pub icon: String,
+    /// The tooltip of a sidebar button, the title of a bottom tab or page.
+    pub title: String,
+    pub content: UiNode,
+}
+
+#[derive(Default)]
+pub struct PluginViews {
+    views: Vec<PluginView>,
+    next_key: u64,
+}
+
+impl PluginViews {
+    pub fn in_slot(&self, slot: ViewSlot) -> impl Iterator<Item = &PluginView> {
+        self.views.iter().filter(move |v| v.slot == slot)
+    }
+
+    pub fn get(&self, key: u64) -> Option<&PluginView> {
+        self.views.iter().find(|v| v.key == key)
+    }
+
+    /// Adds a view, or replaces the one the plugin registered with the same id while keeping its key.
+    pub fn set(&mut self, mut view: PluginView) {
+        match self.views.iter_mut().find(|v| v.plugin == view.plugin && v.id == view.id) {
+            Some(existing) => {
+                view.key = existing.key;
+                *existing = view;
+            }
+            None => {
+                view.key = self.next_key;
+                self.next_key += 1;
+                self.views.push(view);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, plugin: &str, id: &str) {
+        self.views.retain(|v| v.plugin != plugin || v.id != id);
+    }
+
+    pub fn remove_plugin(&mut self, plugin: &str) {
+        self.views.retain(|v| v.plugin != plugin);
+    }
+}
+
+/// Renders a plugin's widget tree, returning the action of a clicked button.
+pub fn show(ui: &mut egui::Ui, node: &UiNode) -> Option<String> {
+    let mut clicked = None;
+    show_node(ui, node, &mut clicked);
+    clicked
+}
+
+fn show_node(ui: &mut egui::Ui, node: &UiNode, clicked: &mut Option<String>) {
+    match node {
+        UiNode::Label { text } => {
+            ui.label(text);
+        }
+        UiNode::Heading { text } => {
+            ui.label(egui::RichText::new(text).strong().size(18.0));
+        }
+        UiNode::Code { text } => {
+            ui.label(egui::RichText::new(text).monospace());
+        }
+        UiNode::Hint { text } => {
+            ui.label(egui::RichText::new(text).weak());
+        }
+        UiNode::Error { text } => {
+            ui.label(egui::RichText::new(text).color(ui.visuals().error_fg_color));
+        }
+        UiNode::Button { label, action } => {
+            if ui.button(label).clicked() {
+                *clicked = Some(action.clone());
+            }
+        }
+        UiNode::Separator => {
+            ui.separator();
+        }
+        UiNode::Spinner => {
+            ui.spinner();
+        }
+        UiNode::Horizontal { children } => {
+            ui.horizontal(|ui| show_children(ui, children, clicked));
+        }
+        UiNode::Vertical { children } => {
+            ui.vertical(|ui| show_children(ui, children, clicked));
+        }
+        UiNode::Collapsing { title, children } => {
+            ui.collapsing(title, |ui| show_children(ui, children, clicked));
+        }
+        UiNode::Scroll { children } => {
+            egui::ScrollArea::vertical()
+                .auto_shrink([false; 2])
+                .show(ui, |ui| show_children(ui, children, clicked));
+        }
+    }
+}
+
+fn show_children(ui: &mut egui::Ui, children: &[UiNode], clicked: &mut Option<String>) {
+    // Ids of nested widgets are salted by position so equal labels in different places do not clash
+    for (i, child) in children.iter().enumerate() {
+        ui.push_id(i, |ui| show_node(ui, child, clicked));
+    }
+}
@@ -0,0 +1,634 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use fs_err as fs;
+use thiserror::Error;
+use wasmtime::{
+    Config, Engine, Store, StoreLimits, StoreLimitsBuilder, Trap,
+    component::{Component, HasSelf, Linker},
+};
+
+use crate::{
+    commands::parse_shortcut,
+    document::Document,
+    events::{Event, EventFilter, EventKind},
+};
+
+use super::{
+    HOOK_TIMEOUT, MEMORY_LIMIT, Plugin, PluginContext,
+    ui::{UiNode, ViewSlot},
+};
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "plugin",
+        imports: { default: trappable },
+    });
+}
+
+use bindings::fexc::plugin::host;
+
+/// The first bytes of every WebAssembly binary.
+const MAGIC: &[u8] = b"\0asm";
+/// Version and layer of binaries in the component model, as opposed to `01 00 00 00` of core modules.
+const COMPONENT_VERSION: &[u8] = &[0x0d, 0x00, 0x01, 0x00];
+
+/// Roughly how many instructions a hook may run.
+const FUEL_PER_HOOK: u64 = 500_000_000;
+
+#[derive(Debug, Error)]
+pub enum WasmError {
+    #[error("Failed to read plugin: {0}")]
+    Read(io::Error),
+    #[error("{0} is not a WebAssembly binary")]
+    NotWasm(PathBuf),
+    #[error("{0} is a core WebAssembly module, but plugins have to be components")]
+    NotComponent(PathBuf),
+    #[error("{0} cannot be loaded: {1}")]
+    Invalid(PathBuf, String),
+}
+
+/// Checks that `path` holds a WebAssembly component of the `plugin` world and instantiates it.
+pub fn load(id: &str, path: &Path) -> Result<Box<dyn Plugin>, WasmError> {
+    let bytes = fs::read(path)
+        .map_err(WasmError::Read)?;
+
+    if !bytes.starts_with(MAGIC) {
+        return Err(WasmError::NotWasm(path.to_owned()));
+    }
+
+    if bytes.get(MAGIC.len()..MAGIC.len() + COMPONENT_VERSION.len()) != Some(COMPONENT_VERSION) {
+        return Err(WasmError::NotComponent(path.to_owned()));
+    }
+
+    let plugin = WasmPlugin::new(id, &bytes)
+        .map_err(|e| WasmError::Invalid(path.to_owned(), format!("{e:#}")))?;
+
+    Ok(Box::new(plugin))
+}
+
+/// A host call of the plugin, run on the UI thread with the editor's context.
+type HostCall = Box<dyn FnOnce(&mut PluginContext) + Send>;
+
+/// What the plugin's thread sends while it runs a hook.
+enum Message {
+    Host(HostCall),
+    Done(Result<(), String>),
+}
+
+enum Hook {
+    Load,
+    Unload,
+    ProjectOpen(String),
+    FileOpen(String),
+    Save(String),
+    BufferChanged(u64),
+    RunCommand(String),
+    UiAction(String, String),
+    Event(host::Event),
+}
+
+/// The store data of a plugin instance.
+struct HostState {
+    messages: mpsc::Sender<Message>,
+    limits: StoreLimits,
+}
+
+impl HostState {
+    /// Runs `call` on the UI thread, which serves host calls while it waits for the hook to finish.
+    fn with_context<R: Send + 'static>(
+        &mut self,
+        f: impl FnOnce(&mut PluginContext) -> R + Send + 'static,
+    ) -> wasmtime::Result<R> {
+        let (reply, result) = mpsc::channel();
+        let call: HostCall = Box::new(move |cx| {
+            let _ = reply.send(f(cx));
+        });
+
+        self.messages.send(Message::Host(call))
+            .map_err(|_| wasmtime::Error::msg("the editor stopped waiting for the plugin"))?;
+
+        Ok(result.recv()?)
+    }
+}
+
+impl host::Host for HostState {
+    fn log(&mut self, message: String) -> wasmtime::Result<()> {
+        self.with_context(move |cx| cx.log(&message))
+    }
+
+    fn info(&mut self, message: String) -> wasmtime::Result<()> {
+        self.with_context(move |cx| cx.info(message))
+    }
+
+    fn error(&mut self, message: String) -> wasmtime::Result<()> {
+        self.with_context(move |cx| cx.error(message))
+    }
+
+    fn active_document(&mut self) -> wasmtime::Result<Result<host::TextDocument, String>> {
+        self.with_context(|cx| cx.active_document().map(to_document).map_err(|e| e.to_string()))
+    }
+
+    fn document(&mut self, id: u64) -> wasmtime::Result<Result<Option<host::TextDocument>, String>> {
+        self.with_context(move |cx| cx.document(id).map(|doc| doc.map(to_document)).map_err(|e| e.to_string()))
+    }
+
+    fn read_file(&mut self, path: String) -> wasmtime::Result<Result<String, String>> {
+        self.with_context(move |cx| cx.read(Path::new(&path)).map_err(|e| e.to_string()))
+    }
+
+    fn open_file(&mut self, path: String) -> wasmtime::Result<Result<(), String>> {
+        self.with_context(move |cx| cx.open(Path::new(&path)).map_err(|e| e.to_string()))
+    }
+
+    fn edit_document(&mut self, id: u64, text: String) -> wasmtime::Result<Result<(), String>> {
+        self.with_context(move |cx| cx.edit(id, text).map_err(|e| e.to_string()))
+    }
+
+    fn edit_file(&mut self, path: String, text: String) -> wasmtime::Result<Result<(), String>> {
+        self.with_context(move |cx| cx.edit_file(Path::new(&path), text).map_err(|e| e.to_string()))
+    }
+
+    fn run_process(
+        &mut self,
+        program: String,
+        args: Vec<String>,
+        input: String,
+        cwd: Option<String>,
+    ) -> wasmtime::Result<Result<host::ProcessOutput, String>> {
+        self.with_context(move |cx| {
+            let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+            cx.run_process(&program, &args, input, cwd.as_deref().map(Path::new))
+                .map(|output| host::ProcessOutput {
+                    status: output.status.code(),
+                    stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+                    stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+                })
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn write_to_terminal(&mut self, text: String) -> wasmtime::Result<Result<(), String>> {
+        self.with_context(move |cx| cx.write_to_terminal(&text).map_err(|e| e.to_string()))
+    }
+
+    fn settings(&mut self) -> wasmtime::Result<String> {
+        self.with_context(|cx| toml::to_string(cx.config()).unwrap_or_default())
+    }
+
+    fn update_settings(&mut self, patch: String) -> wasmtime::Result<Result<(), String>> {
+        self.with_context(move |cx| cx.update_settings(&patch).map_err(|e| e.to_string()))
+    }
+
+    fn register_command(&mut self, id: String, title: String, shortcut: Option<String>) -> wasmtime::Result<()> {
+        self.with_context(move |cx| {
+            let parsed = shortcut.as_deref().and_then(parse_shortcut);
+            if let Some(shortcut) = shortcut.filter(|_| parsed.is_none()) {
+                cx.error(format!("Invalid shortcut {shortcut:?} for command {id}"));
+            }
+            cx.register_command(&id, &title, parsed);
+        })
+    }
+
+    fn subscribe(&mut self, kinds: Vec<host::EventKind>, glob: Option<String>) -> wasmtime::Result<()> {
+        let kinds = kinds.into_iter().map(event_kind).collect();
+        self.with_context(move |cx| cx.subscribe(EventFilter { kinds, glob }))
+    }
+
+    fn set_view(
+        &mut self,
+        slot: host::ViewSlot,
+        id: String,
+        icon: String,
+        title: String,
+        content: String,
+    ) -> wasmtime::Result<Result<(), String>> {
+        let content = match serde_json::from_str::<UiNode>(&content) {
+            Ok(content) => content,
+            Err(e) => return Ok(Err(format!("Invalid view content: {e}"))),
+        };
+
+        self.with_context(move |cx| {
+            cx.set_view(view_slot(slot), &id, &icon, &title, content);
+            Ok(())
+        })
+    }
+
+    fn remove_view(&mut self, id: String) -> wasmtime::Result<()> {
+        self.with_context(move |cx| cx.remove_view(&id))
+    }
+}
+
+fn to_document(doc: &Document) -> host::TextDocument {
+    host::TextDocument {
+        id: doc.id,
+        path: doc.path.as_ref().map(|p| p.to_string_lossy().into_owned()),
+        text: doc.text.clone(),
+        version: doc.version,
+        cursor: doc.cursor as u64,
+    }
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn to_event(event: &Event) -> host::Event {
+    let document = |doc: &u64, path: &Option<PathBuf>| host::DocumentEvent {
+        doc: *doc,
+        path: path.as_deref().map(path_string),
+    };
+
+    match event {
+        Event::DocumentOpened { doc, path } => host::Event::DocumentOpened(document(doc, path)),
+        Event::DocumentChanged { doc, path } => host::Event::DocumentChanged(document(doc, path)),
+        Event::DocumentSaved { path } => host::Event::DocumentSaved(path_string(path)),
+        Event::DocumentClosed { doc, path } => host::Event::DocumentClosed(document(doc, path)),
+        Event::CursorMoved { doc, path, cursor } => host::Event::CursorMoved(host::CursorEvent {
+            doc: *doc,
+            path: path.as_deref().map(path_string),
+            cursor: *cursor as u64,
+        }),
+        Event::ProjectOpened { root } => host::Event::ProjectOpened(path_string(root)),
+        Event::BuildFinished { task, success } => host::Event::BuildFinished(host::BuildEvent {
+            task: task.clone(),
+            success: *success,
+        }),
+        Event::DiagnosticsUpdated { path } => host::Event::DiagnosticsUpdated(path_string(path)),
+        Event::ConfigChanged => host::Event::ConfigChanged,
+        Event::ThemeChanged { theme: egui::Theme::Dark } => host::Event::ThemeChanged(host::Theme::Dark),
+        Event::ThemeChanged { theme: egui::Theme::Light } => host::Event::ThemeChanged(host::Theme::Light),
+    }
+}
+
+fn event_kind(kind: host::EventKind) -> EventKind {
+    match kind {
+        host::EventKind::DocumentOpened => EventKind::DocumentOpened,
+        host::EventKind::DocumentChanged => EventKind::DocumentChanged,
+        host::EventKind::DocumentSaved => EventKind::DocumentSaved,
+        host::EventKind::DocumentClosed => EventKind::DocumentClosed,
+        host::EventKind::CursorMoved => EventKind::CursorMoved,
+        host::EventKind::ProjectOpened => EventKind::ProjectOpened,
+        host::EventKind::BuildFinished => EventKind::BuildFinished,
+        host::EventKind::DiagnosticsUpdated => EventKind::DiagnosticsUpdated,
+        host::EventKind::ConfigChanged => EventKind::ConfigChanged,
+        host::EventKind::ThemeChanged => EventKind::ThemeChanged,
+    }
+}
+
+fn view_slot(slot: host::ViewSlot) -> ViewSlot {
+    match slot {
+        host::ViewSlot::Sidebar => ViewSlot::Sidebar,
+        host::ViewSlot::BottomPanel => ViewSlot::BottomPanel,
+        host::ViewSlot::StatusBar => ViewSlot::StatusBar,
+    }
+}
+
+/// A short reason for a failed hook, without the WebAssembly backtrace.
+fn describe(error: &wasmtime::Error) -> String {
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => "the plugin ran out of fuel".to_owned(),
+        Some(Trap::Interrupt) => format!("the plugin did not finish within {} ms", HOOK_TIMEOUT.as_millis()),
+        Some(trap) => format!("the plugin trapped: {trap}"),
+        None => error.root_cause().to_string(),
+    }
+}
+
+/// Runs the hooks of one plugin instance on its own thread until the plugin is dropped or fails.
+fn run_plugin(engine: Engine, pre: bindings::PluginPre<HostState>, hooks: mpsc::Receiver<Hook>, messages: mpsc::Sender<Message>) {
+    let state = HostState {
+        messages: messages.clone(),
+        limits: StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build(),
+    };
+    let mut store = Store::new(&engine, state);
+    store.limiter(|state| &mut state.limits);
+
+    let mut instance = None;
+
+    for hook in hooks {
+        let result = call_hook(&mut store, &mut instance, &pre, hook).map_err(|e| describe(&e));
+        let failed = result.is_err();
+
+        // A trapped instance cannot be entered again
+        if messages.send(Message::Done(result)).is_err() || failed {
+            return;
+        }
+    }
+}
+
+fn call_hook(
+    store: &mut Store<HostState>,
+    instance: &mut Option<bindings::Plugin>,
+    pre: &bindings::PluginPre<HostState>,
+    hook: Hook,
+) -> wasmtime::Result<()> {
+    store.set_fuel(FUEL_PER_HOOK)?;
+    store.set_epoch_deadline(1);
+
+    // Instantiating runs the component's start functions, so it is limited like the first hook
+    let plugin = match instance {
+        Some(plugin) => plugin,
+        None => instance.insert(pre.instantiate(&mut *store)?),
+    };
+
+    match hook {
+        Hook::Load => plugin.call_on_load(store),
+        Hook::Unload => plugin.call_on_unload(store),
+        Hook::ProjectOpen(root) => plugin.call_on_project_open(store, &root),
+        Hook::FileOpen(path) => plugin.call_on_file_open(store, &path),
+        Hook::Save(path) => plugin.call_on_save(store, &path),
+        Hook::BufferChanged(doc) => plugin.call_on_buffer_changed(store, doc),
+        Hook::RunCommand(command) => plugin.call_run_command(store, &command),
+        Hook::UiAction(view, action) => plugin.call_on_ui_action(store, &view, &action),
+        Hook::Event(event) => plugin.call_on_event(store, &event),
+    }
+}
+
+/// A plugin compiled from a WebAssembly component. Its hooks run on a thread of their own with
+/// fuel, memory and time limits, calling back into the editor through the UI thread.
+pub struct WasmPlugin {
+    id: String,
+    engine: Engine,
+    hooks: mpsc::Sender<Hook>,
+    messages: mpsc::Receiver<Message>,
+    /// Why the plugin stopped, once a hook failed.
+    crash: Option<String>,
+}
+
+impl WasmPlugin {
+    fn new(id: &str, bytes: &[u8]) -> wasmtime::Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true).epoch_interruption(true);
+
+        let engine = Engine::new(&config)?;
+        let component = Component::from_binary(&engine, bytes)?;
+
+        let mut linker = Linker::new(&engine);
+        host::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state)?;
+
+        // Checks the imports and exports against the `plugin` world before anything runs
+        let pre = bindings::PluginPre::new(linker.instantiate_pre(&component)?)?;
+
+        let (hooks, hook_receiver) = mpsc::channel();
+        let (message_sender, messages) = mpsc::channel();
+        let plugin_engine = engine.clone();
+
+        thread::Builder::new()
+            .name(format!("plugin {id}"))
+            .spawn(move || run_plugin(plugin_engine, pre, hook_receiver, message_sender))?;
+
+        Ok(WasmPlugin {
+            id: id.to_owned(),
+            engine,
+            hooks,
+            messages,
+            crash: None,
+        })
+    }
+
+    /// Runs a hook on the plugin's thread, serving its host calls until it finishes or is interrupted.
+    fn call(&mut self, cx: &mut PluginContext, hook: Hook) {
+        if self.crash.is_some() {
+            return;
+        }
+
+        if self.hooks.send(hook).is_err() {
+            self.crash = Some("the plugin's thread exited".to_owned());
+            return;
+        }
+
+        let mut remaining = HOOK_TIMEOUT;
+
+        loop {
+            let waiting = Instant::now();
+
+            match self.messages.recv_timeout(remaining) {
+                Ok(Message::Host(call)) => {
+                    remaining = remaining.saturating_sub(waiting.elapsed());
+                    call(cx);
+                }
+                Ok(Message::Done(result)) => {
+                    self.crash = result.err();
+                    return;
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    // The plugin traps at its next loop iteration or call and reports that as done
+                    self.engine.increment_epoch();
+                    remaining = Duration::MAX;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.crash = Some("the plugin's thread exited".to_owned());
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Plugin for WasmPlugin {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn on_load(&mut self, cx: &mut PluginContext) {
+        self.call(cx, Hook::Load);
+    }
+
+    fn on_unload(&mut self, cx: &mut PluginContext) {
+        self.call(cx, Hook::Unload);
+    }
+
+    fn on_project_open(&mut self, cx: &mut PluginContext, root: &Path) {
+        self.call(cx, Hook::ProjectOpen(path_string(root)));
+    }
+
+    fn on_file_open(&mut self, cx: &mut PluginContext, path: &Path) {
+        self.call(cx, Hook::FileOpen(path_string(path)));
+    }
+
+    fn on_save(&mut self, cx: &mut PluginContext, path: &Path) {
+        self.call(cx, Hook::Save(path_string(path)));
+    }
+
+    fn on_buffer_changed(&mut self, cx: &mut PluginContext, doc: u64) {
+        self.call(cx, Hook::BufferChanged(doc));
+    }
+
+    fn run_command(&mut self, cx: &mut PluginContext, command: &str) {
+        self.call(cx, Hook::RunCommand(command.to_owned()));
+    }
+
+    fn on_ui_action(&mut self, cx: &mut PluginContext, view: &str, action: &str) {
+        self.call(cx, Hook::UiAction(view.to_owned(), action.to_owned()));
+    }
+
+    fn on_event(&mut self, cx: &mut PluginContext, event: &Event) {
+        self.call(cx, Hook::Event(to_event(event)));
+    }
+
+    fn crashed(&mut self) -> Option<String> {
+        self.crash.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::tests::TestEditor;
+
+    /// Declares the host types the hooks use, so a component can import only what it needs.
+    const HOST_TYPES: &str = r#"
+        (type $document-event (record (field "doc" u64) (field "path" (option string))))
+        (export "document-event" (type $document-event' (eq $document-event)))
+        (type $cursor-event (record (field "doc" u64) (field "path" (option string)) (field "cursor" u64)))
+        (export "cursor-event" (type $cursor-event' (eq $cursor-event)))
+        (type $build-event (record (field "task" string) (field "success" bool)))
+        (export "build-event" (type $build-event' (eq $build-event)))
+        (type $theme (enum "dark" "light"))
+        (export "theme" (type $theme' (eq $theme)))
+        (type $event (variant
+            (case "document-opened" $document-event')
+            (case "document-changed" $document-event')
+            (case "document-saved" string)
+            (case "document-closed" $document-event')
+            (case "cursor-moved" $cursor-event')
+            (case "project-opened" string)
+            (case "build-finished" $build-event')
+            (case "diagnostics-updated" string)
+            (case "config-changed")
+            (case "theme-changed" $theme')))
+        (export "event" (type $event' (eq $event)))
+        (export "log" (func (param "message" string)))
+        (export "register-command" (func (param "id" string) (param "title" string) (param "shortcut" (option string))))
+    "#;
+
+    /// A plugin that logs on load, registers `greet` with the shortcut `Ctrl+G`, and loops forever on any other command.
+    /// `$MEMORY` is the number of pages of its memory.
+    const PLUGIN: &str = r#"
+        (component
+          (import "fexc:plugin/host@1.0.0" (instance $host $HOST_TYPES))
+          (alias export $host "event" (type $event))
+          (alias export $host "log" (func $log))
+          (alias export $host "register-command" (func $register-command))
+
+          (core module $memory (memory (export "memory") $MEMORY))
+          (core instance $memory (instantiate $memory))
+          (core func $log (canon lower (func $log) (memory (core memory $memory "memory"))))
+          (core func $register-command (canon lower (func $register-command) (memory (core memory $memory "memory"))))
+          (core instance $host (export "log" (func $log)) (export "register-command" (func $register-command)))
+
+          (core module $plugin
+            (import "host" "log" (func $log (param i32 i32)))
+            (import "host" "register-command" (func $register-command (param i32 i32 i32 i32 i32 i32 i32)))
+            (import "env" "memory" (memory 1))
+            (data (i32.const 0) "loadedgreetGreetCtrl+G")
+            (global $heap (mut i32) (i32.const 1024))
+            (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+              (global.get $heap)
+              (global.set $heap (i32.add (global.get $heap) (local.get 3))))
+            (func (export "on-load")
+              (call $log (i32.const 0) (i32.const 6))
+              (call $register-command (i32.const 6) (i32.const 5) (i32.const 11) (i32.const 5) (i32.const 1) (i32.const 16) (i32.const 6)))
+            (func (export "run-command") (param $ptr i32) (param $len i32)
+              (if (i32.ne (local.get $len) (i32.const 5)) (then (loop $forever (br $forever))))
+              (call $log (local.get $ptr) (local.get $len)))
+            (func (export "on-unload"))
+            (func (export "path") (param i32 i32))
+            (func (export "on-buffer-changed") (param i64))
+            (func (export "on-ui-action") (param i32 i32 i32 i32))
+            (func (export "on-event") (param i32 i64 i32 i32 i32 i64)))
+          (core instance $plugin (instantiate $plugin (with "host" (instance $host)) (with "env" (instance $memory))))
+
+          (func (export "on-load") (canon lift (core func $plugin "on-load")))
+          (func (export "on-unload") (canon lift (core func $plugin "on-unload")))
+          (func (export "on-project-open") (param "root" string)
+            (canon lift (core func $plugin "path") (memory (core memory $memory "memory")) (realloc (core func $plugin "realloc"))))
+          (func (export "on-file-open") (param "path" string)
+            (canon lift (core func $plugin "path") (memory (core memory $memory "memory")) (realloc (core func $plugin "realloc"))))
+          (func (export "on-save") (param "path" string)
+            (canon lift (core func $plugin "path") (memory (core memory $memory "memory")) (realloc (core func $plugin "realloc"))))
+          (func (export "on-buffer-changed") (param "doc" u64) (canon lift (core func $plugin "on-buffer-changed")))
+          (func (export "run-command") (param "command" string)
+            (canon lift (core func $plugin "run-command") (memory (core memory $memory "memory")) (realloc (core func $plugin "realloc"))))
+          (func (export "on-ui-action") (param "view" string) (param "action" string)
+            (canon lift (core func $plugin "on-ui-action") (memory (core memory $memory "memory")) (realloc (core func $plugin "realloc"))))
+          (func (export "on-event") (param "event" $event)
+            (canon lift (core func $plugin "on-event") (memory (core memory $memory "memory")) (realloc (core func $plugin "realloc")))))
+    "#;
+
+    fn plugin(memory_pages: usize) -> WasmPlugin {
+        let text = PLUGIN.replace("$HOST_TYPES", HOST_TYPES).replace("$MEMORY", &memory_pages.to_string());
+        let bytes = wat::parse_str(text).unwrap();
+        WasmPlugin::new("test", &bytes).unwrap()
+    }
+
+    #[test]
+    fn hooks_call_back_into_the_editor() {
+        let mut editor = TestEditor::default();
+        let mut plugin = plugin(1);
+
+        plugin.on_load(&mut editor.context("test", Some(&[])));
+        plugin.run_command(&mut editor.context("test", Some(&[])), "greet");
+        plugin.on_event(&mut editor.context("test", Some(&[])), &Event::DocumentSaved { path: "/a.hs".into() });
+
+        assert_eq!(plugin.crashed(), None);
+        assert_eq!(editor.log, ["loaded", "greet"]);
+        let greet = editor.commands.plugin_commands().find(|c| c.id == "test.greet").unwrap();
+        assert_eq!(greet.shortcut, parse_shortcut("Ctrl+G"));
+    }
+
+    #[test]
+    fn looping_plugin_is_stopped() {
+        let mut editor = TestEditor::default();
+        let mut plugin = plugin(1);
+        plugin.on_load(&mut editor.context("test", Some(&[])));
+
+        let start = Instant::now();
+        plugin.run_command(&mut editor.context("test", Some(&[])), "spin");
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let crash = plugin.crashed().unwrap();
+        assert!(crash.contains("did not finish") || crash.contains("fuel"), "{crash}");
+
+        // A trapped instance is not entered again
+        plugin.run_command(&mut editor.context("test", Some(&[])), "greet");
+        assert_eq!(editor.log, ["loaded"]);
+    }
+
+    #[test]
+    fn memory_is_limited() {
+        let mut editor = TestEditor::default();
+        let mut plugin = plugin(MEMORY_LIMIT / 65536 + 1);
+
+        plugin.on_load(&mut editor.context("test", Some(&[])));
+
+        assert!(plugin.crashed().is_some());
+        assert!(editor.log.is_empty());
+    }
+
+    #[test]
+    fn components_of_another_world_are_rejected() {
+        let bytes = wat::parse_str("(component)").unwrap();
+        assert!(WasmPlugin::new("test", &bytes).is_err());
+    }
+
+    #[test]
+    fn core_modules_are_rejected() {
+        let path = std::env::temp_dir().join(format!("fexc-wasm-{}.wasm", std::process::id()));
+        fs::write(&path, wat::parse_str("(module)").unwrap()).unwrap();
+
+        assert!(matches!(load("test", &path), Err(WasmError::NotComponent(_))));
+
+        fs::write(&path, "not wasm").unwrap();
+        assert!(matches!(load("test", &path), Err(WasmError::NotWasm(_))));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
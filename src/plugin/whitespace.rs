@@ -0,0 +1,39 @@
+use super::{Plugin, PluginContext};
+
+/// A built-in plugin that strips trailing whitespace from the active document.
+pub struct WhitespacePlugin;
+
+impl Plugin for WhitespacePlugin {
+    fn id(&self) -> &str {
+        "whitespace"
+    }
+
+    fn on_load(&mut self, cx: &mut PluginContext) {
+        cx.register_command("trim-trailing-whitespace", "Trim Trailing Whitespace", None);
+    }
+
+    fn run_command(&mut self, cx: &mut PluginContext, command: &str) {
+        if command != "trim-trailing-whitespace" {
+            return;
+        }
+
+        let doc = match cx.active_document() {
+            Ok(doc) => doc,
+            Err(e) => return cx.error(e.to_string()),
+        };
+        let mut text = doc.text.lines().map(str::trim_end).collect::<Vec<_>>().join("\n");
+        if doc.text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        if text == doc.text {
+            cx.info("no trailing whitespace found");
+            return;
+        }
+
+        let id = doc.id;
+        if let Err(e) = cx.edit(id, text) {
+            cx.error(e.to_string());
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path,
+    process::{Command, Output, Stdio},
+    sync::mpsc::Sender,
+    thread,
+};
+
+/// Runs `program`, feeding `input` to its stdin and collecting its output.
+/// Stdin is written on a separate thread so a program that produces output early cannot deadlock.
+pub fn pipe(program: &str, args: &[&str], input: String, cwd: Option<&Path>) -> io::Result<Output> {
+    let mut command = Command::new(program);
+    command.args(args);
+
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let mut stdin = child.stdin.take()
+        .ok_or_else(|| io::Error::other("stdin is not available"))?;
+
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+
+    let written = writer.join()
+        .map_err(|_| io::Error::other("stdin writer panicked"))?;
+
+    // A program may exit without reading all of its input; its exit status tells whether that was a failure
+    match written {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e),
+        _ => Ok(output),
+    }
+}
+
+/// Forwards the lines of `stream` as events on a background thread, sending `end` once it closes.
+pub fn read_lines<T: Send + 'static>(
+    stream: impl Read + Send + 'static,
+    sender: Sender<T>,
+    event: fn(String) -> T,
+    end: Option<T>,
+    ctx: egui::Context,
+) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buf = vec![];
+
+        while reader.read_until(b'\n', &mut buf).is_ok_and(|n| n > 0) {
+            let line = String::from_utf8_lossy(&buf).trim_end_matches(['\r', '\n']).to_owned();
+            buf.clear();
+
+            if sender.send(event(line)).is_err() {
+                return;
+            }
+            ctx.request_repaint();
+        }
+
+        if let Some(end) = end {
+            let _ = sender.send(end);
+            ctx.request_repaint();
+        }
+    });
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feeds_stdin() {
+        let output = pipe("cat", &[], "hello".to_owned(), None).unwrap();
+        assert_eq!(output.stdout, b"hello");
+    }
+
+    #[test]
+    fn ignores_input_the_program_does_not_read() {
+        let output = pipe("true", &[], "x".repeat(1 << 20), None).unwrap();
+        assert!(output.status.success());
+
+        let output = pipe("false", &[], "x".repeat(1 << 20), None).unwrap();
+        assert!(!output.status.success());
+    }
+}
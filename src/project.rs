@@ -0,0 +1,640 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use thiserror::Error;
+use yaml_rust2::{Yaml, YamlLoader};
+
+use crate::{
+    app::Fexc,
+    cabal::{CabalFile, Field},
+    events::Event,
+    tasks::Task,
+};
+
+/// The tool that builds the project and starts its REPL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildTool {
+    Cabal,
+    Stack,
+    /// Loose modules without a package, loaded into plain GHCi.
+    Ghc,
+}
+
+impl BuildTool {
+    pub fn program(self) -> &'static str {
+        match self {
+            BuildTool::Cabal => "cabal",
+            BuildTool::Stack => "stack",
+            BuildTool::Ghc => "ghc",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentKind {
+    Library,
+    Executable,
+    TestSuite,
+    Benchmark,
+}
+
+impl ComponentKind {
+    pub const ALL: [ComponentKind; 4] = [
+        ComponentKind::Library,
+        ComponentKind::Executable,
+        ComponentKind::TestSuite,
+        ComponentKind::Benchmark,
+    ];
+
+    pub fn i18n_key(self) -> &'static str {
+        match self {
+            ComponentKind::Library => "component_library",
+            ComponentKind::Executable => "component_executable",
+            ComponentKind::TestSuite => "component_test_suite",
+            ComponentKind::Benchmark => "component_benchmark",
+        }
+    }
+
+    pub fn from_cabal_stanza(stanza: &str) -> Option<Self> {
+        match stanza {
+            "library" => Some(ComponentKind::Library),
+            "executable" => Some(ComponentKind::Executable),
+            "test-suite" => Some(ComponentKind::TestSuite),
+            "benchmark" => Some(ComponentKind::Benchmark),
+            _ => None,
+        }
+    }
+}
+
+/// A library, executable, test suite or benchmark of a package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub kind: ComponentKind,
+    pub name: String,
+    pub source_dirs: Vec<String>,
+    pub main_is: Option<String>,
+    pub default_extensions: Vec<String>,
+    /// Names of the packages the component depends on, without version bounds.
+    pub dependencies: Vec<String>,
+}
+
+impl Component {
+    fn new(kind: ComponentKind, name: &str) -> Self {
+        Component {
+            kind,
+            name: name.to_owned(),
+            source_dirs: vec![],
+            main_is: None,
+            default_extensions: vec![],
+            dependencies: vec![],
+        }
+    }
+}
+
+/// A package described by a `.cabal` file or hpack's `package.yaml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    pub version: Option<String>,
+    pub dir: PathBuf,
+    pub manifest: PathBuf,
+    pub components: Vec<Component>,
+}
+
+/// The packages and components of the open project, and the files they were read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    pub root: PathBuf,
+    pub tool: BuildTool,
+    pub cabal_project: Option<PathBuf>,
+    pub stack_yaml: Option<PathBuf>,
+    pub hie_yaml: Option<PathBuf>,
+    /// The cradle `hie.yaml` sets up for the language server, e.g. `cabal` or `stack`.
+    pub hie_cradle: Option<String>,
+    pub packages: Vec<Package>,
+}
+
+impl Project {
+    /// Reads the project files in `root`; packages that fail to parse are logged and skipped.
+    pub fn load(root: &Path) -> Project {
+        let existing = |name: &str| Some(root.join(name)).filter(|p| p.is_file());
+
+        let cabal_project = existing("cabal.project");
+        let stack_yaml = existing("stack.yaml");
+        let hie_yaml = existing("hie.yaml");
+
+        let hie_cradle = hie_yaml.as_deref()
+            .and_then(|path| read_yaml(path).inspect_err(|e| log::error!("{e}")).ok())
+            .and_then(|yaml| match &yaml["cradle"] {
+                Yaml::Hash(cradle) => cradle.keys().next().and_then(Yaml::as_str).map(str::to_owned),
+                _ => None,
+            });
+
+        // Package locations, relative to the root and possibly containing `*` wildcards
+        let patterns = if let Some(path) = &stack_yaml {
+            read_yaml(path)
+                .map(|yaml| strings(&yaml["packages"]))
+                .inspect_err(|e| log::error!("{e}"))
+                .ok()
+                .filter(|p| !p.is_empty())
+                .unwrap_or_else(|| vec![".".to_owned()])
+        } else if let Some(path) = &cabal_project {
+            fs::read_to_string(path)
+                .map(|text| cabal_project_packages(&text))
+                .inspect_err(|e| log::error!("{e}"))
+                .unwrap_or_default()
+        } else {
+            vec![".".to_owned()]
+        };
+
+        let mut packages = vec![];
+        for path in patterns.iter().flat_map(|pattern| expand(root, pattern)) {
+            let manifest = if path.is_file() { Some(path) } else { find_manifest(&path) };
+
+            match manifest.map(|m| load_package(&m)) {
+                Some(Ok(package)) if !packages.iter().any(|p: &Package| p.manifest == package.manifest) => packages.push(package),
+                Some(Err(e)) => log::error!("{e}"),
+                _ => {}
+            }
+        }
+
+        let tool = match hie_cradle.as_deref() {
+            Some("stack") => BuildTool::Stack,
+            Some("cabal") => BuildTool::Cabal,
+            _ if stack_yaml.is_some() => BuildTool::Stack,
+            _ if cabal_project.is_some() || !packages.is_empty() => BuildTool::Cabal,
+            _ => BuildTool::Ghc,
+        };
+
+        Project {
+            root: root.to_owned(),
+            tool,
+            cabal_project,
+            stack_yaml,
+            hie_yaml,
+            hie_cradle,
+            packages,
+        }
+    }
+
+    /// The project a file belongs to: the nearest directory with a `cabal.project` or `stack.yaml`,
+    /// or else the nearest one with a package manifest.
+    pub fn find_root(file: &Path) -> Option<PathBuf> {
+        let dirs = file.ancestors().skip(1);
+
+        dirs.clone()
+            .find(|dir| dir.join("cabal.project").is_file() || dir.join("stack.yaml").is_file())
+            .or_else(|| dirs.clone().find(|dir| find_manifest(dir).is_some()))
+            .map(Path::to_owned)
+    }
+
+    /// Whether `path` is one of the files the model is read from, so it has to be reloaded when it changes.
+    pub fn is_project_file(path: &Path) -> bool {
+        path.extension().is_some_and(|e| e == "cabal")
+            || path.file_name().is_some_and(|n| ["cabal.project", "stack.yaml", "package.yaml", "hie.yaml"].iter().any(|f| n == *f))
+    }
+
+    pub fn components(&self) -> impl Iterator<Item = (&Package, &Component)> {
+        self.packages.iter().flat_map(|p| p.components.iter().map(move |c| (p, c)))
+    }
+
+    /// `stack ghci` or `cabal repl` if the project uses them, plain `ghci` otherwise.
+    pub fn repl_command(&self) -> Vec<String> {
+        let command: &[&str] = match self.tool {
+            BuildTool::Stack => &["stack", "ghci"],
+            BuildTool::Cabal => &["cabal", "repl"],
+            BuildTool::Ghc => &["ghci"],
+        };
+
+        command.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Runs a test suite, passing `args` on to the test program.
+    pub fn test_command(&self, package: &str, suite: &str, args: &[String]) -> Vec<String> {
+        let mut command = vec![];
+
+        if self.tool == BuildTool::Stack {
+            command.extend(["stack".to_owned(), "test".to_owned(), format!("{package}:test:{suite}")]);
+            for arg in args {
+                // stack splits the argument into words itself
+                command.extend(["--ta".to_owned(), format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))]);
+            }
+        } else {
+            // Qualified with the package, since a suite name alone is ambiguous in multi-package projects
+            command.extend(["cabal".to_owned(), "test".to_owned(), format!("{package}:test:{suite}"), "--test-show-details=direct".to_owned()]);
+            for arg in args {
+                command.push(format!("--test-option={arg}"));
+            }
+        }
+
+        command
+    }
+
+    /// Whether a task fits the project, i.e. it does not run cabal in a stack project or the other way around.
+    pub fn supports(&self, task: &Task) -> bool {
+        match task.command.first().map(String::as_str) {
+            Some(program @ ("cabal" | "stack")) => program == self.tool.program(),
+            _ => true,
+        }
+    }
+}
+
+fn read_yaml(path: &Path) -> Result<Yaml, ProjectError> {
+    let text = fs::read_to_string(path)
+        .map_err(ProjectError::Read)?;
+
+    let documents = YamlLoader::load_from_str(&text)
+        .map_err(|e| ProjectError::Yaml(path.to_owned(), e))?;
+
+    Ok(documents.into_iter().next().unwrap_or(Yaml::Null))
+}
+
+/// The strings of a YAML scalar, list, or the keys of a map such as hpack's dependency map.
+fn strings(yaml: &Yaml) -> Vec<String> {
+    match yaml {
+        Yaml::String(s) => vec![s.clone()],
+        Yaml::Real(s) => vec![s.clone()],
+        Yaml::Integer(i) => vec![i.to_string()],
+        Yaml::Array(items) => items.iter().flat_map(strings).collect(),
+        Yaml::Hash(map) => map.keys().flat_map(strings).collect(),
+        _ => vec![],
+    }
+}
+
+/// Package names of dependency specifications like `base >= 4.14 && < 5`.
+fn dependency_names(specs: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut names = Vec::<String>::new();
+
+    for spec in specs {
+        for dependency in spec.split(',') {
+            let name = dependency.trim().split(|c: char| c.is_whitespace() || "<>=^:{".contains(c)).next().unwrap_or_default();
+            if !name.is_empty() && !names.iter().any(|n| n == name) {
+                names.push(name.to_owned());
+            }
+        }
+    }
+
+    names
+}
+
+fn cabal_project_packages(text: &str) -> Vec<String> {
+    let fields = field_values(&CabalFile::parse(text).fields);
+
+    let packages = fields.iter()
+        .filter(|(key, _)| key == "packages")
+        .flat_map(|(_, value)| value.split_whitespace().map(str::to_owned))
+        .collect::<Vec<_>>();
+
+    if packages.is_empty() { vec!["./*.cabal".to_owned()] } else { packages }
+}
+
+/// Expands a package location with `*` wildcards into the paths it matches.
+fn expand(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut paths = vec![root.to_owned()];
+
+    for part in Path::new(pattern).components() {
+        let part = part.as_os_str().to_string_lossy();
+
+        paths = match part.split_once('*') {
+            Some((prefix, suffix)) => paths.iter()
+                .filter_map(|dir| fs::read_dir(dir).ok())
+                .flat_map(|entries| entries.flatten().map(|e| e.path()))
+                .filter(|path| path.file_name().is_some_and(|name| {
+                    let name = name.to_string_lossy();
+                    name.len() >= prefix.len() + suffix.len() && name.starts_with(prefix) && name.ends_with(suffix)
+                }))
+                .collect(),
+            None => paths.iter().map(|dir| dir.join(&*part)).filter(|path| path.exists()).collect(),
+        };
+    }
+
+    paths.sort();
+    paths
+}
+
+/// The `.cabal` file of a package directory, or its `package.yaml` if there is none.
+fn find_manifest(dir: &Path) -> Option<PathBuf> {
+    let cabal_file = fs::read_dir(dir).ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| p.extension().is_some_and(|e| e == "cabal") && p.is_file());
+
+    cabal_file.or_else(|| Some(dir.join("package.yaml")).filter(|p| p.is_file()))
+}
+
+fn load_package(manifest: &Path) -> Result<Package, ProjectError> {
+    let dir = manifest.parent().unwrap_or(Path::new(".")).to_owned();
+
+    let (name, version, components) = if manifest.extension().is_some_and(|e| e == "cabal") {
+        let text = fs::read_to_string(manifest)
+            .map_err(ProjectError::Read)?;
+        parse_cabal(&text)
+    } else {
+        parse_hpack(&read_yaml(manifest)?)
+    };
+
+    // Packages without a name field are named after their manifest, like cabal does
+    let name = name.unwrap_or_else(|| {
+        manifest.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+    });
+
+    Ok(Package {
+        name,
+        version,
+        dir,
+        manifest: manifest.to_owned(),
+        components,
+    })
+}
+
+type PackageFields = (Option<String>, Option<String>, Vec<Component>);
+
+/// The `key: value` pairs of parsed fields.
+fn field_values(fields: &[Field]) -> Vec<(String, String)> {
+    fields.iter().map(|f| (f.name.clone(), f.value())).collect()
+}
+
+fn parse_cabal(text: &str) -> PackageFields {
+    let file = CabalFile::parse(text);
+    let top_level = field_values(&file.fields);
+    let stanzas = file.sections.iter()
+        .map(|s| (s.kind.clone(), s.name.clone(), field_values(&s.fields)))
+        .collect::<Vec<_>>();
+
+    let field = |key: &str| top_level.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+    let name = field("name");
+
+    let commons = stanzas.iter()
+        .filter(|(stanza, _, _)| stanza == "common")
+        .map(|(_, name, fields)| (name.clone(), fields.clone()))
+        .collect::<HashMap<_, _>>();
+
+    let components = stanzas.iter()
+        .filter_map(|(stanza, component_name, fields)| {
+            let kind = ComponentKind::from_cabal_stanza(stanza)?;
+            let component_name = if component_name.is_empty() { name.clone().unwrap_or_default() } else { component_name.clone() };
+
+            let mut fields_with_imports = vec![];
+            import_fields(fields, &commons, &mut fields_with_imports, 0);
+
+            let values = |key: &str| fields_with_imports.iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+                .collect::<Vec<_>>();
+            let words = |key: &str| values(key).iter()
+                .flat_map(|v| v.split(|c: char| c.is_whitespace() || c == ',').filter(|w| !w.is_empty()).map(str::to_owned).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+
+            Some(Component {
+                source_dirs: words("hs-source-dirs"),
+                main_is: values("main-is").pop(),
+                default_extensions: words("default-extensions"),
+                dependencies: dependency_names(values("build-depends")),
+                ..Component::new(kind, &component_name)
+            })
+        })
+        .collect();
+
+    (name, field("version"), components)
+}
+
+/// Adds the fields of the `common` stanzas a component imports in front of its own.
+fn import_fields(
+    fields: &[(String, String)],
+    commons: &HashMap<String, Vec<(String, String)>>,
+    result: &mut Vec<(String, String)>,
+    depth: usize,
+) {
+    for (key, value) in fields {
+        if key == "import" {
+            // Common stanzas may import each other, but never in a cycle
+            if let Some(common) = commons.get(value.trim()).filter(|_| depth < 8) {
+                import_fields(common, commons, result, depth + 1);
+            }
+        } else {
+            result.push((key.clone(), value.clone()));
+        }
+    }
+}
+
+fn parse_hpack(yaml: &Yaml) -> PackageFields {
+    let name = yaml["name"].as_str().map(str::to_owned);
+    let version = strings(&yaml["version"]).pop();
+
+    // Top-level fields are shared by all components
+    let component = |kind: ComponentKind, component_name: &str, fields: &Yaml| {
+        let merged = |key: &str| strings(&yaml[key]).into_iter().chain(strings(&fields[key])).collect::<Vec<_>>();
+
+        Component {
+            source_dirs: merged("source-dirs"),
+            main_is: fields["main"].as_str().map(str::to_owned),
+            default_extensions: merged("default-extensions"),
+            dependencies: dependency_names(merged("dependencies")),
+            ..Component::new(kind, component_name)
+        }
+    };
+
+    let mut components = vec![];
+
+    if !yaml["library"].is_badvalue() {
+        components.push(component(ComponentKind::Library, name.as_deref().unwrap_or_default(), &yaml["library"]));
+    }
+
+    for (key, kind) in [
+        ("internal-libraries", ComponentKind::Library),
+        ("executables", ComponentKind::Executable),
+        ("tests", ComponentKind::TestSuite),
+        ("benchmarks", ComponentKind::Benchmark),
+    ] {
+        let Some(map) = yaml[key].as_hash() else { continue };
+
+        for (component_name, fields) in map {
+            if let Some(component_name) = component_name.as_str() {
+                components.push(component(kind, component_name, fields));
+            }
+        }
+    }
+
+    if yaml["executable"].as_hash().is_some() {
+        components.push(component(ComponentKind::Executable, name.as_deref().unwrap_or_default(), &yaml["executable"]));
+    }
+
+    (name, version, components)
+}
+
+#[derive(Debug, Error)]
+pub enum ProjectError {
+    #[error("Failed to read project file: {0}")]
+    Read(io::Error),
+    #[error("Failed to parse {0}: {1}")]
+    Yaml(PathBuf, yaml_rust2::ScanError),
+}
+
+impl Fexc {
+    /// Loads the project model when the project changes.
+    pub fn update_project(&mut self) {
+        if self.project.as_ref().map(|p| &p.root) != self.current_project.as_ref() {
+            self.reload_project();
+
+            if let Some(root) = self.current_project.clone() {
+                self.events.publish(Event::ProjectOpened { root });
+            }
+        }
+    }
+
+    /// Opens a folder as the project, or a file in the editor along with the project it belongs to.
+    pub fn open_path(&mut self, path: &Path) {
+        let path = match fs::canonicalize(path) {
+            Ok(path) => path,
+            Err(e) => return self.notifications.error(e.to_string()),
+        };
+
+        if path.is_dir() {
+            self.current_project = Some(path);
+            return;
+        }
+
+        if self.current_project.is_none() {
+            self.current_project = Project::find_root(&path);
+        }
+
+        if let Err(e) = self.documents.open(&path) {
+            self.notifications.error(e.to_string());
+        }
+    }
+
+    /// Rereads the project files and the test suites declared in them.
+    pub fn reload_project(&mut self) {
+        self.project = self.current_project.as_deref().map(Project::load);
+        self.tests.refresh(self.project.as_ref());
+    }
+
+    /// Reloads the project model after one of its files was saved.
+    pub fn project_file_saved(&mut self, path: &Path) {
+        if Project::is_project_file(path) {
+            self.reload_project();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temporary project directory with the given files, removed when dropped.
+    struct TempProject(PathBuf);
+
+    impl TempProject {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let root = std::env::temp_dir().join(format!("fexc-project-{name}-{}", std::process::id()));
+            for (path, text) in files {
+                let path = root.join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, text).unwrap();
+            }
+            TempProject(root)
+        }
+    }
+
+    impl Drop for TempProject {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn project(tool: BuildTool) -> Project {
+        Project {
+            root: PathBuf::from("/p"),
+            tool,
+            cabal_project: None,
+            stack_yaml: None,
+            hie_yaml: None,
+            hie_cradle: None,
+            packages: vec![],
+        }
+    }
+
+    fn names(project: &Project) -> Vec<&str> {
+        project.packages.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn cabal_project_packages_default_to_the_root() {
+        assert_eq!(cabal_project_packages("packages: core/ app/*.cabal\n  extra/\n"), ["core/", "app/*.cabal", "extra/"]);
+        assert_eq!(cabal_project_packages("with-compiler: ghc-9.6\n"), ["./*.cabal"]);
+    }
+
+    #[test]
+    fn loads_the_packages_of_a_cabal_project() {
+        let dir = TempProject::new("cabal", &[
+            ("cabal.project", "packages: libs/*\n          app\n"),
+            ("libs/core/core.cabal", "name: core\nversion: 0.1\nlibrary\n  hs-source-dirs: src\n"),
+            ("libs/util/package.yaml", "name: util\nlibrary:\n  source-dirs: src\n"),
+            ("app/app.cabal", "name: app\ntest-suite spec\n  main-is: Spec.hs\n"),
+        ]);
+
+        let project = Project::load(&dir.0);
+
+        assert_eq!(project.tool, BuildTool::Cabal);
+        assert_eq!(names(&project), ["core", "util", "app"]);
+        assert_eq!(project.packages[2].components[0].kind, ComponentKind::TestSuite);
+    }
+
+    #[test]
+    fn loads_the_packages_of_a_stack_project() {
+        let dir = TempProject::new("stack", &[
+            ("stack.yaml", "resolver: lts-22.0\npackages:\n- server\n- client\n"),
+            ("server/server.cabal", "name: server\n"),
+            ("client/package.yaml", "name: client\ntests:\n  client-test:\n    main: Main.hs\n"),
+        ]);
+
+        let project = Project::load(&dir.0);
+
+        assert_eq!(project.tool, BuildTool::Stack);
+        assert_eq!(names(&project), ["server", "client"]);
+        assert_eq!(project.packages[1].components[0].name, "client-test");
+    }
+
+    #[test]
+    fn a_stack_project_without_packages_is_the_root_package() {
+        let dir = TempProject::new("stack-root", &[
+            ("stack.yaml", "resolver: lts-22.0\n"),
+            ("hello.cabal", "name: hello\n"),
+        ]);
+
+        assert_eq!(names(&Project::load(&dir.0)), ["hello"]);
+    }
+
+    #[test]
+    fn test_commands_name_the_package() {
+        let args = ["--match".to_owned(), "parses \"x\"".to_owned()];
+
+        assert_eq!(
+            project(BuildTool::Cabal).test_command("core", "spec", &args),
+            ["cabal", "test", "core:test:spec", "--test-show-details=direct", "--test-option=--match", "--test-option=parses \"x\""],
+        );
+        assert_eq!(
+            project(BuildTool::Stack).test_command("core", "spec", &args),
+            ["stack", "test", "core:test:spec", "--ta", "\"--match\"", "--ta", "\"parses \\\"x\\\"\""],
+        );
+    }
+
+    #[test]
+    fn tasks_for_the_other_build_tool_are_not_supported() {
+        let task = |command: &[&str]| Task { name: String::new(), command: command.iter().map(|s| s.to_string()).collect() };
+
+        let cabal = project(BuildTool::Cabal);
+        assert!(cabal.supports(&task(&["cabal", "build"])));
+        assert!(!cabal.supports(&task(&["stack", "build"])));
+        assert!(cabal.supports(&task(&["hlint", "."])));
+
+        let stack = project(BuildTool::Stack);
+        assert!(stack.supports(&task(&["stack", "test"])));
+        assert!(!stack.supports(&task(&["cabal", "test"])));
+        assert!(project(BuildTool::Ghc).supports(&task(&[])));
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver},
+};
+
+use thiserror::Error;
+
+use crate::{
+    app::{BottomView, Fexc},
+    process,
+    project::Project,
+};
+
+/// Prompt GHCi is switched to on start, so the end of every evaluation can be told from its output.
+const PROMPT: &str = "<<fexc-ghci>>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplLineKind {
+    Input,
+    Output,
+    Error,
+    Info,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplLine {
+    pub kind: ReplLineKind,
+    pub text: String,
+}
+
+enum ReplEvent {
+    Stdout(String),
+    Stderr(String),
+    Exited,
+}
+
+/// A running GHCi session.
+struct ReplProcess {
+    child: Child,
+    stdin: ChildStdin,
+    receiver: Receiver<ReplEvent>,
+    /// Number of prompts still expected, i.e. inputs that have not finished evaluating.
+    pending: usize,
+    /// Whether the custom prompt has been set up; earlier output is GHCi's banner and build log.
+    ready: bool,
+}
+
+impl Drop for ReplProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The GHCi REPL of the bottom panel with its transcript and input history.
+#[derive(Default)]
+pub struct Repl {
+    process: Option<ReplProcess>,
+    pub transcript: Vec<ReplLine>,
+    pub input: String,
+    pub history: Vec<String>,
+    /// Position in `history` while browsing it, `None` when editing a new input.
+    pub history_index: Option<usize>,
+    /// Program and arguments the running session was started with.
+    pub command: Vec<String>,
+}
+
+impl Repl {
+    pub fn is_running(&self) -> bool {
+        self.process.is_some()
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.process.as_ref().is_some_and(|p| p.pending > 0)
+    }
+
+    /// Starts the REPL of the project's build tool, or plain `ghci` without a project.
+    pub fn start(&mut self, project: Option<&Project>, ctx: &egui::Context) -> Result<(), ReplError> {
+        self.process = None;
+        self.command = project.map_or_else(|| vec!["ghci".to_owned()], Project::repl_command);
+
+        let mut child = Command::new(&self.command[0])
+            .args(&self.command[1..])
+            .current_dir(project.map_or(Path::new("."), |p| &p.root))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| ReplError::Spawn(self.command.join(" "), e))?;
+
+        let stdin = child.stdin.take().ok_or(ReplError::NoPipe)?;
+        let stdout = child.stdout.take().ok_or(ReplError::NoPipe)?;
+        let stderr = child.stderr.take().ok_or(ReplError::NoPipe)?;
+
+        let (sender, receiver) = mpsc::channel();
+
+        process::read_lines(stdout, sender.clone(), ReplEvent::Stdout, Some(ReplEvent::Exited), ctx.clone());
+        process::read_lines(stderr, sender, ReplEvent::Stderr, None, ctx.clone());
+
+        let mut process = ReplProcess {
+            child,
+            stdin,
+            receiver,
+            pending: 2,
+            ready: false,
+        };
+
+        writeln!(process.stdin, ":set prompt \"{PROMPT}\\n\"")
+            .and_then(|_| writeln!(process.stdin, ":set prompt-cont \"\""))
+            .map_err(ReplError::Write)?;
+
+        self.transcript.push(ReplLine {
+            kind: ReplLineKind::Info,
+            text: self.command.join(" "),
+        });
+        self.process = Some(process);
+
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.process = None;
+    }
+
+    /// Sends an expression or command, wrapping multi-line input in `:{`/`:}`.
+    pub fn send(&mut self, input: &str) -> Result<(), ReplError> {
+        let process = self.process.as_mut().ok_or(ReplError::NotRunning)?;
+        let input = input.trim_end();
+
+        let message = if input.contains('\n') {
+            format!(":{{\n{input}\n:}}\n")
+        } else {
+            format!("{input}\n")
+        };
+
+        process.stdin.write_all(message.as_bytes())
+            .and_then(|_| process.stdin.flush())
+            .map_err(ReplError::Write)?;
+
+        process.pending += 1;
+
+        self.transcript.extend(input.lines().map(|line| ReplLine {
+            kind: ReplLineKind::Input,
+            text: line.to_owned(),
+        }));
+
+        if self.history.last().is_none_or(|last| last != input) {
+            self.history.push(input.to_owned());
+        }
+        self.history_index = None;
+
+        Ok(())
+    }
+
+    /// Collects the output that arrived since the last frame.
+    pub fn poll(&mut self) {
+        let Some(process) = &mut self.process else { return };
+        let mut exited = false;
+
+        while let Ok(event) = process.receiver.try_recv() {
+            let (text, kind) = match event {
+                ReplEvent::Stdout(line) => {
+                    // Output without a trailing newline ends up in front of the prompt
+                    if let Some(before) = line.strip_suffix(PROMPT) {
+                        if process.ready && !before.is_empty() {
+                            self.transcript.push(ReplLine { kind: ReplLineKind::Output, text: before.to_owned() });
+                        }
+                        process.pending = process.pending.saturating_sub(1);
+                        process.ready = true;
+                        continue;
+                    }
+
+                    let kind = if !process.ready || is_status_line(&line) {
+                        ReplLineKind::Info
+                    } else {
+                        ReplLineKind::Output
+                    };
+                    (line, kind)
+                }
+                ReplEvent::Stderr(line) => (line, ReplLineKind::Error),
+                ReplEvent::Exited => {
+                    exited = true;
+                    continue;
+                }
+            };
+
+            self.transcript.push(ReplLine { kind, text });
+        }
+
+        if exited {
+            self.process = None;
+        }
+    }
+}
+
+fn is_status_line(line: &str) -> bool {
+    line.starts_with("Ok, ")
+        || line.starts_with("Failed, ")
+        || (line.starts_with('[') && line.contains(" Compiling "))
+}
+
+#[derive(Debug, Error)]
+pub enum ReplError {
+    #[error("Failed to start `{0}`: {1}")]
+    Spawn(String, io::Error),
+    #[error("REPL process has no stdio pipes")]
+    NoPipe,
+    #[error("Failed to write to the REPL: {0}")]
+    Write(io::Error),
+    #[error("REPL is not running")]
+    NotRunning,
+}
+
+impl Fexc {
+    fn ensure_repl(&mut self, ctx: &egui::Context) -> bool {
+        self.bottom_view = BottomView::Repl;
+
+        if !self.repl.is_running()
+            && let Err(e) = self.repl.start(self.project.as_ref(), ctx)
+        {
+            self.notifications.error(e.to_string());
+            return false;
+        }
+
+        true
+    }
+
+    fn repl_send(&mut self, ctx: &egui::Context, input: &str) {
+        if self.ensure_repl(ctx)
+            && let Err(e) = self.repl.send(input)
+        {
+            self.notifications.error(e.to_string());
+        }
+    }
+
+    /// Saves the active module and `:load`s it into the REPL.
+    pub fn repl_load_active(&mut self, ctx: &egui::Context) {
+        let doc = self.documents.active();
+        let Some(path) = doc.path.clone() else {
+            self.notifications.error(self.i18n("repl_untitled").to_owned());
+            return;
+        };
+
+        if doc.dirty {
+            self.save_document(doc.id);
+        }
+
+        self.repl_send(ctx, &format!(":load \"{}\"", path.display()));
+    }
+
+    pub fn repl_reload(&mut self, ctx: &egui::Context) {
+        self.repl_send(ctx, ":reload");
+    }
+
+    /// Evaluates the selection, or the cursor line if nothing is selected.
+    pub fn repl_eval_selection(&mut self, ctx: &egui::Context) {
+        let doc = self.documents.active();
+        let input = doc.selection().unwrap_or_else(|| doc.cursor_line().to_owned());
+
+        if !input.trim().is_empty() {
+            self.repl_send(ctx, &input);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::{io, path::PathBuf};
+
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::config::{self, ConfigError};
+
+/// State of the workspace that is restored on the next start, stored next to the config.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Session {
+    pub split_terminals: bool,
+    pub terminals: Vec<TerminalSession>,
+    /// Ids of the commands last run from the command palette, menus or keyboard.
+    pub recent_commands: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TerminalSession {
+    pub name: String,
+    pub color: usize,
+    pub cwd: Option<PathBuf>,
+}
+
+impl Session {
+    pub fn load() -> Result<Self, SessionError> {
+        let session_file = session_file()?;
+        if !session_file.exists() {
+            return Ok(Session::default());
+        }
+
+        let contents = fs::read_to_string(&session_file)
+            .map_err(SessionError::Read)?;
+
+        toml::from_str(&contents)
+            .map_err(SessionError::Deserialize)
+    }
+
+    pub fn save(&self) -> Result<(), SessionError> {
+        let toml = toml::to_string(self)
+            .map_err(SessionError::Serialize)?;
+
+        fs::write(session_file()?, toml)
+            .map_err(SessionError::Write)
+    }
+}
+
+fn session_file() -> Result<PathBuf, SessionError> {
+    let config_dir = config::config_dir()
+        .map_err(SessionError::ConfigDir)?;
+
+    Ok(config_dir.join("session.toml"))
+}
+
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("{0}")]
+    ConfigDir(ConfigError),
+    #[error("Failed to read session file: {0}")]
+    Read(io::Error),
+    #[error("Failed to deserialize session file: {0}")]
+    Deserialize(toml::de::Error),
+    #[error("Failed to serialize session file: {0}")]
+    Serialize(toml::ser::Error),
+    #[error("Failed to write session file: {0}")]
+    Write(io::Error),
+}
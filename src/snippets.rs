@@ -0,0 +1,107 @@
+use crate::languages::Edit;
+
+/// Marks where the cursor goes after a snippet is inserted.
+const CURSOR_MARKER: &str = "$0";
+
+/// Text a plugin registered to be inserted by typing its prefix and pressing Tab.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub plugin: String,
+    pub prefix: String,
+    /// The text to insert, where `$0` marks the cursor position afterwards.
+    pub body: String,
+}
+
+#[derive(Default)]
+pub struct Snippets {
+    snippets: Vec<Snippet>,
+}
+
+impl Snippets {
+    /// Adds a snippet, replacing the one with the same prefix.
+    pub fn register(&mut self, snippet: Snippet) {
+        match self.snippets.iter_mut().find(|s| s.prefix == snippet.prefix) {
+            Some(old) => *old = snippet,
+            None => self.snippets.push(snippet),
+        }
+    }
+
+    pub fn remove_plugin(&mut self, plugin: &str) {
+        self.snippets.retain(|s| s.plugin != plugin);
+    }
+
+    /// Replaces the snippet prefix right before `cursor` with the snippet's body, indenting its
+    /// lines like the line it is typed on.
+    pub fn expand(&self, text: &str, cursor: usize) -> Option<Edit> {
+        let chars = text.chars().collect::<Vec<_>>();
+        let start = chars[..cursor].iter().rposition(|c| c.is_whitespace()).map_or(0, |i| i + 1);
+        let prefix = chars[start..cursor].iter().collect::<String>();
+        let snippet = self.snippets.iter().find(|s| !prefix.is_empty() && s.prefix == prefix)?;
+
+        let line_start = chars[..start].iter().rposition(|c| *c == '\n').map_or(0, |i| i + 1);
+        let indent = chars[line_start..start].iter().take_while(|c| **c == ' ' || **c == '\t').collect::<String>();
+        let body = snippet.body.replace('\n', &format!("\n{indent}"));
+
+        let (before, after) = body.split_once(CURSOR_MARKER).unwrap_or((&body, ""));
+        let text = chars[..start].iter().copied()
+            .chain(before.chars())
+            .chain(after.chars())
+            .chain(chars[cursor..].iter().copied())
+            .collect();
+        let cursor = start + before.chars().count();
+
+        Some(Edit { text, cursor, anchor: cursor })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippets() -> Snippets {
+        let mut snippets = Snippets::default();
+        for (prefix, body) in [("main", "main :: IO ()\nmain = $0"), ("->", "→")] {
+            snippets.register(Snippet { plugin: "init.lua".into(), prefix: prefix.into(), body: body.into() });
+        }
+        snippets
+    }
+
+    #[test]
+    fn expands_the_prefix_before_the_cursor() {
+        let edit = snippets().expand("module Main where\n\nmain", 23).unwrap();
+
+        assert_eq!(edit.text, "module Main where\n\nmain :: IO ()\nmain = ");
+        assert_eq!((edit.cursor, edit.anchor), (40, 40));
+    }
+
+    #[test]
+    fn indents_like_the_current_line() {
+        let edit = snippets().expand("  main\nrest", 6).unwrap();
+
+        assert_eq!(edit.text, "  main :: IO ()\n  main = \nrest");
+        assert_eq!(edit.cursor, 25);
+    }
+
+    #[test]
+    fn puts_the_cursor_after_a_body_without_marker() {
+        let edit = snippets().expand("f x -> y", 6).unwrap();
+
+        assert_eq!(edit.text, "f x → y");
+        assert_eq!(edit.cursor, 5);
+    }
+
+    #[test]
+    fn ignores_other_words() {
+        assert!(snippets().expand("domain", 6).is_none());
+        assert!(snippets().expand("main x", 6).is_none());
+        assert!(snippets().expand("", 0).is_none());
+    }
+
+    #[test]
+    fn removes_the_snippets_of_a_plugin() {
+        let mut snippets = snippets();
+        snippets.remove_plugin("init.lua");
+
+        assert!(snippets.expand("main", 4).is_none());
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use fs_err as fs;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+
+use crate::haskell::{self, Declaration};
+
+/// Directories that never contain project sources but may hold thousands of build artifacts.
+const IGNORED_DIRS: &[&str] = &["dist", "dist-newstyle", ".stack-work", ".git", "node_modules"];
+
+#[derive(Debug, Clone)]
+pub struct IndexedSymbol {
+    pub decl: Declaration,
+    pub module: String,
+    pub path: PathBuf,
+}
+
+/// All top-level declarations of the project's `.hs` files, built on a background thread.
+#[derive(Default)]
+pub struct SymbolIndex {
+    root: Option<PathBuf>,
+    symbols: Vec<IndexedSymbol>,
+    receiver: Option<Receiver<Vec<IndexedSymbol>>>,
+}
+
+impl SymbolIndex {
+    /// Rebuilds the index when the project root changes and collects a finished build.
+    pub fn update(&mut self, root: Option<&Path>, ctx: &egui::Context) {
+        if self.root.as_deref() != root {
+            self.root = root.map(Path::to_owned);
+            self.symbols.clear();
+            self.receiver = root.map(|root| {
+                let (sender, receiver) = mpsc::channel();
+                let root = root.to_owned();
+                let ctx = ctx.clone();
+
+                thread::spawn(move || {
+                    if sender.send(index_dir(&root)).is_ok() {
+                        ctx.request_repaint();
+                    }
+                });
+
+                receiver
+            });
+        }
+
+        if let Some(symbols) = self.receiver.as_ref().and_then(|r| r.try_recv().ok()) {
+            self.symbols = symbols;
+            self.receiver = None;
+        }
+    }
+
+    pub fn is_building(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// Replaces the entries of a single file, e.g. after it was saved.
+    pub fn update_file(&mut self, path: &Path, text: &str) {
+        if self.root.as_ref().is_none_or(|root| !path.starts_with(root)) || !is_haskell_file(path) {
+            return;
+        }
+
+        self.symbols.retain(|s| s.path != path);
+        self.symbols.extend(index_file(path, text));
+    }
+
+    pub fn get(&self, index: usize) -> Option<&IndexedSymbol> {
+        self.symbols.get(index)
+    }
+
+    /// Returns the indices of the best matches for `query`, best first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<usize> {
+        let matcher = SkimMatcherV2::default();
+
+        let mut matches = self.symbols.iter()
+            .enumerate()
+            .filter_map(|(i, s)| matcher.fuzzy_match(&s.decl.name, query).map(|score| (score, i)))
+            .collect::<Vec<_>>();
+
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        matches.truncate(limit);
+        matches.into_iter().map(|(_, i)| i).collect()
+    }
+}
+
+pub fn is_haskell_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "hs" || ext == "lhs")
+}
+
+fn index_dir(root: &Path) -> Vec<IndexedSymbol> {
+    let mut symbols = vec![];
+    let mut stack = vec![root.to_owned()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            if path.is_dir() {
+                if !name.starts_with('.') && !IGNORED_DIRS.contains(&name.as_ref()) {
+                    stack.push(path);
+                }
+            } else if is_haskell_file(&path) {
+                match fs::read_to_string(&path) {
+                    Ok(text) => symbols.extend(index_file(&path, &text)),
+                    Err(e) => log::warn!("{e}"),
+                }
+            }
+        }
+    }
+
+    symbols
+}
+
+fn index_file(path: &Path, text: &str) -> Vec<IndexedSymbol> {
+    let module = module_name(text)
+        .unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+
+    haskell::declarations(text)
+        .into_iter()
+        .map(|decl| IndexedSymbol {
+            decl,
+            module: module.clone(),
+            path: path.to_owned(),
+        })
+        .collect()
+}
+
+/// Reads the name from the `module X.Y where` header.
+pub fn module_name(text: &str) -> Option<String> {
+    text.lines()
+        .find_map(|l| l.strip_prefix("module "))
+        .and_then(|rest| rest.split(|c: char| c.is_whitespace() || c == '(').next())
+        .filter(|name| !name.is_empty())
+        .map(str::to_owned)
+}
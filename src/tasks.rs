@@ -0,0 +1,225 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    app::{BottomView, Fexc},
+    events::Event,
+    ghc, process,
+    project::Package,
+};
+
+/// A command that can be run from the Build menu, e.g. `cabal build`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    pub name: String,
+    pub command: Vec<String>,
+}
+
+impl Task {
+    pub fn defaults() -> Vec<Task> {
+        let task = |name: &str, command: &[&str]| Task {
+            name: name.to_owned(),
+            command: command.iter().map(|s| s.to_string()).collect(),
+        };
+
+        vec![
+            task("cabal build", &["cabal", "build"]),
+            task("cabal test", &["cabal", "test"]),
+            task("stack build", &["stack", "build"]),
+            task("stack test", &["stack", "test"]),
+        ]
+    }
+}
+
+enum TaskEvent {
+    Line(String),
+    Closed,
+}
+
+/// A task that is currently running.
+pub struct TaskRun {
+    pub name: String,
+    child: Child,
+    receiver: Receiver<TaskEvent>,
+    /// Output streams that have not been closed yet.
+    open_streams: usize,
+}
+
+impl Drop for TaskRun {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    Succeeded,
+    Failed(Option<i32>),
+    Stopped,
+}
+
+/// The build task runner and the output of its last run.
+#[derive(Default)]
+pub struct Tasks {
+    pub running: Option<TaskRun>,
+    pub output: Vec<String>,
+    /// Name and outcome of the last finished run.
+    pub finished: Option<(String, TaskStatus)>,
+    /// Index of the last task started from the config.
+    pub last: Option<usize>,
+    /// Directory the last task ran in, which relative paths in its output refer to.
+    pub root: PathBuf,
+}
+
+impl Tasks {
+    pub fn start(&mut self, task: &Task, root: &Path, ctx: &egui::Context) -> Result<(), TaskError> {
+        let (program, args) = task.command.split_first().ok_or(TaskError::EmptyCommand)?;
+
+        self.running = None;
+        self.output.clear();
+        self.finished = None;
+        self.root = root.to_owned();
+
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| TaskError::Spawn(task.command.join(" "), e))?;
+
+        let stdout = child.stdout.take().ok_or(TaskError::NoPipe)?;
+        let stderr = child.stderr.take().ok_or(TaskError::NoPipe)?;
+
+        let (sender, receiver) = mpsc::channel();
+        process::read_lines(stdout, sender.clone(), TaskEvent::Line, Some(TaskEvent::Closed), ctx.clone());
+        process::read_lines(stderr, sender, TaskEvent::Line, Some(TaskEvent::Closed), ctx.clone());
+
+        self.output.push(format!("$ {}", task.command.join(" ")));
+        self.running = Some(TaskRun {
+            name: task.name.clone(),
+            child,
+            receiver,
+            open_streams: 2,
+        });
+
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(run) = self.running.take() {
+            self.finished = Some((run.name.clone(), TaskStatus::Stopped));
+        }
+    }
+
+    /// Collects new output and returns whether the running task finished.
+    pub fn poll(&mut self) -> bool {
+        let Some(run) = &mut self.running else { return false };
+
+        while let Ok(event) = run.receiver.try_recv() {
+            match event {
+                TaskEvent::Line(line) => self.output.push(line),
+                TaskEvent::Closed => run.open_streams -= 1,
+            }
+        }
+
+        if run.open_streams > 0 {
+            return false;
+        }
+
+        let status = match run.child.wait() {
+            Ok(status) if status.success() => TaskStatus::Succeeded,
+            Ok(status) => TaskStatus::Failed(status.code()),
+            Err(e) => {
+                log::error!("{e}");
+                TaskStatus::Failed(None)
+            }
+        };
+
+        self.finished = Some((run.name.clone(), status));
+        self.running = None;
+
+        true
+    }
+
+    /// Errors and warnings in the output of the last run.
+    pub fn messages(&self, packages: &[Package]) -> Vec<ghc::GhcMessage> {
+        ghc::parse_messages(&self.output.join("\n"), &self.root, packages)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TaskError {
+    #[error("Task has an empty command")]
+    EmptyCommand,
+    #[error("Failed to run `{0}`: {1}")]
+    Spawn(String, io::Error),
+    #[error("Task process has no output pipes")]
+    NoPipe,
+}
+
+impl Fexc {
+    pub fn run_task(&mut self, index: usize, ctx: &egui::Context) {
+        let Some(task) = self.config.tasks.get(index) else { return };
+
+        let root = self.project.as_ref()
+            .map(|p| p.root.clone())
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+
+        self.tasks.last = Some(index);
+        self.tests.running = None;
+        self.bottom_view = BottomView::Output;
+
+        if let Err(e) = self.tasks.start(task, &root, ctx) {
+            self.notifications.error(e.to_string());
+        }
+    }
+
+    /// Reruns the last task, or the first configured one that fits the project.
+    pub fn run_last_task(&mut self, ctx: &egui::Context) {
+        let first = self.config.tasks.iter()
+            .position(|task| self.project.as_ref().is_none_or(|p| p.supports(task)));
+
+        if let Some(index) = self.tasks.last.or(first) {
+            self.run_task(index, ctx);
+        }
+    }
+
+    /// Streams task output and turns the messages of a finished build into diagnostics.
+    pub fn poll_tasks(&mut self) {
+        if !self.tasks.poll() {
+            return;
+        }
+
+        let packages = self.project.as_ref().map_or(&[][..], |p| &p.packages);
+
+        for path in self.diagnostics.set_build(ghc::to_diagnostics(&self.tasks.messages(packages))) {
+            self.events.publish(Event::DiagnosticsUpdated { path });
+        }
+
+        match &self.tasks.finished {
+            Some((name, TaskStatus::Succeeded)) => {
+                self.notifications.info(format!("{name}: {}", self.i18n("task_succeeded")));
+            }
+            Some((name, TaskStatus::Failed(_))) => {
+                self.notifications.error(format!("{name}: {}", self.i18n("task_failed")));
+            }
+            _ => {}
+        }
+
+        if let Some((name, status)) = &self.tasks.finished {
+            let event = Event::BuildFinished { task: name.clone(), success: matches!(status, TaskStatus::Succeeded) };
+            self.events.publish(event);
+        }
+    }
+}
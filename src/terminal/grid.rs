@@ -0,0 +1,572 @@
+use std::collections::VecDeque;
+
+use vte::{Params, Perform};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Color {
+    #[default]
+    Default,
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub inverse: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell { ch: ' ', style: Style::default() }
+    }
+}
+
+type Line = Vec<Cell>;
+
+/// The screen of a VT100/xterm-like terminal, fed by a `vte::Parser`.
+pub struct Grid {
+    cols: usize,
+    rows: usize,
+    lines: Vec<Line>,
+    scrollback: VecDeque<Line>,
+    /// The primary screen and cursor, saved while the alternate screen is active.
+    primary: Option<(Vec<Line>, (usize, usize))>,
+    cursor: (usize, usize),
+    saved_cursor: (usize, usize),
+    wrap_pending: bool,
+    style: Style,
+    scroll_top: usize,
+    scroll_bottom: usize,
+    pub cursor_visible: bool,
+    pub app_cursor_keys: bool,
+    pub bracketed_paste: bool,
+    pub title: Option<String>,
+    /// Replies the terminal has to send back to the program, e.g. cursor position reports.
+    pub responses: Vec<u8>,
+}
+
+impl Grid {
+    pub const SCROLLBACK: usize = 10_000;
+
+    pub fn new(cols: usize, rows: usize) -> Self {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+
+        Grid {
+            cols,
+            rows,
+            lines: vec![vec![Cell::default(); cols]; rows],
+            scrollback: VecDeque::new(),
+            primary: None,
+            cursor: (0, 0),
+            saved_cursor: (0, 0),
+            wrap_pending: false,
+            style: Style::default(),
+            scroll_top: 0,
+            scroll_bottom: rows - 1,
+            cursor_visible: true,
+            app_cursor_keys: false,
+            bracketed_paste: false,
+            title: None,
+            responses: vec![],
+        }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols, self.rows)
+    }
+
+    /// Row and column of the cursor on the screen.
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    pub fn scrollback_len(&self) -> usize {
+        self.scrollback.len()
+    }
+
+    /// A line by index into scrollback followed by the screen.
+    pub fn line(&self, index: usize) -> Option<&[Cell]> {
+        if index < self.scrollback.len() {
+            self.scrollback.get(index).map(Vec::as_slice)
+        } else {
+            self.lines.get(index - self.scrollback.len()).map(Vec::as_slice)
+        }
+    }
+
+    /// Text between two (line, column) positions, inclusive of the start and exclusive of the end.
+    pub fn text_range(&self, start: (usize, usize), end: (usize, usize)) -> String {
+        let (start, end) = if start <= end { (start, end) } else { (end, start) };
+        let mut text = String::new();
+
+        for index in start.0..=end.0 {
+            let Some(cells) = self.line(index) else { break };
+
+            let from = if index == start.0 { start.1 } else { 0 };
+            let to = if index == end.0 { end.1 } else { cells.len() };
+
+            let part = cells.iter()
+                .skip(from)
+                .take(to.saturating_sub(from))
+                .map(|c| c.ch)
+                .collect::<String>();
+
+            text.push_str(part.trim_end());
+            if index != end.0 {
+                text.push('\n');
+            }
+        }
+
+        text
+    }
+
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+
+        if (cols, rows) == (self.cols, self.rows) {
+            return;
+        }
+
+        for line in self.lines.iter_mut() {
+            line.resize(cols, Cell::default());
+        }
+
+        // Keep the cursor on screen by moving the top lines into scrollback
+        while self.lines.len() > rows {
+            if self.cursor.0 > 0 {
+                let line = self.lines.remove(0);
+                self.push_scrollback(line);
+                self.cursor.0 -= 1;
+            } else {
+                self.lines.pop();
+            }
+        }
+
+        while self.lines.len() < rows {
+            self.lines.push(vec![Cell::default(); cols]);
+        }
+
+        self.cols = cols;
+        self.rows = rows;
+        self.scroll_top = 0;
+        self.scroll_bottom = rows - 1;
+        self.cursor.0 = self.cursor.0.min(rows - 1);
+        self.cursor.1 = self.cursor.1.min(cols - 1);
+        self.wrap_pending = false;
+    }
+
+    fn push_scrollback(&mut self, line: Line) {
+        if self.primary.is_some() {
+            return;
+        }
+
+        self.scrollback.push_back(line);
+        if self.scrollback.len() > Self::SCROLLBACK {
+            self.scrollback.pop_front();
+        }
+    }
+
+    fn blank_line(&self) -> Line {
+        vec![Cell { ch: ' ', style: Style { bg: self.style.bg, ..Style::default() } }; self.cols]
+    }
+
+    fn scroll_up(&mut self, count: usize) {
+        // Scrolling by more than the region is the same as clearing it
+        let count = count.min(self.scroll_bottom - self.scroll_top + 1);
+
+        for _ in 0..count {
+            let line = self.lines.remove(self.scroll_top);
+            if self.scroll_top == 0 {
+                self.push_scrollback(line);
+            }
+            let blank = self.blank_line();
+            self.lines.insert(self.scroll_bottom, blank);
+        }
+    }
+
+    fn scroll_down(&mut self, count: usize) {
+        let count = count.min(self.scroll_bottom - self.scroll_top + 1);
+
+        for _ in 0..count {
+            self.lines.remove(self.scroll_bottom);
+            let blank = self.blank_line();
+            self.lines.insert(self.scroll_top, blank);
+        }
+    }
+
+    fn linefeed(&mut self) {
+        if self.cursor.0 == self.scroll_bottom {
+            self.scroll_up(1);
+        } else if self.cursor.0 + 1 < self.rows {
+            self.cursor.0 += 1;
+        }
+    }
+
+    fn put(&mut self, ch: char) {
+        if self.wrap_pending {
+            self.cursor.1 = 0;
+            self.linefeed();
+            self.wrap_pending = false;
+        }
+
+        let (row, col) = self.cursor;
+        self.lines[row][col] = Cell { ch, style: self.style };
+
+        if col + 1 < self.cols {
+            self.cursor.1 += 1;
+        } else {
+            self.wrap_pending = true;
+        }
+    }
+
+    fn move_to(&mut self, row: usize, col: usize) {
+        self.cursor = (row.min(self.rows - 1), col.min(self.cols - 1));
+        self.wrap_pending = false;
+    }
+
+    fn erase(&mut self, row: usize, cols: std::ops::Range<usize>) {
+        let blank = Cell { ch: ' ', style: Style { bg: self.style.bg, ..Style::default() } };
+        let end = cols.end.min(self.cols);
+
+        for cell in &mut self.lines[row][cols.start.min(end)..end] {
+            *cell = blank;
+        }
+    }
+
+    fn set_alternate_screen(&mut self, enabled: bool) {
+        if enabled && self.primary.is_none() {
+            let screen = vec![vec![Cell::default(); self.cols]; self.rows];
+            let primary = std::mem::replace(&mut self.lines, screen);
+            self.primary = Some((primary, self.cursor));
+        } else if !enabled && let Some((lines, cursor)) = self.primary.take() {
+            self.lines = lines;
+            self.lines.resize(self.rows, vec![Cell::default(); self.cols]);
+            for line in self.lines.iter_mut() {
+                line.resize(self.cols, Cell::default());
+            }
+            self.move_to(cursor.0, cursor.1);
+        }
+    }
+
+    fn set_mode(&mut self, params: &Params, private: bool, enabled: bool) {
+        for param in params.iter().flat_map(|p| p.iter()) {
+            match (private, param) {
+                (true, 1) => self.app_cursor_keys = enabled,
+                (true, 25) => self.cursor_visible = enabled,
+                (true, 47 | 1047 | 1049) => self.set_alternate_screen(enabled),
+                (true, 2004) => self.bracketed_paste = enabled,
+                _ => {}
+            }
+        }
+    }
+
+    fn set_style(&mut self, params: &Params) {
+        let mut params = params.iter().map(|p| p.first().copied().unwrap_or(0)).peekable();
+
+        if params.peek().is_none() {
+            self.style = Style::default();
+        }
+
+        while let Some(param) = params.next() {
+            match param {
+                0 => self.style = Style::default(),
+                1 => self.style.bold = true,
+                3 => self.style.italic = true,
+                4 => self.style.underline = true,
+                7 => self.style.inverse = true,
+                22 => self.style.bold = false,
+                23 => self.style.italic = false,
+                24 => self.style.underline = false,
+                27 => self.style.inverse = false,
+                30..=37 => self.style.fg = Color::Indexed((param - 30) as u8),
+                39 => self.style.fg = Color::Default,
+                40..=47 => self.style.bg = Color::Indexed((param - 40) as u8),
+                49 => self.style.bg = Color::Default,
+                90..=97 => self.style.fg = Color::Indexed((param - 90 + 8) as u8),
+                100..=107 => self.style.bg = Color::Indexed((param - 100 + 8) as u8),
+                38 | 48 => {
+                    let color = match params.next() {
+                        Some(5) => params.next().map(|i| Color::Indexed(i as u8)),
+                        Some(2) => match (params.next(), params.next(), params.next()) {
+                            (Some(r), Some(g), Some(b)) => Some(Color::Rgb(r as u8, g as u8, b as u8)),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+
+                    if let Some(color) = color {
+                        if param == 38 {
+                            self.style.fg = color;
+                        } else {
+                            self.style.bg = color;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Perform for Grid {
+    fn print(&mut self, c: char) {
+        self.put(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' | 0x0b | 0x0c => self.linefeed(),
+            b'\r' => {
+                self.cursor.1 = 0;
+                self.wrap_pending = false;
+            }
+            0x08 => {
+                self.cursor.1 = self.cursor.1.saturating_sub(1);
+                self.wrap_pending = false;
+            }
+            b'\t' => {
+                let next = (self.cursor.1 / 8 + 1) * 8;
+                self.cursor.1 = next.min(self.cols - 1);
+            }
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, action: char) {
+        let private = intermediates.first() == Some(&b'?');
+        let args = params.iter().map(|p| p.first().copied().unwrap_or(0) as usize).collect::<Vec<_>>();
+        let arg = |i: usize, default: usize| args.get(i).copied().filter(|&a| a != 0).unwrap_or(default);
+        let (row, col) = self.cursor;
+
+        match action {
+            'A' => self.move_to(row.saturating_sub(arg(0, 1)), col),
+            'B' | 'e' => self.move_to(row + arg(0, 1), col),
+            'C' | 'a' => self.move_to(row, col + arg(0, 1)),
+            'D' => self.move_to(row, col.saturating_sub(arg(0, 1))),
+            'E' => self.move_to(row + arg(0, 1), 0),
+            'F' => self.move_to(row.saturating_sub(arg(0, 1)), 0),
+            'G' | '`' => self.move_to(row, arg(0, 1) - 1),
+            'd' => self.move_to(arg(0, 1) - 1, col),
+            'H' | 'f' => self.move_to(arg(0, 1) - 1, arg(1, 1) - 1),
+            'J' => {
+                match args.first().copied().unwrap_or(0) {
+                    0 => {
+                        self.erase(row, col..self.cols);
+                        for r in row + 1..self.rows {
+                            self.erase(r, 0..self.cols);
+                        }
+                    }
+                    1 => {
+                        for r in 0..row {
+                            self.erase(r, 0..self.cols);
+                        }
+                        self.erase(row, 0..col + 1);
+                    }
+                    2 => {
+                        for r in 0..self.rows {
+                            self.erase(r, 0..self.cols);
+                        }
+                    }
+                    3 => self.scrollback.clear(),
+                    _ => {}
+                }
+            }
+            'K' => match args.first().copied().unwrap_or(0) {
+                0 => self.erase(row, col..self.cols),
+                1 => self.erase(row, 0..col + 1),
+                _ => self.erase(row, 0..self.cols),
+            },
+            'X' => self.erase(row, col..col + arg(0, 1)),
+            '@' => {
+                let count = arg(0, 1).min(self.cols - col);
+                let line = &mut self.lines[row];
+                line.truncate(self.cols - count);
+                for _ in 0..count {
+                    line.insert(col, Cell::default());
+                }
+            }
+            'P' => {
+                let count = arg(0, 1).min(self.cols - col);
+                let line = &mut self.lines[row];
+                line.drain(col..col + count);
+                line.resize(self.cols, Cell::default());
+            }
+            'L' if (self.scroll_top..=self.scroll_bottom).contains(&row) => {
+                let top = std::mem::replace(&mut self.scroll_top, row);
+                self.scroll_down(arg(0, 1).min(self.scroll_bottom - row + 1));
+                self.scroll_top = top;
+            }
+            'M' if (self.scroll_top..=self.scroll_bottom).contains(&row) => {
+                let top = std::mem::replace(&mut self.scroll_top, row);
+                let count = arg(0, 1).min(self.scroll_bottom - row + 1);
+                for _ in 0..count {
+                    self.lines.remove(row);
+                    let blank = self.blank_line();
+                    self.lines.insert(self.scroll_bottom, blank);
+                }
+                self.scroll_top = top;
+            }
+            'S' => self.scroll_up(arg(0, 1)),
+            'T' => self.scroll_down(arg(0, 1)),
+            'r' => {
+                let top = arg(0, 1) - 1;
+                let bottom = arg(1, self.rows) - 1;
+                if top < bottom && bottom < self.rows {
+                    self.scroll_top = top;
+                    self.scroll_bottom = bottom;
+                    self.move_to(0, 0);
+                }
+            }
+            'h' => self.set_mode(params, private, true),
+            'l' => self.set_mode(params, private, false),
+            'm' if !private => self.set_style(params),
+            's' => self.saved_cursor = self.cursor,
+            'u' => self.move_to(self.saved_cursor.0, self.saved_cursor.1),
+            'n' if args.first() == Some(&6) => {
+                self.responses.extend(format!("\x1b[{};{}R", row + 1, col + 1).bytes());
+            }
+            _ => {}
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        if !intermediates.is_empty() {
+            return;
+        }
+
+        match byte {
+            b'7' => self.saved_cursor = self.cursor,
+            b'8' => self.move_to(self.saved_cursor.0, self.saved_cursor.1),
+            b'D' => self.linefeed(),
+            b'E' => {
+                self.cursor.1 = 0;
+                self.linefeed();
+            }
+            b'M' => {
+                if self.cursor.0 == self.scroll_top {
+                    self.scroll_down(1);
+                } else {
+                    self.cursor.0 = self.cursor.0.saturating_sub(1);
+                }
+            }
+            b'c' => *self = Grid::new(self.cols, self.rows),
+            _ => {}
+        }
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        if let [b"0" | b"2", title, ..] = params {
+            self.title = Some(String::from_utf8_lossy(title).into_owned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(cols: usize, rows: usize, input: &str) -> Grid {
+        let mut grid = Grid::new(cols, rows);
+        vte::Parser::new().advance(&mut grid, input.as_bytes());
+        grid
+    }
+
+    fn text(grid: &Grid, index: usize) -> String {
+        grid.line(index).unwrap().iter().map(|c| c.ch).collect::<String>().trim_end().to_owned()
+    }
+
+    fn screen(grid: &Grid) -> Vec<String> {
+        (0..grid.rows).map(|row| text(grid, grid.scrollback_len() + row)).collect()
+    }
+
+    #[test]
+    fn prints_and_wraps() {
+        let grid = feed(4, 3, "abcdef\r\nxy");
+        assert_eq!(screen(&grid), ["abcd", "ef", "xy"]);
+        assert_eq!(grid.cursor(), (2, 2));
+    }
+
+    #[test]
+    fn linefeed_at_the_bottom_fills_scrollback() {
+        let grid = feed(10, 2, "one\r\ntwo\r\nthree");
+        assert_eq!(grid.scrollback_len(), 1);
+        assert_eq!(text(&grid, 0), "one");
+        assert_eq!(screen(&grid), ["two", "three"]);
+    }
+
+    #[test]
+    fn moves_the_cursor_and_erases() {
+        let grid = feed(10, 3, "hello\r\nworld\x1b[1;3H\x1b[K\x1b[2;2H\x1b[1K");
+        assert_eq!(screen(&grid), ["he", "  rld", ""]);
+
+        let grid = feed(10, 3, "a\r\nb\r\nc\x1b[2;1H\x1b[J");
+        assert_eq!(screen(&grid), ["a", "", ""]);
+    }
+
+    #[test]
+    fn inserts_and_deletes_characters() {
+        let grid = feed(6, 1, "abcdef\x1b[1;2H\x1b[2@");
+        assert_eq!(screen(&grid), ["a  bcd"]);
+
+        let grid = feed(6, 1, "abcdef\x1b[1;2H\x1b[2P");
+        assert_eq!(screen(&grid), ["adef"]);
+    }
+
+    #[test]
+    fn scroll_counts_are_clamped_to_the_region() {
+        let grid = feed(5, 3, "a\r\nb\r\nc\x1b[999S");
+        assert_eq!(screen(&grid), ["", "", ""]);
+        assert_eq!(grid.scrollback_len(), 3);
+
+        let grid = feed(5, 3, "a\r\nb\r\nc\x1b[999T");
+        assert_eq!(screen(&grid), ["", "", ""]);
+
+        let grid = feed(5, 4, "a\r\nb\r\nc\r\nd\x1b[2;3r\x1b[999S");
+        assert_eq!(screen(&grid), ["a", "", "", "d"]);
+    }
+
+    #[test]
+    fn clearing_scrollback_and_resetting() {
+        let grid = feed(5, 2, "a\r\nb\r\nc\r\nd\x1b[3J");
+        assert_eq!(grid.scrollback_len(), 0);
+        assert_eq!(screen(&grid), ["c", "d"]);
+
+        let grid = feed(5, 2, "a\r\nb\r\nc\x1b[31m\x1bc");
+        assert_eq!(grid.scrollback_len(), 0);
+        assert_eq!(screen(&grid), ["", ""]);
+        assert_eq!(grid.style, Style::default());
+    }
+
+    #[test]
+    fn alternate_screen_keeps_the_primary_one() {
+        let grid = feed(5, 2, "main\x1b[?1049hfull\x1b[?1049l");
+        assert_eq!(screen(&grid), ["main", ""]);
+        assert_eq!(grid.cursor(), (0, 4));
+    }
+
+    #[test]
+    fn sets_styles() {
+        let grid = feed(5, 1, "\x1b[1;31;48;5;200mx\x1b[0my");
+        let line = grid.line(0).unwrap();
+        assert_eq!(line[0].style, Style { fg: Color::Indexed(1), bg: Color::Indexed(200), bold: true, ..Style::default() });
+        assert_eq!(line[1].style, Style::default());
+    }
+
+    #[test]
+    fn reports_the_cursor_position() {
+        let grid = feed(10, 5, "\x1b[3;4H\x1b[6n");
+        assert_eq!(grid.responses, b"\x1b[3;4R");
+    }
+}
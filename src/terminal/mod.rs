@@ -0,0 +1,164 @@
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+};
+
+use fs_err as fs;
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use thiserror::Error;
+
+mod grid;
+
+pub use grid::{Cell, Color, Grid};
+
+/// Parser and screen state shared with the reader thread.
+pub struct Screen {
+    parser: vte::Parser,
+    pub grid: Grid,
+}
+
+/// A shell running in a pseudo terminal.
+pub struct Terminal {
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    child: Box<dyn Child + Send + Sync>,
+    screen: Arc<Mutex<Screen>>,
+    exited: Arc<AtomicBool>,
+    cwd: Option<PathBuf>,
+}
+
+impl Terminal {
+    /// Spawns the user's `$SHELL` (or `sh`) in `cwd`.
+    pub fn spawn(cwd: Option<&Path>, cols: u16, rows: u16, ctx: egui::Context) -> Result<Self, TerminalError> {
+        let size = PtySize { rows, cols, pixel_width: 0, pixel_height: 0 };
+
+        let pair = native_pty_system()
+            .openpty(size)
+            .map_err(|e| TerminalError::Open(e.to_string()))?;
+
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_owned());
+        let mut command = CommandBuilder::new(&shell);
+        command.env("TERM", "xterm-256color");
+        command.env("COLORTERM", "truecolor");
+
+        if let Some(cwd) = cwd {
+            command.cwd(cwd);
+        }
+
+        let child = pair.slave
+            .spawn_command(command)
+            .map_err(|e| TerminalError::Spawn(shell, e.to_string()))?;
+
+        let mut reader = pair.master
+            .try_clone_reader()
+            .map_err(|e| TerminalError::Open(e.to_string()))?;
+
+        let writer = pair.master
+            .take_writer()
+            .map_err(|e| TerminalError::Open(e.to_string()))?;
+
+        let screen = Arc::new(Mutex::new(Screen {
+            parser: vte::Parser::new(),
+            grid: Grid::new(cols as usize, rows as usize),
+        }));
+
+        let exited = Arc::new(AtomicBool::new(false));
+
+        thread::spawn({
+            let screen = screen.clone();
+            let exited = exited.clone();
+
+            move || {
+                let mut buf = [0u8; 8192];
+
+                loop {
+                    match reader.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => {
+                            let Ok(mut screen) = screen.lock() else { break };
+                            let Screen { parser, grid } = &mut *screen;
+                            parser.advance(grid, &buf[..n]);
+                            drop(screen);
+                            ctx.request_repaint();
+                        }
+                    }
+                }
+
+                exited.store(true, Ordering::Relaxed);
+                ctx.request_repaint();
+            }
+        });
+
+        Ok(Terminal {
+            master: pair.master,
+            writer,
+            child,
+            screen,
+            exited,
+            cwd: cwd.map(Path::to_owned),
+        })
+    }
+
+    pub fn screen(&self) -> MutexGuard<'_, Screen> {
+        self.screen.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::Relaxed)
+    }
+
+    /// The working directory of the shell, or the one it was started in where that cannot be determined.
+    pub fn current_dir(&self) -> Option<PathBuf> {
+        self.child.process_id()
+            .and_then(|pid| fs::read_link(format!("/proc/{pid}/cwd")).ok())
+            .or_else(|| self.cwd.clone())
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        if let Err(e) = self.writer.write_all(bytes).and_then(|_| self.writer.flush()) {
+            log::warn!("Failed to write to terminal: {e}");
+        }
+    }
+
+    /// Sends pending replies of the emulator (e.g. cursor position reports) back to the shell.
+    pub fn flush_responses(&mut self) {
+        let responses = std::mem::take(&mut self.screen().grid.responses);
+        if !responses.is_empty() {
+            self.write(&responses);
+        }
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        let (current_cols, current_rows) = self.screen().grid.size();
+        if (current_cols, current_rows) == (cols as usize, rows as usize) {
+            return;
+        }
+
+        self.screen().grid.resize(cols as usize, rows as usize);
+
+        let size = PtySize { rows, cols, pixel_width: 0, pixel_height: 0 };
+        if let Err(e) = self.master.resize(size) {
+            log::warn!("Failed to resize terminal: {e}");
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TerminalError {
+    #[error("Failed to open a pseudo terminal: {0}")]
+    Open(String),
+    #[error("Failed to start shell `{0}`: {1}")]
+    Spawn(String, String),
+}
@@ -0,0 +1,712 @@
+use crate::{
+    app::{BottomView, Fexc},
+    links,
+    project::{ComponentKind, Project},
+    tasks::{Task, TaskStatus},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestFramework {
+    Hspec,
+    Tasty,
+    HUnit,
+}
+
+impl TestFramework {
+    pub fn name(self) -> &'static str {
+        match self {
+            TestFramework::Hspec => "hspec",
+            TestFramework::Tasty => "tasty",
+            TestFramework::HUnit => "HUnit",
+        }
+    }
+
+    /// Guesses the framework from the dependencies of a test suite.
+    fn detect(dependencies: &[String]) -> Self {
+        if dependencies.iter().any(|d| d.starts_with("hspec")) {
+            TestFramework::Hspec
+        } else if dependencies.iter().any(|d| d.starts_with("tasty")) {
+            TestFramework::Tasty
+        } else {
+            TestFramework::HUnit
+        }
+    }
+
+    /// Arguments that make the suite run only the given cases, if the framework supports it.
+    fn filter_args(self, cases: &[Vec<String>]) -> Option<Vec<String>> {
+        match self {
+            TestFramework::Hspec => Some(
+                cases.iter()
+                    .flat_map(|path| ["--match".to_owned(), format!("/{}/", path.join("/"))])
+                    .collect(),
+            ),
+            TestFramework::Tasty => {
+                let pattern = cases.iter()
+                    .map(|path| format!("$0 == \"{}\"", path.join(".").replace('"', "\\\"")))
+                    .collect::<Vec<_>>()
+                    .join(" || ");
+                Some(vec!["-p".to_owned(), pattern])
+            }
+            TestFramework::HUnit => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// A group or a case in the results of a test suite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestNode {
+    pub name: String,
+    /// Outcome of a case; groups take theirs from their children.
+    pub status: Option<TestStatus>,
+    pub duration: Option<String>,
+    /// Failure message or other details printed below the case.
+    pub message: String,
+    /// Location of the failing assertion, relative to the project.
+    pub location: Option<(String, usize, usize)>,
+    pub children: Vec<TestNode>,
+}
+
+impl TestNode {
+    fn new(name: &str) -> Self {
+        TestNode {
+            name: name.to_owned(),
+            status: None,
+            duration: None,
+            message: String::new(),
+            location: None,
+            children: vec![],
+        }
+    }
+
+    pub fn is_case(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// The status of a case, or the worst status among the cases of a group.
+    pub fn summary(&self) -> Option<TestStatus> {
+        if self.is_case() {
+            return self.status;
+        }
+
+        let statuses = self.children.iter().filter_map(TestNode::summary).collect::<Vec<_>>();
+        [TestStatus::Failed, TestStatus::Passed, TestStatus::Skipped].into_iter()
+            .find(|status| statuses.contains(status))
+    }
+
+    /// Paths of the failed cases below this node.
+    fn failures(&self, path: &mut Vec<String>, failures: &mut Vec<Vec<String>>) {
+        path.push(self.name.clone());
+
+        if self.is_case() && self.status == Some(TestStatus::Failed) {
+            failures.push(path.clone());
+        }
+        for child in &self.children {
+            child.failures(path, failures);
+        }
+
+        path.pop();
+    }
+
+    fn find_mut(&mut self, path: &[String]) -> Option<&mut TestNode> {
+        match path.split_first() {
+            Some((name, rest)) => self.children.iter_mut().find(|c| &c.name == name)?.find_mut(rest),
+            None => Some(self),
+        }
+    }
+}
+
+/// Inserts `node` below the groups of `path`, creating the missing ones and replacing an older result.
+fn insert(nodes: &mut Vec<TestNode>, path: &[String], node: TestNode) {
+    match path.split_first() {
+        Some((name, rest)) => {
+            let index = match nodes.iter().position(|n| &n.name == name) {
+                Some(index) => index,
+                None => {
+                    nodes.push(TestNode::new(name));
+                    nodes.len() - 1
+                }
+            };
+            insert(&mut nodes[index].children, rest, node);
+        }
+        None => match nodes.iter_mut().find(|n| n.name == node.name) {
+            Some(existing) if node.is_case() => *existing = node,
+            Some(existing) => {
+                for child in node.children {
+                    insert(&mut existing.children, &[], child);
+                }
+            }
+            None => nodes.push(node),
+        },
+    }
+}
+
+/// A test-suite component of the project and its last results.
+#[derive(Debug, Clone)]
+pub struct TestSuite {
+    pub name: String,
+    pub package: String,
+    pub framework: TestFramework,
+    pub results: Vec<TestNode>,
+    /// Outcome of the last run of the whole suite process.
+    pub status: Option<TaskStatus>,
+}
+
+impl TestSuite {
+    /// Paths of the failed cases in the last results.
+    pub fn failures(&self) -> Vec<Vec<String>> {
+        let mut failures = vec![];
+        for node in &self.results {
+            node.failures(&mut vec![], &mut failures);
+        }
+        failures
+    }
+
+    fn parse(&self, output: &str) -> Vec<TestNode> {
+        match self.framework {
+            TestFramework::Hspec => parse_hspec(output),
+            TestFramework::Tasty => parse_tasty(output),
+            TestFramework::HUnit => parse_hunit(output),
+        }
+    }
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Removes up to `width` leading spaces, keeping the alignment of deeper lines.
+fn dedent(line: &str, width: usize) -> &str {
+    let spaces = line.len() - line.trim_start_matches(' ').len();
+    line[spaces.min(width)..].trim_end()
+}
+
+/// Splits a trailing duration like `(0.01s)` or `(12ms)` off a line.
+fn split_duration(text: &str) -> (&str, Option<String>) {
+    if let Some(open) = text.rfind(" (")
+        && let Some(duration) = text[open + 2..].strip_suffix(')')
+        && duration.ends_with('s')
+        && duration.starts_with(|c: char| c.is_ascii_digit())
+    {
+        return (text[..open].trim_end(), Some(duration.to_owned()));
+    }
+
+    (text, None)
+}
+
+/// Tracks the enclosing groups and the last case while walking an indented test report.
+#[derive(Default)]
+struct ReportTree {
+    nodes: Vec<TestNode>,
+    groups: Vec<(usize, String)>,
+    /// Path and indentation of the last case, whose details are indented below it.
+    case: Option<(Vec<String>, usize)>,
+}
+
+impl ReportTree {
+    fn enter(&mut self, indent: usize) {
+        while self.groups.last().is_some_and(|(i, _)| *i >= indent) {
+            self.groups.pop();
+        }
+    }
+
+    fn group(&mut self, indent: usize, name: &str) {
+        self.enter(indent);
+        self.groups.push((indent, name.to_owned()));
+        self.case = None;
+    }
+
+    fn case(&mut self, indent: usize, node: TestNode) {
+        self.enter(indent);
+
+        let groups = self.groups.iter().map(|(_, g)| g.clone()).collect::<Vec<_>>();
+        let mut path = groups.clone();
+        path.push(node.name.clone());
+
+        insert(&mut self.nodes, &groups, node);
+        self.case = Some((path, indent));
+    }
+
+    /// Adds a line printed below the last case, if it is indented deeper than the case.
+    fn detail(&mut self, line: &str) -> bool {
+        let Some((path, indent)) = &self.case else { return false };
+        if indent_of(line) <= *indent {
+            return false;
+        }
+
+        if let Some(node) = find_mut(&mut self.nodes, path) {
+            add_detail(node, dedent(line, indent + 2));
+        }
+        true
+    }
+}
+
+fn find_mut<'a>(nodes: &'a mut [TestNode], path: &[String]) -> Option<&'a mut TestNode> {
+    let (name, rest) = path.split_first()?;
+    nodes.iter_mut().find(|n| &n.name == name)?.find_mut(rest)
+}
+
+fn add_detail(node: &mut TestNode, line: &str) {
+    if node.location.is_none()
+        && let Some(link) = links::find(line).into_iter().next()
+    {
+        node.location = Some((link.path, link.line, link.column));
+    }
+
+    if !node.message.is_empty() {
+        node.message.push('\n');
+    }
+    node.message.push_str(line);
+}
+
+/// Parses hspec's default `specdoc` report, including the `Failures:` section at its end.
+fn parse_hspec(output: &str) -> Vec<TestNode> {
+    let mut tree = ReportTree::default();
+    let mut lines = output.lines();
+
+    for line in lines.by_ref() {
+        if line.trim() == "Failures:" {
+            break;
+        }
+        if line.trim().is_empty() || tree.detail(line) {
+            continue;
+        }
+
+        let (text, duration) = split_duration(line.trim());
+        let (name, status) = if let Some(name) = text.strip_suffix("[✔]") {
+            (name, TestStatus::Passed)
+        } else if let Some(name) = text.strip_suffix("[✘]") {
+            (name, TestStatus::Failed)
+        } else if let Some(name) = text.strip_suffix("[‐]") {
+            (name, TestStatus::Skipped)
+        } else if let Some((name, _)) = text.strip_prefix("- ").and_then(|t| t.rsplit_once(" FAILED [")) {
+            (name, TestStatus::Failed)
+        } else if let Some(name) = text.strip_prefix("- ") {
+            (name, TestStatus::Passed)
+        } else {
+            tree.group(indent_of(line), text);
+            continue;
+        };
+
+        tree.case(indent_of(line), TestNode {
+            status: Some(status),
+            duration,
+            ..TestNode::new(name.trim())
+        });
+    }
+
+    // Failures are listed as the location, then `1) Group, case` and the indented message
+    let mut location = None;
+    let mut failure = None;
+
+    for line in lines {
+        let text = line.trim();
+
+        if let Some((number, path)) = text.split_once(") ")
+            && !number.is_empty()
+            && number.chars().all(|c| c.is_ascii_digit())
+        {
+            let path = path.split(", ").map(str::to_owned).collect::<Vec<_>>();
+            failure = find_mut(&mut tree.nodes, &path).map(|_| path);
+
+            if let Some(node) = failure.as_ref().and_then(|path| find_mut(&mut tree.nodes, path)) {
+                node.message.clear();
+                node.location = location.take();
+            }
+        } else if text.is_empty() || text.starts_with("To rerun use:") || text.starts_with("Randomized with seed") {
+            failure = None;
+        } else if let Some(node) = failure.as_ref().and_then(|path| find_mut(&mut tree.nodes, path)) {
+            add_detail(node, dedent(line, 7));
+        } else if let Some(link) = links::find(text).into_iter().find(|l| l.range.start == 0) {
+            location = Some((link.path, link.line, link.column));
+        }
+    }
+
+    tree.nodes
+}
+
+/// Parses tasty's console report, where cases end in `: OK`, `: FAIL` or `: SKIP`.
+fn parse_tasty(output: &str) -> Vec<TestNode> {
+    let mut tree = ReportTree::default();
+
+    for line in output.lines() {
+        if line.trim().is_empty() || line.trim().starts_with("Use -p ") || tree.detail(line) {
+            continue;
+        }
+
+        let text = line.trim();
+
+        // The summary at the end, e.g. `1 out of 3 tests failed (0.02s)`
+        if indent_of(line) == 0 && (text.starts_with("All ") || text.contains(" out of ")) {
+            tree.case = None;
+            continue;
+        }
+
+        let case = text.rsplit_once(':').and_then(|(name, result)| {
+            let (result, duration) = split_duration(result.trim());
+            let status = match result.split_whitespace().next()? {
+                "OK" => TestStatus::Passed,
+                "FAIL" => TestStatus::Failed,
+                "SKIP" | "IGNORED" => TestStatus::Skipped,
+                _ => return None,
+            };
+            Some((name, status, duration))
+        });
+
+        match case {
+            Some((name, status, duration)) => tree.case(indent_of(line), TestNode {
+                status: Some(status),
+                duration,
+                ..TestNode::new(name.trim())
+            }),
+            None => tree.group(indent_of(line), text),
+        }
+    }
+
+    tree.nodes
+}
+
+/// Parses the failures `runTestTT` reports; passing HUnit cases are not listed by name.
+fn parse_hunit(output: &str) -> Vec<TestNode> {
+    let mut nodes = vec![];
+    let mut current: Option<TestNode> = None;
+
+    for line in output.lines() {
+        let failure = line.strip_prefix("### Failure in: ").or_else(|| line.strip_prefix("### Error in: "));
+
+        if let Some(name) = failure {
+            nodes.extend(current.take());
+            current = Some(TestNode {
+                status: Some(TestStatus::Failed),
+                ..TestNode::new(name.trim())
+            });
+        } else if line.starts_with("Cases: ") || line.starts_with("###") {
+            nodes.extend(current.take());
+        } else if let Some(node) = &mut current
+            && !line.trim().is_empty()
+        {
+            add_detail(node, line.trim());
+        }
+    }
+
+    nodes.extend(current);
+    nodes
+}
+
+/// Which cases of a suite a run covers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TestFilter {
+    All,
+    Case(Vec<String>),
+    Failures,
+}
+
+/// The test suites of the current project and the run in progress.
+#[derive(Default)]
+pub struct TestExplorer {
+    pub suites: Vec<TestSuite>,
+    /// Suite index and filter of the run in progress.
+    pub running: Option<(usize, TestFilter)>,
+}
+
+impl TestExplorer {
+    /// Takes the test suites from the project model, keeping the results of suites that still exist.
+    pub fn refresh(&mut self, project: Option<&Project>) {
+        let old = std::mem::take(&mut self.suites);
+
+        self.suites = project.into_iter()
+            .flat_map(Project::components)
+            .filter(|(_, component)| component.kind == ComponentKind::TestSuite)
+            .map(|(package, component)| {
+                let previous = old.iter().find(|s| s.package == package.name && s.name == component.name);
+
+                TestSuite {
+                    name: component.name.clone(),
+                    package: package.name.clone(),
+                    framework: TestFramework::detect(&component.dependencies),
+                    results: previous.map(|s| s.results.clone()).unwrap_or_default(),
+                    status: previous.and_then(|s| s.status),
+                }
+            })
+            .collect();
+
+        self.running = None;
+    }
+}
+
+impl Fexc {
+    /// Collects the results of a finished test run.
+    pub fn update_tests(&mut self) {
+        if self.tests.running.is_some() && self.tasks.running.is_none() {
+            self.finish_tests();
+        }
+    }
+
+    /// Runs a test suite as a task, so its output shows up in the Output view.
+    pub fn run_tests(&mut self, suite: usize, filter: TestFilter, ctx: &egui::Context) {
+        let Some(project) = &self.project else { return };
+        let Some(test_suite) = self.tests.suites.get(suite) else { return };
+
+        let cases = match &filter {
+            TestFilter::All => None,
+            TestFilter::Case(path) => Some(vec![path.clone()]),
+            TestFilter::Failures => Some(test_suite.failures()).filter(|f| !f.is_empty()),
+        };
+
+        // Frameworks without filtering rerun the whole suite
+        let filter_args = cases.and_then(|cases| test_suite.framework.filter_args(&cases));
+        let filter = if filter_args.is_some() { filter } else { TestFilter::All };
+
+        let mut args = filter_args.unwrap_or_default();
+        if test_suite.framework == TestFramework::Hspec {
+            args.insert(0, "--times".to_owned());
+        }
+
+        let task = Task {
+            name: test_suite.name.clone(),
+            command: project.test_command(&test_suite.package, &test_suite.name, &args),
+        };
+        let root = project.root.clone();
+
+        self.bottom_view = BottomView::Output;
+
+        match self.tasks.start(&task, &root, ctx) {
+            Ok(()) => self.tests.running = Some((suite, filter)),
+            Err(e) => self.notifications.error(e.to_string()),
+        }
+    }
+
+    fn finish_tests(&mut self) {
+        let Some((index, filter)) = self.tests.running.take() else { return };
+        let Some(suite) = self.tests.suites.get_mut(index) else { return };
+
+        let results = suite.parse(&self.tasks.output.join("\n"));
+        suite.status = self.tasks.finished.as_ref().map(|(_, status)| *status);
+
+        if filter == TestFilter::All {
+            suite.results = results;
+        } else {
+            for node in results {
+                insert(&mut suite.results, &[], node);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HSPEC: &str = "\
+Data.Parser
+  parse
+    parses numbers [✔] (12ms)
+    parses words [✘]
+    handles empty input [‐]
+      # PENDING: No reason given
+  render
+    renders a list [✔]
+
+Failures:
+
+  test/Data/ParserSpec.hs:14:32: 
+  1) Data.Parser, parse, parses words
+       expected: [\"a\",\"b\"]
+        but got: [\"a b\"]
+
+  To rerun use: --match \"/Data.Parser/parse/parses words/\" --seed 1733540
+
+Randomized with seed 1733540
+
+Finished in 0.0041 seconds
+4 examples, 1 failure, 1 pending
+";
+
+    const TASTY: &str = "\
+Tests
+  Unit tests
+    List comparison (different length): FAIL (0.02s)
+      test/Main.hs:25:
+      expected: LT
+       but got: GT
+      Use -p '/List comparison (different length)/' to rerun this test only.
+    List comparison (same length):      OK
+  Properties
+    sort == sort . reverse:             OK (0.03s)
+      +++ OK, passed 100 tests.
+
+1 out of 3 tests failed (0.05s)
+";
+
+    const HUNIT: &str = "\
+### Failure in: 1:addition
+test/Main.hs:10
+expected: 3
+ but got: 4
+### Error in: 2:reads config
+user error (config.toml: openFile: does not exist)
+Cases: 3  Tried: 3  Errors: 1  Failures: 1
+";
+
+    fn path(names: &[&str]) -> Vec<String> {
+        names.iter().map(|&n| n.to_owned()).collect()
+    }
+
+    fn node<'a>(nodes: &'a [TestNode], names: &[&str]) -> &'a TestNode {
+        let (name, rest) = names.split_first().unwrap();
+        let node = nodes.iter().find(|n| n.name == *name).unwrap_or_else(|| panic!("no {name} in {nodes:?}"));
+        if rest.is_empty() { node } else { self::node(&node.children, rest) }
+    }
+
+    fn suite(framework: TestFramework, results: Vec<TestNode>) -> TestSuite {
+        TestSuite {
+            name: "spec".to_owned(),
+            package: "parser".to_owned(),
+            framework,
+            results,
+            status: None,
+        }
+    }
+
+    #[test]
+    fn hspec_tree() {
+        let nodes = parse_hspec(HSPEC);
+
+        assert_eq!(nodes.len(), 1);
+        let parse = node(&nodes, &["Data.Parser", "parse"]);
+        assert_eq!(parse.children.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+            ["parses numbers", "parses words", "handles empty input"]);
+        assert_eq!(parse.summary(), Some(TestStatus::Failed));
+
+        let numbers = node(&nodes, &["Data.Parser", "parse", "parses numbers"]);
+        assert_eq!(numbers.status, Some(TestStatus::Passed));
+        assert_eq!(numbers.duration.as_deref(), Some("12ms"));
+
+        let pending = node(&nodes, &["Data.Parser", "parse", "handles empty input"]);
+        assert_eq!(pending.status, Some(TestStatus::Skipped));
+        assert_eq!(pending.message, "# PENDING: No reason given");
+
+        let render = node(&nodes, &["Data.Parser", "render"]);
+        assert_eq!(render.summary(), Some(TestStatus::Passed));
+    }
+
+    #[test]
+    fn hspec_failures_section() {
+        let nodes = parse_hspec(HSPEC);
+        let failed = node(&nodes, &["Data.Parser", "parse", "parses words"]);
+
+        assert_eq!(failed.status, Some(TestStatus::Failed));
+        assert_eq!(failed.location, Some(("test/Data/ParserSpec.hs".to_owned(), 13, 31)));
+        assert_eq!(failed.message, "expected: [\"a\",\"b\"]\n but got: [\"a b\"]");
+    }
+
+    #[test]
+    fn hspec_progress_format() {
+        let nodes = parse_hspec("Data.Parser\n  - parses numbers\n  - parses words FAILED [1]\n");
+
+        assert_eq!(node(&nodes, &["Data.Parser", "parses numbers"]).status, Some(TestStatus::Passed));
+        assert_eq!(node(&nodes, &["Data.Parser", "parses words"]).status, Some(TestStatus::Failed));
+    }
+
+    #[test]
+    fn tasty_tree() {
+        let nodes = parse_tasty(TASTY);
+
+        let failed = node(&nodes, &["Tests", "Unit tests", "List comparison (different length)"]);
+        assert_eq!(failed.status, Some(TestStatus::Failed));
+        assert_eq!(failed.duration.as_deref(), Some("0.02s"));
+        assert_eq!(failed.location, Some(("test/Main.hs".to_owned(), 24, 0)));
+        assert_eq!(failed.message, "test/Main.hs:25:\nexpected: LT\n but got: GT");
+
+        let same = node(&nodes, &["Tests", "Unit tests", "List comparison (same length)"]);
+        assert_eq!(same.status, Some(TestStatus::Passed));
+        assert!(same.message.is_empty());
+
+        let property = node(&nodes, &["Tests", "Properties", "sort == sort . reverse"]);
+        assert_eq!(property.status, Some(TestStatus::Passed));
+        assert_eq!(property.message, "+++ OK, passed 100 tests.");
+
+        assert_eq!(node(&nodes, &["Tests"]).children.len(), 2);
+    }
+
+    #[test]
+    fn hunit_failures() {
+        let nodes = parse_hunit(HUNIT);
+
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].name, "1:addition");
+        assert_eq!(nodes[0].location, Some(("test/Main.hs".to_owned(), 9, 0)));
+        assert_eq!(nodes[0].message, "test/Main.hs:10\nexpected: 3\nbut got: 4");
+        assert_eq!(nodes[1].name, "2:reads config");
+        assert_eq!(nodes[1].status, Some(TestStatus::Failed));
+        assert_eq!(nodes[1].message, "user error (config.toml: openFile: does not exist)");
+    }
+
+    #[test]
+    fn rerun_failures_filters_failed_cases() {
+        let hspec = suite(TestFramework::Hspec, parse_hspec(HSPEC));
+        let failures = hspec.failures();
+
+        assert_eq!(failures, [path(&["Data.Parser", "parse", "parses words"])]);
+        assert_eq!(hspec.framework.filter_args(&failures).unwrap(), ["--match", "/Data.Parser/parse/parses words/"]);
+
+        let tasty = suite(TestFramework::Tasty, parse_tasty(TASTY));
+        assert_eq!(tasty.framework.filter_args(&tasty.failures()).unwrap(),
+            ["-p", "$0 == \"Tests.Unit tests.List comparison (different length)\""]);
+
+        let hunit = suite(TestFramework::HUnit, parse_hunit(HUNIT));
+        assert_eq!(hunit.failures().len(), 2);
+        assert_eq!(hunit.framework.filter_args(&hunit.failures()), None);
+    }
+
+    #[test]
+    fn rerun_results_replace_only_the_rerun_cases() {
+        let mut results = parse_hspec(HSPEC);
+        let rerun = parse_hspec("\
+Data.Parser
+  parse
+    parses words [✔]
+
+Finished in 0.0008 seconds
+1 example, 0 failures
+");
+
+        for node in rerun {
+            insert(&mut results, &[], node);
+        }
+
+        let parse = node(&results, &["Data.Parser", "parse"]);
+        assert_eq!(parse.children.len(), 3);
+        assert_eq!(parse.summary(), Some(TestStatus::Passed));
+
+        let words = node(&results, &["Data.Parser", "parse", "parses words"]);
+        assert_eq!(words.status, Some(TestStatus::Passed));
+        assert!(words.message.is_empty());
+        assert_eq!(words.location, None);
+
+        assert_eq!(node(&results, &["Data.Parser", "render", "renders a list"]).status, Some(TestStatus::Passed));
+        assert!(suite(TestFramework::Hspec, results).failures().is_empty());
+    }
+
+    #[test]
+    fn insert_creates_missing_groups() {
+        let mut nodes = vec![];
+        insert(&mut nodes, &path(&["A", "B"]), TestNode { status: Some(TestStatus::Passed), ..TestNode::new("one") });
+        insert(&mut nodes, &path(&["A", "B"]), TestNode { status: Some(TestStatus::Failed), ..TestNode::new("two") });
+        insert(&mut nodes, &path(&["A"]), TestNode { status: Some(TestStatus::Skipped), ..TestNode::new("three") });
+        insert(&mut nodes, &path(&["A", "B"]), TestNode { status: Some(TestStatus::Passed), ..TestNode::new("two") });
+
+        assert_eq!(nodes.len(), 1);
+        let a = node(&nodes, &["A"]);
+        assert_eq!(a.children.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["B", "three"]);
+        assert_eq!(node(&nodes, &["A", "B"]).children.len(), 2);
+        assert_eq!(node(&nodes, &["A", "B", "two"]).status, Some(TestStatus::Passed));
+        assert_eq!(a.summary(), Some(TestStatus::Passed));
+    }
+}
@@ -0,0 +1,180 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    app::Fexc,
+    events::Event,
+    ghc,
+    project::{Package, Project},
+    repl::{Repl, ReplError, ReplLineKind},
+    symbol_index::is_haskell_file,
+};
+
+/// Line printed to GHCi's stderr after a reload, so its diagnostics are known to be complete.
+const DONE_MARKER: &str = "<<fexc-watch-done>>";
+
+/// ghcid-style type checking: a background GHCi session that reloads the project whenever a file is saved.
+#[derive(Default)]
+pub struct Watch {
+    ghci: Repl,
+    root: Option<PathBuf>,
+    /// Whether a reload has been sent and its diagnostics are not in yet.
+    checking: bool,
+    /// A file saved while checking, which is reloaded once the check finishes.
+    queued: Option<PathBuf>,
+    /// Files added with `:add` when GHCi is not started by cabal or stack, which load the project themselves.
+    added: Vec<PathBuf>,
+    /// Set when the session could not be started, so it is not retried every frame.
+    failed: bool,
+}
+
+impl Watch {
+    pub fn is_running(&self) -> bool {
+        self.ghci.is_running()
+    }
+
+    pub fn is_checking(&self) -> bool {
+        self.checking
+    }
+
+    pub fn command(&self) -> String {
+        self.ghci.command.join(" ")
+    }
+
+    fn start(&mut self, project: Option<&Project>, ctx: &egui::Context) -> Result<(), String> {
+        self.root = project.map(|p| p.root.clone());
+        self.added.clear();
+        self.ghci.transcript.clear();
+
+        self.ghci.start(project, ctx).map_err(|e| e.to_string())?;
+        self.ghci.send(":set -ferror-spans").map_err(|e| e.to_string())?;
+
+        // cabal and stack load the project on start, report what they found
+        self.finish_command().map_err(|e| e.to_string())
+    }
+
+    fn stop(&mut self) {
+        self.ghci.stop();
+        self.checking = false;
+        self.queued = None;
+    }
+
+    /// Reloads after `path` was saved.
+    fn file_saved(&mut self, path: &Path) {
+        let is_cabal_file = path.extension().is_some_and(|e| e == "cabal");
+
+        // Give a session that failed to start another chance once something was fixed
+        if self.failed && (is_haskell_file(path) || is_cabal_file) {
+            self.failed = false;
+            return;
+        }
+
+        if !self.is_running() || !is_haskell_file(path) {
+            return;
+        }
+
+        if self.checking {
+            self.queued = Some(path.to_owned());
+            return;
+        }
+
+        let plain_ghci = self.ghci.command.first().is_some_and(|c| c == "ghci");
+        let result = if plain_ghci && !self.added.iter().any(|p| p == path) {
+            self.added.push(path.to_owned());
+            self.ghci.send(&format!(":add \"{}\"", path.display()))
+        } else {
+            self.ghci.send(":reload")
+        };
+
+        if let Err(e) = result.and_then(|_| self.finish_command()) {
+            log::error!("{e}");
+        }
+    }
+
+    fn finish_command(&mut self) -> Result<(), ReplError> {
+        self.ghci.send(&format!("System.IO.hPutStrLn System.IO.stderr \"{DONE_MARKER}\""))?;
+        self.checking = true;
+        Ok(())
+    }
+
+    /// Returns the messages of a finished check.
+    fn poll(&mut self, packages: &[Package]) -> Option<Vec<ghc::GhcMessage>> {
+        self.ghci.poll();
+
+        if !self.checking {
+            return None;
+        }
+
+        // A session that exited, e.g. because the project does not build, reports its last output
+        let done = match self.ghci.transcript.iter().position(|l| l.kind == ReplLineKind::Error && l.text == DONE_MARKER) {
+            Some(done) => done,
+            None if !self.ghci.is_running() => {
+                self.failed = true;
+                self.ghci.transcript.len().checked_sub(1)?
+            }
+            None => return None,
+        };
+
+        let output = self.ghci.transcript.drain(..=done)
+            .filter(|l| l.kind != ReplLineKind::Input)
+            .map(|l| l.text)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.checking = false;
+
+        let root = self.root.clone()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+
+        Some(ghc::parse_messages(&output, &root, packages))
+    }
+}
+
+impl Fexc {
+    /// Starts or stops the watch session to follow the setting and collects finished checks.
+    pub fn update_watch(&mut self, ctx: &egui::Context) {
+        let watch = &mut self.watch;
+
+        if !self.config.watch_mode {
+            if watch.is_running() {
+                watch.stop();
+                for path in self.diagnostics.set_watch(Default::default()) {
+                    self.events.publish(Event::DiagnosticsUpdated { path });
+                }
+            }
+            watch.failed = false;
+            return;
+        }
+
+        if watch.is_running() && watch.root.as_ref() != self.project.as_ref().map(|p| &p.root) {
+            watch.stop();
+        }
+
+        let packages = self.project.as_ref().map_or(&[][..], |p| &p.packages);
+
+        if let Some(messages) = watch.poll(packages) {
+            for path in self.diagnostics.set_watch(ghc::to_diagnostics(&messages)) {
+                self.events.publish(Event::DiagnosticsUpdated { path });
+            }
+
+            if let Some(path) = watch.queued.take() {
+                watch.file_saved(&path);
+            }
+        }
+
+        if !watch.is_running()
+            && !watch.failed
+            && let Err(e) = watch.start(self.project.as_ref(), ctx)
+        {
+            watch.failed = true;
+            self.notifications.error(e);
+        }
+    }
+
+    /// Rechecks the project in watch mode after a file was saved.
+    pub fn watch_file_saved(&mut self, path: &Path) {
+        if self.config.watch_mode {
+            self.watch.file_saved(path);
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use egui_phosphor::bold as ph;
+
+use crate::{
+    app::Fexc,
+    cabal::{split_dependency, CabalFile, Field, Item},
+    project::ComponentKind,
+    widgets::pages::project::component_icon,
+};
+
+/// Package description fields shown at the top of the form, whether the file sets them or not.
+const METADATA_FIELDS: [&str; 11] = [
+    "cabal-version",
+    "name",
+    "version",
+    "synopsis",
+    "description",
+    "license",
+    "author",
+    "maintainer",
+    "category",
+    "homepage",
+    "build-type",
+];
+
+/// Which `.cabal` buffers show the form instead of their text, and the text typed into the form's inputs.
+#[derive(Default)]
+pub struct CabalFormState {
+    pub form_docs: HashSet<u64>,
+    inputs: HashMap<egui::Id, String>,
+    /// Modules the user chose not to add, per `.cabal` file.
+    pub ignored_modules: HashSet<(PathBuf, String)>,
+}
+
+/// A stanza a new module can be added to.
+pub struct OfferTarget {
+    pub section: usize,
+    pub title: String,
+    pub is_library: bool,
+}
+
+/// A saved module that none of the stanzas of its `.cabal` file list.
+pub struct ModuleOffer {
+    pub manifest: PathBuf,
+    pub module: String,
+    pub targets: Vec<OfferTarget>,
+    pub selected: usize,
+}
+
+enum Edit {
+    Value(Option<usize>, String, String),
+    Replace(Item, String),
+    Add(Option<usize>, &'static str, String),
+    Remove(Option<usize>, &'static str, usize),
+}
+
+struct Labels {
+    add: String,
+    remove: String,
+    package_name: String,
+    version_bounds: String,
+}
+
+/// Shows the Text/Form switch for `.cabal` buffers, returning whether the form is shown.
+pub fn view_switch(ui: &mut egui::Ui, app: &mut Fexc) -> bool {
+    let doc = app.documents.active();
+    let is_cabal_file = doc.path.as_deref().is_some_and(|p| p.extension().is_some_and(|e| e == "cabal"));
+    if !is_cabal_file {
+        return false;
+    }
+
+    let id = doc.id;
+    let text_label = format!("{} {}", ph::TEXT_ALIGN_LEFT, app.i18n("text_view"));
+    let form_label = format!("{} {}", ph::LIST_DASHES, app.i18n("form_view"));
+    let mut form = app.cabal_form.form_docs.contains(&id);
+
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut form, false, text_label);
+        ui.selectable_value(&mut form, true, form_label);
+    });
+
+    if form {
+        app.cabal_form.form_docs.insert(id);
+    } else {
+        app.cabal_form.form_docs.remove(&id);
+    }
+
+    form
+}
+
+/// A structured view of the active `.cabal` buffer whose edits are written back into its text.
+pub struct CabalForm<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> CabalForm<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        CabalForm { app }
+    }
+}
+
+impl egui::Widget for CabalForm<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let package_label = self.app.i18n("package").to_owned();
+        let list_labels = [
+            ("exposed-modules", self.app.i18n("exposed_modules").to_owned()),
+            ("other-modules", self.app.i18n("other_modules").to_owned()),
+            ("default-extensions", self.app.i18n("default_extensions").to_owned()),
+            ("ghc-options", self.app.i18n("ghc_options").to_owned()),
+        ];
+        let dependencies_label = self.app.i18n("dependencies").to_owned();
+        let labels = Labels {
+            add: self.app.i18n("add").to_owned(),
+            remove: self.app.i18n("remove").to_owned(),
+            package_name: self.app.i18n("package_name").to_owned(),
+            version_bounds: self.app.i18n("version_bounds").to_owned(),
+        };
+
+        let doc = self.app.documents.active();
+        let doc_id = doc.id;
+        let file = CabalFile::parse(&doc.text);
+        let inputs = &mut self.app.cabal_form.inputs;
+        let mut edit = None;
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui|
+        {
+            ui.spacing_mut().item_spacing = egui::vec2(6.0, 6.0);
+
+            ui.label(egui::RichText::new(format!("{} {package_label}", ph::PACKAGE)).strong());
+
+            egui::Grid::new(("cabal_package", doc_id))
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for name in METADATA_FIELDS {
+                        let value = file.field(None, name).map(Field::value).unwrap_or_default();
+                        let id = egui::Id::new(("cabal_field", doc_id, name));
+
+                        ui.label(egui::RichText::new(name).monospace().weak());
+                        if let Some(value) = draft_edit(ui, inputs, id, &value, name == "description") {
+                            edit = Some(Edit::Value(None, name.to_owned(), value));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            for (index, section) in file.sections.iter().enumerate() {
+                let kind = section.component_kind();
+                if kind.is_none() && section.kind != "common" {
+                    continue;
+                }
+
+                let icon = kind.map_or(ph::SHARE_NETWORK, component_icon);
+                let title = format!("{icon} {} {}", section.kind, section.name);
+
+                ui.separator();
+
+                egui::CollapsingHeader::new(egui::RichText::new(title.trim()).monospace().strong())
+                    .id_salt(("cabal_section", doc_id, index))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        let section = Some(index);
+
+                        ui.label(egui::RichText::new(&dependencies_label).weak());
+                        dependencies_ui(ui, &file, section, (doc_id, index), inputs, &labels, &mut edit);
+
+                        for (name, label) in &list_labels {
+                            // Only libraries expose modules
+                            if *name == "exposed-modules"
+                                && kind != Some(ComponentKind::Library)
+                                && file.field(section, name).is_none()
+                            {
+                                continue;
+                            }
+
+                            ui.label(egui::RichText::new(label).weak());
+                            list_ui(ui, &file, section, name, (doc_id, index), inputs, &labels, &mut edit);
+                        }
+                    });
+            }
+        });
+
+        if let Some(edit) = edit {
+            let new_text = match edit {
+                Edit::Value(section, name, value) => file.set_value(section, &name, &value),
+                Edit::Replace(item, text) => file.replace_item(&item, &text),
+                Edit::Add(section, name, text) => file.add_item(section, name, &text),
+                Edit::Remove(section, name, index) => file.remove_item(section, name, index),
+            };
+
+            self.app.documents.active_mut().replace_preserving_cursor(new_text);
+        }
+
+        ui.label("")
+    }
+}
+
+/// `build-depends` as rows of package names and version bounds.
+fn dependencies_ui(
+    ui: &mut egui::Ui,
+    file: &CabalFile,
+    section: Option<usize>,
+    salt: (u64, usize),
+    inputs: &mut HashMap<egui::Id, String>,
+    labels: &Labels,
+    edit: &mut Option<Edit>,
+) {
+    let items = file.field(section, "build-depends").map(Field::items).unwrap_or_default();
+
+    egui::Grid::new(("cabal_dependencies", salt))
+        .num_columns(3)
+        .show(ui, |ui| {
+            for (i, item) in items.iter().enumerate() {
+                let (name, bounds) = split_dependency(&item.text);
+                let name_id = egui::Id::new(("cabal_dependency_name", salt, i));
+                let bounds_id = egui::Id::new(("cabal_dependency_bounds", salt, i));
+
+                if let Some(name) = draft_edit(ui, inputs, name_id, name, false) {
+                    *edit = Some(Edit::Replace(item.clone(), format!("{name} {bounds}")));
+                }
+                if let Some(bounds) = draft_edit(ui, inputs, bounds_id, bounds, false) {
+                    *edit = Some(Edit::Replace(item.clone(), format!("{name} {bounds}")));
+                }
+                if ui.small_button(ph::X).on_hover_text(&labels.remove).clicked() {
+                    *edit = Some(Edit::Remove(section, "build-depends", i));
+                }
+                ui.end_row();
+            }
+        });
+
+    let id = egui::Id::new(("cabal_new_dependency", salt));
+    if let Some(text) = new_item_ui(ui, inputs, id, &format!("{} {}", labels.package_name, labels.version_bounds), labels) {
+        *edit = Some(Edit::Add(section, "build-depends", text));
+    }
+}
+
+/// A list field such as `exposed-modules` as removable chips with an input for new elements.
+#[allow(clippy::too_many_arguments)]
+fn list_ui(
+    ui: &mut egui::Ui,
+    file: &CabalFile,
+    section: Option<usize>,
+    name: &'static str,
+    salt: (u64, usize),
+    inputs: &mut HashMap<egui::Id, String>,
+    labels: &Labels,
+    edit: &mut Option<Edit>,
+) {
+    let items = file.field(section, name).map(Field::items).unwrap_or_default();
+
+    ui.horizontal_wrapped(|ui| {
+        for (i, item) in items.iter().enumerate() {
+            egui::Frame::new()
+                .fill(ui.visuals().faint_bg_color)
+                .corner_radius(4.0)
+                .inner_margin(egui::Margin::symmetric(4, 2))
+                .show(ui, |ui| {
+                    ui.spacing_mut().item_spacing.x = 2.0;
+                    ui.label(egui::RichText::new(&item.text).monospace());
+                    if ui.add(egui::Button::new(ph::X).frame(false).small()).on_hover_text(&labels.remove).clicked() {
+                        *edit = Some(Edit::Remove(section, name, i));
+                    }
+                });
+        }
+    });
+
+    let id = egui::Id::new(("cabal_new_item", salt, name));
+    if let Some(text) = new_item_ui(ui, inputs, id, name, labels) {
+        *edit = Some(Edit::Add(section, name, text));
+    }
+}
+
+/// An input and an add button, returning the entered text when it is submitted.
+fn new_item_ui(
+    ui: &mut egui::Ui,
+    inputs: &mut HashMap<egui::Id, String>,
+    id: egui::Id,
+    hint: &str,
+    labels: &Labels,
+) -> Option<String> {
+    let text = inputs.entry(id).or_default();
+    let mut submit = false;
+
+    ui.horizontal(|ui| {
+        let response = ui.add(egui::TextEdit::singleline(text).id(id).hint_text(hint).desired_width(240.0));
+        submit |= response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        submit |= ui.button(format!("{} {}", ph::PLUS, labels.add)).clicked();
+    });
+
+    (submit && !text.trim().is_empty()).then(|| inputs.remove(&id).unwrap_or_default())
+}
+
+/// An input editing a copy of `value` while it has focus, returning the new text once focus leaves it,
+/// so the buffer is not rewritten on every keystroke.
+fn draft_edit(
+    ui: &mut egui::Ui,
+    inputs: &mut HashMap<egui::Id, String>,
+    id: egui::Id,
+    value: &str,
+    multiline: bool,
+) -> Option<String> {
+    let mut text = inputs.remove(&id)
+        .filter(|_| ui.memory(|m| m.has_focus(id)))
+        .unwrap_or_else(|| value.to_owned());
+
+    let text_edit = if multiline {
+        egui::TextEdit::multiline(&mut text).desired_rows(3)
+    } else {
+        egui::TextEdit::singleline(&mut text)
+    };
+    let response = ui.add(text_edit.id(id).desired_width(f32::INFINITY));
+
+    if response.has_focus() {
+        inputs.insert(id, text);
+        None
+    } else {
+        (response.lost_focus() && text != value).then_some(text)
+    }
+}
+
+/// Asks whether a saved module that no stanza lists should be added to `exposed-modules` or `other-modules`.
+pub struct ModuleOfferDialog<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> ModuleOfferDialog<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        ModuleOfferDialog { app }
+    }
+
+    pub fn show(self, ctx: &egui::Context) {
+        let title = self.app.i18n("new_module").to_owned();
+        let not_listed_label = self.app.i18n("module_not_listed").to_owned();
+        let exposed_label = self.app.i18n("add_to_exposed_modules").to_owned();
+        let other_label = self.app.i18n("add_to_other_modules").to_owned();
+        let ignore_label = self.app.i18n("ignore").to_owned();
+
+        let Some(offer) = &mut self.app.module_offer else { return };
+        let mut open = true;
+        let mut choice = None;
+
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut open)
+            .show(ctx, |ui|
+        {
+            let manifest = offer.manifest.file_name().unwrap_or_default().to_string_lossy();
+            ui.label(format!("{} {not_listed_label} {manifest}", offer.module));
+
+            if offer.targets.len() > 1 {
+                egui::ComboBox::from_id_salt("module_offer_target")
+                    .selected_text(&offer.targets[offer.selected].title)
+                    .show_ui(ui, |ui| {
+                        for (i, target) in offer.targets.iter().enumerate() {
+                            ui.selectable_value(&mut offer.selected, i, &target.title);
+                        }
+                    });
+            }
+
+            ui.horizontal(|ui| {
+                if offer.targets[offer.selected].is_library && ui.button(&exposed_label).clicked() {
+                    choice = Some(Some("exposed-modules"));
+                }
+                if ui.button(&other_label).clicked() {
+                    choice = Some(Some("other-modules"));
+                }
+                if ui.button(&ignore_label).clicked() {
+                    choice = Some(None);
+                }
+            });
+        });
+
+        if !open {
+            choice = Some(None);
+        }
+
+        let Some(choice) = choice else { return };
+        let Some(offer) = self.app.module_offer.take() else { return };
+
+        match choice {
+            Some(field) => self.app.add_offered_module(&offer, field),
+            None => {
+                self.app.cabal_form.ignored_modules.insert((offer.manifest, offer.module));
+            }
+        }
+    }
+}
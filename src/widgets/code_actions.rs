@@ -0,0 +1,76 @@
+use egui_phosphor::bold as ph;
+
+use crate::{
+    app::Fexc,
+    code_actions::QuickFix,
+    document::char_to_position,
+};
+
+pub struct CodeActionsMenu<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> CodeActionsMenu<'app> {
+    pub const WIDTH: f32 = 360.0;
+
+    pub fn new(app: &'app mut Fexc) -> Self {
+        CodeActionsMenu { app }
+    }
+
+    pub fn show(self, ctx: &egui::Context) {
+        if !self.app.code_actions.menu_open {
+            return;
+        }
+
+        let doc = self.app.documents.active();
+        let line = char_to_position(&doc.text, doc.cursor).line;
+        let fixes = self.app.code_actions.fixes(doc.id, doc.version, line);
+        let anchor = self.app.code_actions.anchor.unwrap_or_else(|| ctx.screen_rect().center());
+        let empty_label = self.app.i18n("no_code_actions").to_owned();
+
+        let mut picked = None;
+        let mut close = ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape));
+
+        egui::Area::new(egui::Id::new("code_actions_menu"))
+            .fixed_pos(anchor)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui|
+        {
+            egui::Frame::popup(ui.style())
+                .show(ui, |ui|
+            {
+                ui.set_max_width(Self::WIDTH);
+
+                if fixes.is_empty() {
+                    ui.weak(&empty_label);
+                }
+
+                for (i, fix) in fixes.iter().enumerate() {
+                    let icon = match fix {
+                        QuickFix::Lsp(_) => ph::WRENCH,
+                        QuickFix::Hlint(_) => ph::LIGHTBULB,
+                    };
+
+                    let button = egui::Button::new(format!("{icon} {}", fix.title()))
+                        .frame(false)
+                        .wrap_mode(egui::TextWrapMode::Truncate);
+
+                    if ui.add(button).clicked() {
+                        picked = Some(i);
+                    }
+                }
+            });
+
+            if ui.input(|i| i.pointer.any_click()) && !ui.ui_contains_pointer() {
+                close = true;
+            }
+        });
+
+        if let Some(fix) = picked.and_then(|i| fixes.into_iter().nth(i)) {
+            self.app.code_actions.menu_open = false;
+            self.app.apply_quick_fix(fix);
+        } else if close {
+            self.app.code_actions.menu_open = false;
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{
+    app::Fexc,
+    widgets::quick_pick::{QuickPick, QuickPickResponse},
+};
+
+#[derive(Default)]
+pub struct CommandPaletteState {
+    pub query: String,
+    pub selected: usize,
+}
+
+pub struct CommandPaletteDialog<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> CommandPaletteDialog<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        CommandPaletteDialog { app }
+    }
+
+    pub fn show(self, ctx: &egui::Context) {
+        let hint = self.app.i18n("type_command").to_owned();
+        let recent_label = self.app.i18n("recently_used").to_owned();
+
+        // Labels depend on the language and the registered commands, so they are built every frame
+        let labels = self.app.commands.iter()
+            .map(|c| self.app.command_label(c))
+            .collect::<Vec<_>>();
+
+        let Some(state) = &mut self.app.command_palette else { return };
+        let commands = &self.app.commands;
+        let results = commands.search(&state.query, &labels);
+
+        let response = QuickPick::new("command_palette", &mut state.query, &mut state.selected, &hint, results.len())
+            .show(ctx, |ui, i, selected| {
+                let Some(command) = commands.get(results[i]) else { return ui.label("") };
+
+                ui.horizontal(|ui| {
+                    let response = ui.selectable_label(selected, &labels[results[i]]);
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if let Some(shortcut) = &commands.shortcut(command) {
+                            ui.label(egui::RichText::new(ui.ctx().format_shortcut(shortcut)).weak().monospace());
+                        }
+                        if commands.recent().contains(&command.id) {
+                            ui.label(egui::RichText::new(&recent_label).weak());
+                        }
+                    });
+
+                    response
+                }).inner
+            });
+
+        match response {
+            QuickPickResponse::Picked(i) => {
+                self.app.command_palette = None;
+
+                if let Some(id) = results.get(i).and_then(|&i| self.app.commands.get(i)).map(|c| c.id.clone()) {
+                    self.app.run_command(&id, ctx);
+                }
+            }
+            QuickPickResponse::Closed => self.app.command_palette = None,
+            QuickPickResponse::None => {}
+        }
+    }
+}
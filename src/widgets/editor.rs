@@ -1,8 +1,18 @@
-use std::f32;
+use std::{collections::BTreeMap, f32};
 
-use egui_extras::syntax_highlighting::{highlight, CodeTheme};
+use egui::text::{CCursor, CCursorRange};
+use egui_phosphor::bold as ph;
+use egui_extras::syntax_highlighting::{highlight, highlight_with, CodeTheme};
 
-use crate::app::Fexc;
+use crate::{
+    app::Fexc,
+    config::Config,
+    document::{char_to_position, line_col_to_char, position_to_byte, Document},
+    hints::Hints,
+    languages::{Edit, LanguageDef},
+    snippets::Snippets,
+    widgets::cabal_form::{self, CabalForm},
+};
 
 pub struct CodeEditor<'app> {
     app: &'app mut Fexc,
@@ -11,29 +21,63 @@ pub struct CodeEditor<'app> {
 impl<'app> CodeEditor<'app> {
     pub const DARK_BG_COLOR: egui::Color32 = egui::Color32::from_rgb(24, 25, 38);
     pub const LIGHT_BG_COLOR: egui::Color32 = egui::Color32::from_rgb(220, 224, 232);
+    pub const GUTTER_WIDTH: f32 = 20.0;
 
     pub fn new(app: &'app mut Fexc) -> Self {
         Self { app }
     }
+
+    /// The id of the text edit showing `doc`, which has focus while the user types into it.
+    pub fn id(doc: &Document) -> egui::Id {
+        egui::Id::new(("code_editor", doc.id))
+    }
 }
 
 impl egui::Widget for CodeEditor<'_> {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
         let rows = ui.max_rect().height() / self.app.config.editor_font_size as f32;
-        let language = "hs";
-        let code = &mut self.app.code;
+
+        tabs_show(ui, self.app);
+
+        if cabal_form::view_switch(ui, self.app) {
+            return ui.add(CabalForm::new(self.app));
+        }
+
+        let doc = self.app.documents.active_mut();
+        let language = self.app.languages.for_path(doc.path.as_deref());
+        let syntect = self.app.languages.syntect();
+        let hints = &self.app.hints;
+        let snippets = &self.app.snippets;
+        let diagnostics = doc.path.as_deref()
+            .map(|path| self.app.diagnostics.for_path(path).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let config = &self.app.config;
+        let code_actions = &mut self.app.code_actions;
+        let mut clicked_lens = None;
+        let mut unresolved_lenses = vec![];
+
+        let editor_id = CodeEditor::id(doc);
+        let jumped = restore_editor_state(ui.ctx(), editor_id, doc);
+
+        if ui.memory(|m| m.has_focus(editor_id)) {
+            language_keys(ui, editor_id, doc, &language.def, snippets);
+        }
 
         let theme = CodeTheme::from_memory(ui.ctx(), ui.style());
 
+        let inline_hints = if hints.source == Some((doc.id, doc.version)) {
+            inline_hints(ui, &doc.text, hints, config)
+        } else {
+            vec![]
+        };
+
         let mut layouter = |ui: &egui::Ui, buf: &dyn egui::TextBuffer, _: f32| {
-            let mut layout_job = highlight(
-                ui.ctx(),
-                ui.style(),
-                &theme,
-                buf.as_str(),
-                language,
-            );
+            let mut layout_job = match syntect {
+                Some(settings) => highlight_with(ui.ctx(), ui.style(), &theme, buf.as_str(), &language.syntax, settings),
+                None => highlight(ui.ctx(), ui.style(), &theme, buf.as_str(), &language.syntax),
+            };
             layout_job.wrap.max_width = f32::INFINITY;
+            insert_gaps(&mut layout_job, &inline_hints);
             ui.fonts(|f| {
                 f.layout_job(layout_job)
             })
@@ -55,7 +99,7 @@ impl egui::Widget for CodeEditor<'_> {
                 .show(ui, |ui| 
             {
                 ui.horizontal_top(|ui| {
-                    numlines_show(ui, code, rows as usize, self.app.config.editor_font_size as f32);
+                    numlines_show(ui, &doc.text, rows as usize, self.app.config.editor_font_size as f32);
 
                     egui::Frame::new()
                         .fill(match self.app.config.theme {
@@ -69,27 +113,456 @@ impl egui::Widget for CodeEditor<'_> {
                             .auto_shrink([true; 2])
                             .show(ui, |ui| 
                         {
-                            ui.add(
-                                egui::TextEdit::multiline(code)
-                                    .margin(egui::vec2(10.0, 10.0))
-                                    .frame(false)
-                                    .font(egui::TextStyle::Monospace)
-                                    .code_editor()
-                                    .desired_rows(rows as usize)
-                                    .lock_focus(true)
-                                    .desired_width(f32::INFINITY)
-                                    .layouter(&mut layouter),
-                            );
+                            let output = egui::TextEdit::multiline(&mut doc.text)
+                                .id(editor_id)
+                                .margin(egui::vec2(10.0, 10.0))
+                                .frame(false)
+                                .font(egui::TextStyle::Monospace)
+                                .code_editor()
+                                .desired_rows(rows as usize)
+                                .lock_focus(true)
+                                .desired_width(f32::INFINITY)
+                                .layouter(&mut layouter)
+                                .show(ui);
+
+                            if output.response.changed() {
+                                doc.touch();
+                            }
+
+                            let cursor_rect = output.galley
+                                .pos_from_cursor(CCursor::new(doc.cursor))
+                                .translate(output.galley_pos.to_vec2());
+                            code_actions.anchor = Some(cursor_rect.left_bottom());
+
+                            let line = char_to_position(&doc.text, doc.cursor).line;
+                            if !code_actions.fixes(doc.id, doc.version, line).is_empty() {
+                                let bulb_rect = egui::Rect::from_min_size(
+                                    egui::pos2(output.galley_pos.x - Self::GUTTER_WIDTH, cursor_rect.top()),
+                                    egui::vec2(Self::GUTTER_WIDTH, cursor_rect.height()),
+                                );
+
+                                let response = ui.interact(bulb_rect, editor_id.with("lightbulb"), egui::Sense::click())
+                                    .on_hover_cursor(egui::CursorIcon::PointingHand);
+
+                                ui.painter().text(
+                                    bulb_rect.center(),
+                                    egui::Align2::CENTER_CENTER,
+                                    ph::LIGHTBULB,
+                                    egui::FontId::proportional(cursor_rect.height()),
+                                    egui::Color32::from_rgb(238, 212, 159),
+                                );
+
+                                if response.clicked() {
+                                    code_actions.menu_open = !code_actions.menu_open;
+                                }
+                            }
+
+                            diagnostics_paint(ui, &output, &doc.text, &diagnostics, editor_id);
+
+                            if hints.source == Some((doc.id, doc.version)) {
+                                (clicked_lens, unresolved_lenses) = hints_paint(ui, &output, &doc.text, hints, &inline_hints, config);
+                            }
+
+                            if jumped {
+                                ui.scroll_to_rect(cursor_rect, Some(egui::Align::Center));
+                                output.response.request_focus();
+                            }
+
+                            if let Some(range) = output.cursor_range {
+                                doc.cursor = range.primary.index;
+                                doc.anchor = range.secondary.index;
+                            }
                         });
                     });
                 });
             });
         });
 
+        if let Some(command) = clicked_lens {
+            self.app.execute_lsp_command(&command);
+        }
+
+        self.app.resolve_code_lenses(unresolved_lenses);
+
         ui.label("")
     }
 }
 
+fn tabs_show(ui: &mut egui::Ui, app: &mut Fexc) {
+    let active = app.documents.active_index();
+    let mut switch_to = None;
+
+    ui.horizontal(|ui| {
+        for (i, doc) in app.documents.iter().enumerate() {
+            let label = if doc.dirty {
+                format!("{} ●", doc.name())
+            } else {
+                doc.name()
+            };
+
+            let response = ui.selectable_label(i == active, label);
+            let response = match &doc.path {
+                Some(path) => response.on_hover_text(path.display().to_string()),
+                None => response,
+            };
+
+            if response.clicked() {
+                switch_to = Some(i);
+            }
+        }
+    });
+
+    if let Some(i) = switch_to {
+        app.documents.set_active(i);
+    }
+}
+
+/// Underlines diagnostics with a wavy line and shows the messages of the hovered ones.
+fn diagnostics_paint(
+    ui: &egui::Ui,
+    output: &egui::text_edit::TextEditOutput,
+    text: &str,
+    diagnostics: &[&lsp_types::Diagnostic],
+    editor_id: egui::Id,
+) {
+    let painter = ui.painter_at(output.text_clip_rect);
+    let pointer = ui.input(|i| i.pointer.hover_pos()).filter(|p| output.text_clip_rect.contains(*p));
+    let char_rect = |index: usize| output.galley
+        .pos_from_cursor(CCursor::new(index))
+        .translate(output.galley_pos.to_vec2());
+
+    let mut hovered = vec![];
+
+    for diagnostic in diagnostics {
+        let color = match diagnostic.severity {
+            Some(lsp_types::DiagnosticSeverity::ERROR) | None => ui.visuals().error_fg_color,
+            Some(lsp_types::DiagnosticSeverity::WARNING) => ui.visuals().warn_fg_color,
+            _ => ui.visuals().hyperlink_color,
+        };
+
+        let (start, end) = (diagnostic.range.start, diagnostic.range.end);
+        let start_char = line_col_to_char(text, start.line as usize, start.character as usize);
+        let mut end_char = line_col_to_char(text, end.line as usize, end.character as usize);
+
+        // Point locations cover the identifier they are on
+        if end_char <= start_char {
+            end_char = start_char + text.chars()
+                .skip(start_char)
+                .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '\'')
+                .count()
+                .max(1);
+        }
+
+        for line in start.line as usize..=end.line as usize {
+            let from = if line == start.line as usize { start_char } else { line_col_to_char(text, line, 0) };
+            let to = if line == end.line as usize { end_char } else { line_end_char(text, line) };
+
+            let (from, to) = (char_rect(from), char_rect(to));
+            let rect = egui::Rect::from_min_max(from.left_top(), egui::pos2(to.left().max(from.left() + 4.0), from.bottom()));
+
+            wave_paint(&painter, rect.left(), rect.right(), rect.bottom() - 1.0, color);
+
+            if pointer.is_some_and(|p| rect.contains(p)) && !hovered.contains(diagnostic) {
+                hovered.push(*diagnostic);
+            }
+        }
+    }
+
+    if !hovered.is_empty() {
+        egui::Tooltip::always_open(ui.ctx().clone(), ui.layer_id(), editor_id.with("diagnostic"), egui::PopupAnchor::Pointer)
+            .gap(12.0)
+            .show(|ui| {
+                for diagnostic in hovered {
+                    ui.label(egui::RichText::new(&diagnostic.message).monospace());
+
+                    let code = match &diagnostic.code {
+                        Some(lsp_types::NumberOrString::String(code)) => code.clone(),
+                        Some(lsp_types::NumberOrString::Number(code)) => code.to_string(),
+                        None => String::new(),
+                    };
+                    let source = [diagnostic.source.clone().unwrap_or_default(), code].join(" ");
+                    if !source.trim().is_empty() {
+                        ui.label(egui::RichText::new(source.trim()).weak().small());
+                    }
+                }
+            });
+    }
+}
+
+fn wave_paint(painter: &egui::Painter, left: f32, right: f32, y: f32, color: egui::Color32) {
+    const STEP: f32 = 3.0;
+    const AMPLITUDE: f32 = 1.5;
+
+    let points = (0..)
+        .map(|i| i as f32 * STEP)
+        .take_while(|dx| left + dx <= right + STEP)
+        .enumerate()
+        .map(|(i, dx)| egui::pos2((left + dx).min(right), y + if i % 2 == 0 { AMPLITUDE } else { -AMPLITUDE }))
+        .collect::<Vec<_>>();
+
+    painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, color)));
+}
+
+/// An inlay hint in the middle of a line, shown in a gap left in the text before `byte`.
+struct InlineHint {
+    byte: usize,
+    char_index: usize,
+    label: String,
+    width: f32,
+}
+
+fn hint_label(hint: &lsp_types::InlayHint) -> String {
+    match &hint.label {
+        lsp_types::InlayHintLabel::String(label) => label.clone(),
+        lsp_types::InlayHintLabel::LabelParts(parts) => parts.iter().map(|p| p.value.as_str()).collect(),
+    }
+}
+
+fn hint_enabled(hint: &lsp_types::InlayHint, config: &Config) -> bool {
+    match hint.kind {
+        Some(lsp_types::InlayHintKind::TYPE) => config.inlay_type_hints,
+        _ => config.inlay_other_hints,
+    }
+}
+
+/// Whether a hint at `byte` is at the end of its line, where it can be painted without moving any text.
+fn at_line_end(text: &str, byte: usize) -> bool {
+    text.get(byte..).is_none_or(|rest| rest.is_empty() || rest.starts_with(['\n', '\r']))
+}
+
+/// The enabled hints that sit inside a line, with the room each needs.
+fn inline_hints(ui: &egui::Ui, text: &str, hints: &Hints, config: &Config) -> Vec<InlineHint> {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let padding = ui.fonts(|f| f.glyph_width(&font, ' '));
+
+    let mut inline = hints.inlay.iter()
+        .filter(|hint| hint_enabled(hint, config))
+        .filter_map(|hint| {
+            let byte = position_to_byte(text, hint.position);
+            if at_line_end(text, byte) {
+                return None;
+            }
+
+            let label = hint_label(hint);
+            let width = ui.fonts(|f| f.layout_no_wrap(label.clone(), font.clone(), egui::Color32::PLACEHOLDER).size().x) + padding;
+            let char_index = text[..byte].chars().count();
+            Some(InlineHint { byte, char_index, label, width })
+        })
+        .collect::<Vec<_>>();
+
+    inline.sort_by_key(|hint| hint.byte);
+    inline
+}
+
+/// Leaves room in the layout before each inline hint, splitting sections where a hint falls inside one.
+fn insert_gaps(job: &mut egui::text::LayoutJob, hints: &[InlineHint]) {
+    for hint in hints {
+        if !job.text.is_char_boundary(hint.byte) {
+            continue;
+        }
+
+        let Some(i) = job.sections.iter().position(|s| s.byte_range.contains(&hint.byte)) else { continue };
+
+        if job.sections[i].byte_range.start == hint.byte {
+            job.sections[i].leading_space += hint.width;
+        } else {
+            let mut tail = job.sections[i].clone();
+            tail.byte_range.start = hint.byte;
+            tail.leading_space = hint.width;
+            job.sections[i].byte_range.end = hint.byte;
+            job.sections.insert(i + 1, tail);
+        }
+    }
+}
+
+/// Paints inlay hints and code lenses as virtual text after the end of their line.
+/// Returns the command of a clicked code lens and the indices of visible lenses that still need resolving.
+fn hints_paint(
+    ui: &mut egui::Ui,
+    output: &egui::text_edit::TextEditOutput,
+    text: &str,
+    hints: &Hints,
+    inline: &[InlineHint],
+    config: &Config,
+) -> (Option<lsp_types::Command>, Vec<usize>) {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let hint_color = ui.visuals().weak_text_color();
+    let lens_color = ui.visuals().hyperlink_color;
+    let painter = ui.painter_at(output.text_clip_rect);
+    let gap = ui.fonts(|f| f.glyph_width(&font, ' ')) * 2.0;
+    let mut clicked = None;
+    let mut unresolved = vec![];
+
+    // Inline hints go in the gaps the layouter left before their position
+    for hint in inline {
+        let rect = output.galley
+            .pos_from_cursor(CCursor::new(hint.char_index))
+            .translate(output.galley_pos.to_vec2());
+
+        painter.text(egui::pos2(rect.left() - hint.width, rect.top()), egui::Align2::LEFT_TOP, &hint.label, font.clone(), hint_color);
+    }
+
+    // Hints at the end of a line and code lenses follow the line's text
+    let mut per_line: BTreeMap<u32, Vec<(String, Option<&lsp_types::Command>)>> = BTreeMap::new();
+
+    for hint in &hints.inlay {
+        if hint_enabled(hint, config) && at_line_end(text, position_to_byte(text, hint.position)) {
+            per_line.entry(hint.position.line).or_default().push((hint_label(hint), None));
+        }
+    }
+
+    if config.code_lenses {
+        for (i, lens) in hints.lenses.iter().enumerate() {
+            match &lens.command {
+                Some(command) => per_line.entry(lens.range.start.line).or_default().push((command.title.clone(), Some(command))),
+                // Lenses without a command are shown once `codeLens/resolve` fills it in
+                None => {
+                    let rect = output.galley
+                        .pos_from_cursor(CCursor::new(line_end_char(text, lens.range.start.line as usize)))
+                        .translate(output.galley_pos.to_vec2());
+
+                    if output.text_clip_rect.y_range().intersects(rect.y_range()) {
+                        unresolved.push(i);
+                    }
+                }
+            }
+        }
+    }
+
+    for (line, labels) in per_line {
+        let line_end = line_end_char(text, line as usize);
+        let end_rect = output.galley
+            .pos_from_cursor(CCursor::new(line_end))
+            .translate(output.galley_pos.to_vec2());
+
+        let mut x = end_rect.right() + gap;
+
+        for (i, (label, command)) in labels.into_iter().enumerate() {
+            let color = if command.is_some() { lens_color } else { hint_color };
+            let rect = painter.text(egui::pos2(x, end_rect.top()), egui::Align2::LEFT_TOP, &label, font.clone(), color);
+
+            if let Some(command) = command {
+                let response = ui.interact(rect, ui.id().with(("code_lens", line, i)), egui::Sense::click())
+                    .on_hover_cursor(egui::CursorIcon::PointingHand);
+
+                if response.hovered() {
+                    painter.line_segment([rect.left_bottom(), rect.right_bottom()], egui::Stroke::new(1.0, color));
+                }
+                if response.clicked() {
+                    clicked = Some(command.clone());
+                }
+            }
+
+            x = rect.right() + gap;
+        }
+    }
+
+    (clicked, unresolved)
+}
+
+fn line_end_char(text: &str, line: usize) -> usize {
+    let mut index = 0;
+
+    for (i, l) in text.split_inclusive('\n').enumerate() {
+        let len = l.chars().count();
+        if i == line {
+            return index + l.trim_end_matches('\n').chars().count();
+        }
+        index += len;
+    }
+
+    index
+}
+
+/// Pushes cursor moves and undo snapshots made outside of the editor into its `TextEditState`.
+/// Returns whether the cursor was moved.
+fn restore_editor_state(ctx: &egui::Context, id: egui::Id, doc: &mut Document) -> bool {
+    if doc.pending_cursor.is_none() && doc.undo_snapshot.is_none() {
+        return false;
+    }
+
+    let mut state = egui::TextEdit::load_state(ctx, id).unwrap_or_default();
+
+    if let Some((cursor, text)) = doc.undo_snapshot.take() {
+        let mut undoer = state.undoer();
+        undoer.add_undo(&(CCursorRange::one(CCursor::new(cursor)), text));
+        state.set_undoer(undoer);
+    }
+
+    let jumped = match doc.pending_cursor.take() {
+        Some(cursor) => {
+            state.cursor.set_char_range(Some(CCursorRange::two(CCursor::new(doc.anchor), CCursor::new(cursor))));
+            true
+        }
+        None => false,
+    };
+
+    state.store(ctx, id);
+    jumped
+}
+
+/// Handles the keys that depend on the language before the text edit sees them:
+/// indentation on Enter and Tab, snippets and bracket pairs.
+fn language_keys(ui: &egui::Ui, id: egui::Id, doc: &mut Document, language: &LanguageDef, snippets: &Snippets) {
+    let mut undo = None;
+    let mut apply = |doc: &mut Document, edit: Edit| {
+        let old_text = std::mem::replace(&mut doc.text, edit.text);
+        undo.get_or_insert((doc.cursor, old_text));
+        doc.cursor = edit.cursor;
+        doc.anchor = edit.anchor;
+    };
+
+    ui.input_mut(|input| {
+        // Once the text edit has to handle an event, later ones are left to it as well to keep their order
+        let mut passed_on = false;
+        input.events.retain(|event| {
+            if passed_on {
+                return true;
+            }
+
+            let (start, end) = (doc.cursor.min(doc.anchor), doc.cursor.max(doc.anchor));
+            let edit = match event {
+                egui::Event::Key { key: egui::Key::Enter, pressed: true, modifiers, .. } if modifiers.is_none() => {
+                    language.newline(&doc.text, start, end)
+                }
+                egui::Event::Key { key: egui::Key::Tab, pressed: true, modifiers, .. } if modifiers.is_none() && start == end => {
+                    snippets.expand(&doc.text, start).or_else(|| language.indent(&doc.text, start))
+                }
+                egui::Event::Text(text) => {
+                    let mut chars = text.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => language.type_char(&doc.text, start, end, c),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+
+            match edit {
+                Some(edit) => {
+                    apply(doc, edit);
+                    false
+                }
+                None => {
+                    passed_on = matches!(event, egui::Event::Text(_) | egui::Event::Paste(_) | egui::Event::Key { pressed: true, .. });
+                    true
+                }
+            }
+        });
+    });
+
+    let Some((cursor, old_text)) = undo else { return };
+
+    let mut state = egui::TextEdit::load_state(ui.ctx(), id).unwrap_or_default();
+    let mut undoer = state.undoer();
+    undoer.add_undo(&(CCursorRange::one(CCursor::new(cursor)), old_text));
+    state.set_undoer(undoer);
+    state.cursor.set_char_range(Some(CCursorRange::two(CCursor::new(doc.anchor), CCursor::new(doc.cursor))));
+    state.store(ui.ctx(), id);
+
+    doc.touch();
+}
+
 fn numlines_show(ui: &mut egui::Ui, text: &str, rows: usize, font_size: f32) {
     let total = if text.ends_with('\n') || text.is_empty() {
         text.lines().count() + 1
@@ -119,7 +592,7 @@ fn numlines_show(ui: &mut egui::Ui, text: &str, rows: usize, font_size: f32) {
         egui::TextEdit::multiline(&mut counter)
             .margin(egui::Margin {
                 left: 16,
-                right: 0,
+                right: CodeEditor::GUTTER_WIDTH as i8,
                 top: 10,
                 bottom: 10,
             })
@@ -130,4 +603,4 @@ fn numlines_show(ui: &mut egui::Ui, text: &str, rows: usize, font_size: f32) {
             .desired_rows(rows)
             .desired_width(width)
     );
-}
\ No newline at end of file
+}
@@ -1,5 +1,19 @@
+pub mod cabal_form;
+pub mod code_actions;
+pub mod command_palette;
 pub mod editor;
 pub mod menubar;
+pub mod notifications;
+pub mod open_dialog;
+pub mod output;
 pub mod pages;
+pub mod permission_prompt;
+pub mod plugin_view;
+pub mod problems;
+pub mod quick_pick;
+pub mod rename;
+pub mod repl;
+pub mod symbol_search;
+pub mod terminal;
 pub mod toolbar;
 pub mod window_frame;
\ No newline at end of file
@@ -0,0 +1,56 @@
+use egui_phosphor::bold as ph;
+
+use crate::notifications::{Level, Notifications};
+
+pub struct NotificationsArea<'a> {
+    notifications: &'a mut Notifications,
+}
+
+impl<'a> NotificationsArea<'a> {
+    pub const WIDTH: f32 = 320.0;
+
+    pub fn new(notifications: &'a mut Notifications) -> Self {
+        NotificationsArea { notifications }
+    }
+
+    pub fn show(self, ctx: &egui::Context) {
+        if let Some(next) = self.notifications.prune() {
+            ctx.request_repaint_after(next);
+        }
+
+        let mut closed = None;
+
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -16.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui|
+        {
+            for (i, notification) in self.notifications.iter().enumerate() {
+                egui::Frame::popup(ui.style())
+                    .show(ui, |ui|
+                {
+                    ui.set_width(Self::WIDTH);
+                    ui.horizontal_top(|ui| {
+                        let (icon, color) = match notification.level {
+                            Level::Info => (ph::INFO, ui.visuals().text_color()),
+                            Level::Error => (ph::WARNING, ui.visuals().error_fg_color),
+                        };
+
+                        ui.label(egui::RichText::new(icon).color(color).size(18.0));
+                        ui.add(egui::Label::new(&notification.message).wrap());
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                            if ui.small_button(ph::X).clicked() {
+                                closed = Some(i);
+                            }
+                        });
+                    });
+                });
+            }
+        });
+
+        if let Some(i) = closed {
+            self.notifications.remove(i);
+        }
+    }
+}
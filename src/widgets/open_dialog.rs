@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use crate::app::Fexc;
+
+#[derive(Default)]
+pub struct OpenState {
+    pub path: String,
+}
+
+enum OpenAction {
+    Open,
+    Close,
+}
+
+/// Asks for a folder to open as the project or a file to open in the editor.
+pub struct OpenDialog<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> OpenDialog<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        OpenDialog { app }
+    }
+
+    pub fn show(self, ctx: &egui::Context) {
+        if self.app.open_dialog.is_none() {
+            return;
+        }
+
+        let title = self.app.i18n("open").to_owned();
+        let hint = self.app.i18n("open_hint").to_owned();
+        let open_label = self.app.i18n("open").to_owned();
+        let cancel_label = self.app.i18n("cancel").to_owned();
+
+        let mut open = true;
+        let mut action = None;
+        let Some(state) = &mut self.app.open_dialog else { return };
+
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .default_width(480.0)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 80.0))
+            .open(&mut open)
+            .show(ctx, |ui|
+        {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut state.path)
+                    .hint_text(&hint)
+                    .desired_width(f32::INFINITY)
+            );
+            response.request_focus();
+
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                action = Some(OpenAction::Open);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button(&open_label).clicked() {
+                    action = Some(OpenAction::Open);
+                }
+                if ui.button(&cancel_label).clicked() {
+                    action = Some(OpenAction::Close);
+                }
+            });
+        });
+
+        if !open || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            action = Some(OpenAction::Close);
+        }
+
+        match action {
+            Some(OpenAction::Open) => {
+                let path = self.app.open_dialog.take().map(|s| s.path).unwrap_or_default();
+                let path = path.trim();
+
+                if !path.is_empty() {
+                    self.app.open_path(&expand_home(path));
+                }
+            }
+            Some(OpenAction::Close) => self.app.open_dialog = None,
+            None => {}
+        }
+    }
+}
+
+/// Replaces a leading `~` with the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches(['/', '\\'])),
+        _ => PathBuf::from(path),
+    }
+}
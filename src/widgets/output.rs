@@ -0,0 +1,125 @@
+use std::path::PathBuf;
+
+use egui_phosphor::bold as ph;
+
+use crate::{
+    app::Fexc,
+    links,
+    tasks::TaskStatus,
+    widgets::terminal::show_link_hint,
+};
+
+pub struct OutputView<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> OutputView<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        OutputView { app }
+    }
+}
+
+impl egui::Widget for OutputView<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let stop_label = self.app.i18n("stop_task").to_owned();
+        let clear_label = self.app.i18n("clear").to_owned();
+        let succeeded_label = self.app.i18n("task_succeeded").to_owned();
+        let failed_label = self.app.i18n("task_failed").to_owned();
+        let stopped_label = self.app.i18n("task_stopped").to_owned();
+        let no_output_label = self.app.i18n("no_task_output").to_owned();
+        let open_link_label = self.app.i18n("open_link_hint").to_owned();
+
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let row_height = ui.fonts(|f| f.row_height(&font_id));
+        let text_color = ui.visuals().text_color();
+        let command_held = ui.input(|i| i.modifiers.command);
+        let project = self.app.current_project.clone();
+        let package_dirs = self.app.project.iter()
+            .flat_map(|p| p.packages.iter().map(|p| p.dir.clone()))
+            .collect::<Vec<_>>();
+        let mut clicked = None;
+
+        let response = ui.vertical(|ui| {
+            let tasks = &mut self.app.tasks;
+
+            ui.horizontal(|ui| {
+                if let Some(run) = &tasks.running {
+                    ui.spinner();
+                    ui.label(&run.name);
+                } else if let Some((name, status)) = &tasks.finished {
+                    let (icon, text, color) = match status {
+                        TaskStatus::Succeeded => (ph::CHECK_CIRCLE, succeeded_label, egui::Color32::from_rgb(166, 218, 149)),
+                        TaskStatus::Failed(Some(code)) => (ph::X_CIRCLE, format!("{failed_label} ({code})"), ui.visuals().error_fg_color),
+                        TaskStatus::Failed(None) => (ph::X_CIRCLE, failed_label, ui.visuals().error_fg_color),
+                        TaskStatus::Stopped => (ph::STOP_CIRCLE, stopped_label, ui.visuals().warn_fg_color),
+                    };
+
+                    ui.label(egui::RichText::new(icon).color(color));
+                    ui.label(format!("{name}: {text}"));
+                } else {
+                    ui.label(egui::RichText::new(&no_output_label).weak());
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.add_enabled(tasks.running.is_some(), egui::Button::new(ph::STOP))
+                        .on_hover_text(&stop_label)
+                        .clicked()
+                    {
+                        tasks.stop();
+                    }
+
+                    if ui.button(ph::BROOM).on_hover_text(&clear_label).clicked() {
+                        tasks.output.clear();
+                    }
+                });
+            });
+
+            egui::ScrollArea::both()
+                .auto_shrink(false)
+                .stick_to_bottom(true)
+                .show_rows(ui, row_height, tasks.output.len(), |ui, range| {
+                    for line in &tasks.output[range] {
+                        let galley = ui.painter().layout_no_wrap(line.clone(), font_id.clone(), text_color);
+                        let (rect, response) = ui.allocate_exact_size(galley.size(), egui::Sense::click());
+
+                        // Locations of compiler messages and test failures open the file at that position
+                        let hovered = response.hover_pos().and_then(|pos| {
+                            let index = galley.cursor_from_pos(pos - rect.min).index;
+                            links::find(line).into_iter().find(|link| link.range.contains(&index))
+                        });
+
+                        if let Some(link) = hovered {
+                            let start = galley.pos_from_cursor(egui::text::CCursor::new(link.range.start));
+                            let end = galley.pos_from_cursor(egui::text::CCursor::new(link.range.end));
+                            ui.painter().hline(
+                                rect.left() + start.left()..=rect.left() + end.left(),
+                                rect.top() + start.bottom(),
+                                egui::Stroke::new(1.0, text_color),
+                            );
+
+                            show_link_hint(ui, response.id, &open_link_label, command_held);
+
+                            if command_held && response.clicked() {
+                                let bases = [tasks.root.as_path()].into_iter()
+                                    .chain(package_dirs.iter().map(PathBuf::as_path))
+                                    .chain(project.as_deref())
+                                    .collect::<Vec<_>>();
+                                clicked = Some((links::resolve(&link.path, &bases), (link.line, link.column)));
+                            }
+                        }
+
+                        ui.painter().galley(rect.min, galley, text_color);
+                    }
+                });
+        }).response;
+
+        if let Some((path, (line, column))) = clicked {
+            match self.app.documents.open(&path) {
+                Ok(doc) => doc.jump_to(line, column),
+                Err(e) => self.app.notifications.error(e.to_string()),
+            }
+        }
+
+        response
+    }
+}
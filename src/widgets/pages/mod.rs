@@ -1,4 +1,6 @@
+pub mod outline;
 pub mod plugins;
 pub mod project;
 pub mod settings;
-pub mod search;
\ No newline at end of file
+pub mod search;
+pub mod tests;
\ No newline at end of file
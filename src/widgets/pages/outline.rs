@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+
+use egui_phosphor::bold as ph;
+
+use crate::{
+    app::Fexc,
+    haskell::{self, Declaration, DeclarationKind},
+};
+
+#[derive(Default)]
+pub struct OutlineState {
+    pub query: String,
+    pub hidden: HashSet<DeclarationKind>,
+    cache: Option<(u64, i32, Vec<Declaration>)>,
+}
+
+impl OutlineState {
+    /// Returns the declarations of the given document version, reparsing only when it changed.
+    fn declarations(&mut self, doc: u64, version: i32, text: &str) -> &[Declaration] {
+        let outdated = !matches!(&self.cache, Some((d, v, _)) if *d == doc && *v == version);
+        if outdated {
+            self.cache = Some((doc, version, haskell::declarations(text)));
+        }
+
+        self.cache.as_ref()
+            .map(|(_, _, decls)| decls.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+pub struct OutlinePage<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> OutlinePage<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        OutlinePage { app }
+    }
+}
+
+impl egui::Widget for OutlinePage<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.style_mut().spacing.item_spacing = egui::vec2(10.0, 10.0);
+
+        ui.add(egui::Label::new(
+            egui::RichText::new(self.app.i18n("outline").to_uppercase())
+                .size(28.0)
+                .strong()
+                .family(egui::FontFamily::Monospace)
+        ));
+
+        let kind_labels = DeclarationKind::ALL.map(|kind| (kind, self.app.i18n(kind.i18n_key()).to_owned()));
+        let filter_hint = self.app.i18n("filter").to_owned();
+
+        let outline = &mut self.app.outline;
+
+        ui.add(
+            egui::TextEdit::singleline(&mut outline.query)
+                .hint_text(filter_hint)
+                .desired_width(f32::INFINITY)
+        );
+
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(4.0, 4.0);
+
+            for (kind, label) in &kind_labels {
+                let shown = !outline.hidden.contains(kind);
+                let text = format!("{} {label}", kind_icon(*kind));
+
+                if ui.selectable_label(shown, text).clicked() {
+                    if shown {
+                        outline.hidden.insert(*kind);
+                    } else {
+                        outline.hidden.remove(kind);
+                    }
+                }
+            }
+        });
+
+        ui.separator();
+
+        let doc = self.app.documents.active();
+        let query = outline.query.to_lowercase();
+        let hidden = outline.hidden.clone();
+        let mut target = None;
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui|
+        {
+            ui.spacing_mut().item_spacing = egui::vec2(4.0, 4.0);
+
+            let decls = outline.declarations(doc.id, doc.version, &doc.text)
+                .iter()
+                .filter(|d| !hidden.contains(&d.kind))
+                .filter(|d| query.is_empty() || d.name.to_lowercase().contains(&query));
+
+            for decl in decls {
+                let response = ui.add(
+                    egui::Button::new(
+                        egui::RichText::new(format!("{} {}", kind_icon(decl.kind), decl.name))
+                            .family(egui::FontFamily::Monospace)
+                    )
+                    .frame(false)
+                    .wrap_mode(egui::TextWrapMode::Truncate)
+                );
+
+                let response = match &decl.detail {
+                    Some(detail) => response.on_hover_text(detail),
+                    None => response,
+                };
+
+                if response.clicked() {
+                    target = Some(decl.line);
+                }
+            }
+        });
+
+        if let Some(line) = target {
+            self.app.documents.active_mut().jump_to(line, 0);
+        }
+
+        ui.label("")
+    }
+}
+
+pub fn kind_icon(kind: DeclarationKind) -> &'static str {
+    match kind {
+        DeclarationKind::Function => ph::FUNCTION,
+        DeclarationKind::Data => ph::CUBE,
+        DeclarationKind::TypeSynonym => ph::TEXT_T,
+        DeclarationKind::Class => ph::SHAPES,
+        DeclarationKind::Instance => ph::TAG,
+        DeclarationKind::TypeFamily => ph::TREE_STRUCTURE,
+    }
+}
@@ -1,4 +1,59 @@
-use crate::app::Fexc;
+use std::path::{Path, PathBuf};
+
+use egui_phosphor::bold as ph;
+
+use crate::{
+    app::Fexc,
+    plugin::{manifest::PluginManifest, permissions::Permission, script::INIT_SCRIPT_ID},
+};
+
+#[derive(Default)]
+pub struct PluginsState {
+    pub install_path: String,
+    /// The plugin whose uninstall button was clicked once and waits for confirmation.
+    confirm_uninstall: Option<String>,
+}
+
+enum Action {
+    Enable(String, bool),
+    Install(PathBuf),
+    Uninstall(String),
+    Revoke(String, Permission),
+}
+
+enum Source<'a> {
+    Builtin,
+    Installed(Option<PluginManifest>),
+    /// The user's init script at this path.
+    Script(&'a Path),
+}
+
+struct Entry<'a> {
+    id: String,
+    source: Source<'a>,
+}
+
+impl Entry<'_> {
+    fn manifest(&self) -> Option<&PluginManifest> {
+        match &self.source {
+            Source::Installed(manifest) => manifest.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+struct Labels {
+    enabled: String,
+    builtin: String,
+    commands: String,
+    log: String,
+    uninstall: String,
+    confirm_uninstall: String,
+    permissions: String,
+    granted: String,
+    not_granted: String,
+    revoke: String,
+}
 
 pub struct PluginsPage<'app> {
     app: &'app mut Fexc,
@@ -19,8 +74,210 @@ impl egui::Widget for PluginsPage<'_> {
                 .size(28.0)
                 .strong()
                 .family(egui::FontFamily::Monospace)
-        ));            
+        ));
+
+        let install_label = self.app.i18n("install_plugin").to_owned();
+        let install_hint = self.app.i18n("install_plugin_hint").to_owned();
+        let developer_mode_label = self.app.i18n("developer_mode").to_owned();
+        let developer_mode_hint = self.app.i18n("developer_mode_hint").to_owned();
+        let labels = Labels {
+            enabled: self.app.i18n("enabled").to_owned(),
+            builtin: self.app.i18n("builtin_plugin").to_owned(),
+            commands: self.app.i18n("commands").to_owned(),
+            log: self.app.i18n("plugin_log").to_owned(),
+            uninstall: self.app.i18n("uninstall").to_owned(),
+            confirm_uninstall: self.app.i18n("confirm_uninstall").to_owned(),
+            permissions: self.app.i18n("permissions").to_owned(),
+            granted: self.app.i18n("permission_granted").to_owned(),
+            not_granted: self.app.i18n("permission_not_granted").to_owned(),
+            revoke: self.app.i18n("revoke").to_owned(),
+        };
+        let permission_labels = [
+            Permission::ReadFiles,
+            Permission::WriteFiles,
+            Permission::SpawnProcesses,
+            Permission::Terminal,
+            Permission::Settings,
+        ].map(|p| (p, self.app.i18n(p.i18n_key()).to_owned()));
+
+        let mut action = None;
+
+        ui.horizontal(|ui| {
+            let state = &mut self.app.plugins_page;
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut state.install_path)
+                    .hint_text(&install_hint)
+                    .desired_width(ui.available_width() - 100.0)
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+            if (ui.button(format!("{} {install_label}", ph::DOWNLOAD_SIMPLE)).clicked() || submitted)
+                && !state.install_path.trim().is_empty()
+            {
+                action = Some(Action::Install(PathBuf::from(state.install_path.trim())));
+            }
+        });
+
+        if ui.checkbox(&mut self.app.config.plugin_developer_mode, &developer_mode_label)
+            .on_hover_text(&developer_mode_hint)
+            .changed()
+        {
+            self.app.save_config();
+        }
+
+        let command_titles = self.app.commands.plugin_commands()
+            .map(|c| (c.plugin_id().unwrap_or_default().to_owned(), self.app.command_title(&c.title)))
+            .collect::<Vec<_>>();
+
+        let plugins = &self.app.plugins;
+        let entries = plugins.builtin.iter()
+            .map(|id| Entry { id: id.clone(), source: Source::Builtin })
+            .chain(plugins.installed.iter().map(|p| Entry { id: p.id(), source: Source::Installed(p.manifest.clone().ok()) }))
+            .chain(plugins.init_script.as_deref().map(|path| Entry { id: INIT_SCRIPT_ID.to_owned(), source: Source::Script(path) }))
+            .collect::<Vec<_>>();
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui|
+        {
+            ui.spacing_mut().item_spacing = egui::vec2(4.0, 4.0);
+
+            for entry in &entries {
+                let error = plugins.errors.get(&entry.id).or_else(|| {
+                    // A broken manifest is reported by the installed plugin itself
+                    plugins.installed.iter().find(|p| p.id() == entry.id).and_then(|p| p.manifest.as_ref().err())
+                });
+
+                let (icon, color) = match error {
+                    Some(_) => (ph::WARNING_CIRCLE, ui.visuals().error_fg_color),
+                    None if plugins.is_loaded(&entry.id) => (ph::PUZZLE_PIECE, ui.visuals().text_color()),
+                    None => (ph::PUZZLE_PIECE, ui.visuals().weak_text_color()),
+                };
+
+                let title = match entry.manifest() {
+                    Some(manifest) => format!("{icon} {} {}", entry.id, manifest.version),
+                    None => format!("{icon} {}", entry.id),
+                };
+
+                egui::CollapsingHeader::new(egui::RichText::new(title).monospace().color(color))
+                    .id_salt(("plugin", &entry.id))
+                    .show(ui, |ui| {
+                        let mut enabled = !self.app.config.disabled_plugins.contains(&entry.id);
+                        if ui.checkbox(&mut enabled, &labels.enabled).changed() {
+                            action = Some(Action::Enable(entry.id.clone(), enabled));
+                        }
+
+                        match &entry.source {
+                            Source::Builtin => {
+                                ui.label(egui::RichText::new(&labels.builtin).weak());
+                            }
+                            Source::Script(path) => {
+                                ui.label(egui::RichText::new(path.display().to_string()).monospace().weak());
+                            }
+                            Source::Installed(_) => {}
+                        }
+
+                        if let Some(manifest) = entry.manifest() {
+                            if !manifest.author.is_empty() {
+                                ui.label(egui::RichText::new(&manifest.author).weak());
+                            }
+                            if !manifest.description.is_empty() {
+                                ui.label(&manifest.description);
+                            }
+                        }
+
+                        if let Some(error) = error {
+                            ui.label(egui::RichText::new(error).color(ui.visuals().error_fg_color));
+                        }
+
+                        let commands = command_titles.iter()
+                            .filter(|(plugin, _)| *plugin == entry.id)
+                            .map(|(_, title)| title.clone())
+                            .chain(entry.manifest().into_iter().flat_map(|m| m.commands.iter().map(|c| c.title.clone())))
+                            .fold(Vec::<String>::new(), |mut titles, title| {
+                                if !titles.contains(&title) {
+                                    titles.push(title);
+                                }
+                                titles
+                            });
+
+                        let declared = entry.manifest().map_or(&[][..], |m| &m.permissions[..]);
+                        if !declared.is_empty() {
+                            ui.label(egui::RichText::new(&labels.permissions).weak());
+
+                            let granted = self.app.config.plugin_permissions.get(&entry.id);
+                            for (permission, label) in permission_labels.iter().filter(|(p, _)| declared.contains(p)) {
+                                ui.horizontal(|ui| {
+                                    ui.label(label);
+
+                                    if !permission.is_sensitive() {
+                                        return;
+                                    }
+
+                                    if granted.is_some_and(|g| g.contains(permission)) {
+                                        ui.label(egui::RichText::new(&labels.granted).weak());
+                                        if ui.small_button(&labels.revoke).clicked() {
+                                            action = Some(Action::Revoke(entry.id.clone(), *permission));
+                                        }
+                                    } else {
+                                        ui.label(egui::RichText::new(&labels.not_granted).weak());
+                                    }
+                                });
+                            }
+                        }
+
+                        if !commands.is_empty() {
+                            ui.label(egui::RichText::new(&labels.commands).weak());
+                            for title in commands {
+                                ui.label(egui::RichText::new(title).monospace());
+                            }
+                        }
+
+                        if let Some(log) = plugins.logs.get(&entry.id).filter(|log| !log.is_empty()) {
+                            egui::CollapsingHeader::new(&labels.log)
+                                .id_salt(("plugin_log", &entry.id))
+                                .show(ui, |ui| {
+                                    egui::ScrollArea::vertical()
+                                        .max_height(160.0)
+                                        .stick_to_bottom(true)
+                                        .show(ui, |ui| {
+                                            for line in log {
+                                                ui.label(egui::RichText::new(line).monospace().small());
+                                            }
+                                        });
+                                });
+                        }
+
+                        if let Source::Installed(_) = entry.source {
+                            let confirming = self.app.plugins_page.confirm_uninstall.as_ref() == Some(&entry.id);
+                            let label = if confirming { &labels.confirm_uninstall } else { &labels.uninstall };
+
+                            if ui.button(format!("{} {label}", ph::TRASH)).clicked() {
+                                if confirming {
+                                    action = Some(Action::Uninstall(entry.id.clone()));
+                                } else {
+                                    self.app.plugins_page.confirm_uninstall = Some(entry.id.clone());
+                                }
+                            }
+                        }
+                    });
+            }
+        });
+
+        match action {
+            Some(Action::Enable(id, enabled)) => self.app.set_plugin_enabled(&id, enabled),
+            Some(Action::Install(path)) => {
+                self.app.install_plugin(&path);
+                self.app.plugins_page.install_path.clear();
+            }
+            Some(Action::Revoke(id, permission)) => self.app.revoke_permission(&id, permission),
+            Some(Action::Uninstall(id)) => {
+                self.app.plugins_page.confirm_uninstall = None;
+                self.app.uninstall_plugin(&id);
+            }
+            None => {}
+        }
 
         ui.label("")
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,17 @@
-use crate::app::Fexc;
+use egui_phosphor::bold as ph;
+
+use crate::{
+    app::Fexc,
+    project::{Component, ComponentKind},
+    widgets::open_dialog::OpenState,
+};
+
+struct Labels {
+    source_dirs: String,
+    main_is: String,
+    extensions: String,
+    dependencies: String,
+}
 
 pub struct ProjectPage<'app> {
     app: &'app mut Fexc,
@@ -22,12 +35,145 @@ impl egui::Widget for ProjectPage<'_> {
                     .family(egui::FontFamily::Monospace)
             ));
         } else {
-            ui.vertical_centered(|ui| {
+            let response = ui.vertical_centered(|ui| {
                 ui.add_space(ui.available_height() / 2.0 - 20.0); // Adjust for vertical centering
-                ui.label(self.app.i18n("no_project_open"));
+                ui.add(egui::Label::new(self.app.i18n("no_project_open")).sense(egui::Sense::click()))
+            }).inner;
+
+            if response.double_clicked() {
+                self.app.open_dialog = Some(OpenState::default());
+            }
+        }
+
+        let Some(project) = &self.app.project else { return ui.label("") };
+
+        let build_tool_label = self.app.i18n("build_tool").to_owned();
+        let project_files_label = self.app.i18n("project_files").to_owned();
+        let labels = Labels {
+            source_dirs: self.app.i18n("source_dirs").to_owned(),
+            main_is: self.app.i18n("main_is").to_owned(),
+            extensions: self.app.i18n("default_extensions").to_owned(),
+            dependencies: self.app.i18n("dependencies").to_owned(),
+        };
+        let reload_label = self.app.i18n("refresh").to_owned();
+        let kind_labels = ComponentKind::ALL.map(|kind| (kind, self.app.i18n(kind.i18n_key()).to_owned()));
+
+        let mut reload = false;
+        let mut open = None;
+
+        egui::Grid::new("project_info")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new(&build_tool_label).weak());
+                ui.label(egui::RichText::new(project.tool.program()).monospace());
+                ui.end_row();
+
+                ui.label(egui::RichText::new(&project_files_label).weak());
+                ui.vertical(|ui| {
+                    ui.spacing_mut().item_spacing = egui::vec2(4.0, 4.0);
+
+                    let files = [
+                        (&project.cabal_project, None),
+                        (&project.stack_yaml, None),
+                        (&project.hie_yaml, project.hie_cradle.as_ref()),
+                    ];
+
+                    for (path, cradle) in files {
+                        let Some(path) = path else { continue };
+
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        let name = match cradle {
+                            Some(cradle) => format!("{name} ({cradle})"),
+                            None => name.into_owned(),
+                        };
+
+                        if ui.link(egui::RichText::new(name).monospace()).clicked() {
+                            open = Some(path.clone());
+                        }
+                    }
+                });
+                ui.end_row();
             });
+
+        if ui.button(format!("{} {reload_label}", ph::ARROW_CLOCKWISE)).clicked() {
+            reload = true;
+        }
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui|
+        {
+            ui.spacing_mut().item_spacing = egui::vec2(4.0, 4.0);
+
+            for package in &project.packages {
+                let title = match &package.version {
+                    Some(version) => format!("{} {} {version}", ph::PACKAGE, package.name),
+                    None => format!("{} {}", ph::PACKAGE, package.name),
+                };
+
+                egui::CollapsingHeader::new(egui::RichText::new(title).monospace().strong())
+                    .id_salt(("package", &package.manifest))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        let manifest = package.manifest.strip_prefix(&project.root).unwrap_or(&package.manifest);
+                        if ui.link(egui::RichText::new(manifest.display().to_string()).monospace()).clicked() {
+                            open = Some(package.manifest.clone());
+                        }
+
+                        for (kind, kind_label) in &kind_labels {
+                            for component in package.components.iter().filter(|c| c.kind == *kind) {
+                                let title = format!("{} {kind_label}: {}", component_icon(*kind), component.name);
+
+                                egui::CollapsingHeader::new(egui::RichText::new(title).monospace())
+                                    .id_salt(("component", &package.manifest, *kind, &component.name))
+                                    .show(ui, |ui| component_ui(ui, component, &labels));
+                            }
+                        }
+                    });
+            }
+        });
+
+        if reload {
+            self.app.reload_project();
+        }
+
+        if let Some(path) = open
+            && let Err(e) = self.app.documents.open(&path)
+        {
+            self.app.notifications.error(e.to_string());
         }
 
         ui.label("")
     }
-}
\ No newline at end of file
+}
+
+fn component_ui(ui: &mut egui::Ui, component: &Component, labels: &Labels) {
+    let list = |ui: &mut egui::Ui, label: &str, items: &[String]| {
+        if items.is_empty() {
+            return;
+        }
+
+        ui.label(egui::RichText::new(label).weak());
+        ui.horizontal_wrapped(|ui| {
+            for item in items {
+                ui.label(egui::RichText::new(item).monospace());
+            }
+        });
+    };
+
+    list(ui, &labels.source_dirs, &component.source_dirs);
+    list(ui, &labels.main_is, component.main_is.as_slice());
+    list(ui, &labels.extensions, &component.default_extensions);
+    list(ui, &labels.dependencies, &component.dependencies);
+}
+
+pub fn component_icon(kind: ComponentKind) -> &'static str {
+    match kind {
+        ComponentKind::Library => ph::BOOKS,
+        ComponentKind::Executable => ph::TERMINAL,
+        ComponentKind::TestSuite => ph::FLASK,
+        ComponentKind::Benchmark => ph::GAUGE,
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{app::Fexc, i18n::Language};
+use crate::{app::Fexc, format::Formatter, i18n::Language};
 
 pub struct SettingsPage<'app> {
     app: &'app mut Fexc,
@@ -70,12 +70,62 @@ impl egui::Widget for SettingsPage<'_> {
                 )
             );
             ui.end_row();
+
+            // Formatter row
+            ui.label(format!("{}:", self.app.i18n("formatter")));
+            egui::ComboBox::from_id_salt("formatter")
+                .selected_text(self.app.config.formatter.name())
+                .show_ui(ui, |ui| {
+                    for formatter in Formatter::ALL {
+                        responses.push(
+                            ui.selectable_value(&mut self.app.config.formatter, formatter, formatter.name())
+                        );
+                    }
+                });
+            ui.end_row();
+
+            // Format on save row
+            ui.label(format!("{}:", self.app.i18n("format_on_save")));
+            responses.push(
+                ui.checkbox(&mut self.app.config.format_on_save, "")
+            );
+            ui.end_row();
+
+            // Inlay hints rows
+            ui.label(format!("{}:", self.app.i18n("inlay_type_hints")));
+            responses.push(
+                ui.checkbox(&mut self.app.config.inlay_type_hints, "")
+            );
+            ui.end_row();
+
+            ui.label(format!("{}:", self.app.i18n("inlay_other_hints")));
+            responses.push(
+                ui.checkbox(&mut self.app.config.inlay_other_hints, "")
+            );
+            ui.end_row();
+
+            ui.label(format!("{}:", self.app.i18n("code_lenses")));
+            responses.push(
+                ui.checkbox(&mut self.app.config.code_lenses, "")
+            );
+            ui.end_row();
+
+            ui.label(format!("{}:", self.app.i18n("hlint_suggestions")));
+            responses.push(
+                ui.checkbox(&mut self.app.config.hlint, "")
+            );
+            ui.end_row();
+
+            ui.label(format!("{}:", self.app.i18n("watch_mode")));
+            responses.push(
+                ui.checkbox(&mut self.app.config.watch_mode, "")
+                    .on_hover_text(self.app.i18n("watch_mode_hint"))
+            );
+            ui.end_row();
         });
 
         if responses.iter().any(|r| r.changed()) {
-            self.app.config.save().unwrap_or_else(|e| {
-                log::error!("Failed to save config: {e}");
-            });
+            self.app.save_config();
         }
 
         ui.label("")
@@ -0,0 +1,212 @@
+use egui_phosphor::bold as ph;
+
+use crate::{
+    app::Fexc,
+    links,
+    tasks::TaskStatus,
+    testing::{TestFilter, TestNode, TestStatus},
+};
+
+enum Action {
+    Run(usize, TestFilter),
+    Open(String, usize, usize),
+}
+
+struct Labels {
+    run: String,
+    rerun_failures: String,
+    run_case: String,
+    not_run: String,
+}
+
+pub struct TestsPage<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> TestsPage<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        TestsPage { app }
+    }
+}
+
+impl egui::Widget for TestsPage<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.style_mut().spacing.item_spacing = egui::vec2(10.0, 10.0);
+
+        ui.add(egui::Label::new(
+            egui::RichText::new(self.app.i18n("tests").to_uppercase())
+                .size(28.0)
+                .strong()
+                .family(egui::FontFamily::Monospace)
+        ));
+
+        let labels = Labels {
+            run: self.app.i18n("run_tests").to_owned(),
+            rerun_failures: self.app.i18n("rerun_failures").to_owned(),
+            run_case: self.app.i18n("run_test_case").to_owned(),
+            not_run: self.app.i18n("tests_not_run").to_owned(),
+        };
+        let refresh_label = self.app.i18n("refresh").to_owned();
+        let no_suites_label = self.app.i18n("no_test_suites").to_owned();
+
+        let busy = self.app.tasks.running.is_some();
+        let running = self.app.tests.running.as_ref().map(|(suite, _)| *suite);
+        let mut refresh = false;
+        let mut action = None;
+
+        ui.horizontal(|ui| {
+            if ui.button(format!("{} {refresh_label}", ph::ARROW_CLOCKWISE)).clicked() {
+                refresh = true;
+            }
+        });
+
+        if self.app.tests.suites.is_empty() {
+            ui.label(egui::RichText::new(&no_suites_label).weak());
+        }
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui|
+        {
+            ui.spacing_mut().item_spacing = egui::vec2(4.0, 4.0);
+
+            for (index, suite) in self.app.tests.suites.iter().enumerate() {
+                let (icon, color) = match suite.status {
+                    Some(TaskStatus::Succeeded) => status_icon(ui, Some(TestStatus::Passed)),
+                    Some(TaskStatus::Failed(_)) => status_icon(ui, Some(TestStatus::Failed)),
+                    Some(TaskStatus::Stopped) => status_icon(ui, Some(TestStatus::Skipped)),
+                    None => status_icon(ui, None),
+                };
+
+                let header = egui::RichText::new(format!("{icon} {}", suite.name))
+                    .family(egui::FontFamily::Monospace)
+                    .color(color);
+
+                egui::CollapsingHeader::new(header)
+                    .id_salt(("test_suite", &suite.name))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            if running == Some(index) {
+                                ui.spinner();
+                            }
+
+                            ui.label(egui::RichText::new(suite.framework.name()).weak());
+
+                            if ui.add_enabled(!busy, egui::Button::new(format!("{} {}", ph::PLAY, labels.run))).clicked() {
+                                action = Some(Action::Run(index, TestFilter::All));
+                            }
+
+                            let has_failures = !suite.failures().is_empty();
+                            if ui.add_enabled(!busy && has_failures, egui::Button::new(ph::ARROW_COUNTER_CLOCKWISE))
+                                .on_hover_text(&labels.rerun_failures)
+                                .clicked()
+                            {
+                                action = Some(Action::Run(index, TestFilter::Failures));
+                            }
+                        });
+
+                        if suite.results.is_empty() {
+                            ui.label(egui::RichText::new(&labels.not_run).weak());
+                        }
+
+                        for node in &suite.results {
+                            node_ui(ui, node, &mut vec![], index, !busy, &labels, &mut action);
+                        }
+                    });
+            }
+        });
+
+        if refresh {
+            self.app.reload_project();
+        }
+
+        match action {
+            Some(Action::Run(suite, filter)) => self.app.run_tests(suite, filter, ui.ctx()),
+            Some(Action::Open(path, line, column)) => {
+                let bases = self.app.current_project.as_deref().into_iter().collect::<Vec<_>>();
+                let path = links::resolve(&path, &bases);
+
+                match self.app.documents.open(&path) {
+                    Ok(doc) => doc.jump_to(line, column),
+                    Err(e) => self.app.notifications.error(e.to_string()),
+                }
+            }
+            None => {}
+        }
+
+        ui.label("")
+    }
+}
+
+/// Shows a group as a collapsible header and a case as a row that can be rerun or opened at its failure.
+fn node_ui(
+    ui: &mut egui::Ui,
+    node: &TestNode,
+    path: &mut Vec<String>,
+    suite: usize,
+    can_run: bool,
+    labels: &Labels,
+    action: &mut Option<Action>,
+) {
+    path.push(node.name.clone());
+    let (icon, color) = status_icon(ui, node.summary());
+
+    if node.is_case() {
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(icon).color(color));
+
+            let mut response = ui.add(
+                egui::Button::new(egui::RichText::new(&node.name).family(egui::FontFamily::Monospace))
+                    .frame(false)
+                    .wrap_mode(egui::TextWrapMode::Truncate)
+            );
+            if !node.message.is_empty() {
+                response = response.on_hover_text(egui::RichText::new(&node.message).monospace());
+            }
+
+            if response.clicked()
+                && let Some((file, line, column)) = &node.location
+            {
+                *action = Some(Action::Open(file.clone(), *line, *column));
+            }
+
+            if let Some(duration) = &node.duration {
+                ui.label(egui::RichText::new(duration).weak());
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.add_enabled(can_run, egui::Button::new(ph::PLAY).frame(false))
+                    .on_hover_text(&labels.run_case)
+                    .clicked()
+                {
+                    *action = Some(Action::Run(suite, TestFilter::Case(path.clone())));
+                }
+            });
+        });
+    } else {
+        let header = egui::RichText::new(format!("{icon} {}", node.name))
+            .family(egui::FontFamily::Monospace)
+            .color(color);
+
+        egui::CollapsingHeader::new(header)
+            .id_salt(("test_group", suite, path.clone()))
+            .default_open(node.summary() == Some(TestStatus::Failed))
+            .show(ui, |ui| {
+                for child in &node.children {
+                    node_ui(ui, child, path, suite, can_run, labels, action);
+                }
+            });
+    }
+
+    path.pop();
+}
+
+fn status_icon(ui: &egui::Ui, status: Option<TestStatus>) -> (&'static str, egui::Color32) {
+    match status {
+        Some(TestStatus::Passed) => (ph::CHECK_CIRCLE, egui::Color32::from_rgb(166, 218, 149)),
+        Some(TestStatus::Failed) => (ph::X_CIRCLE, ui.visuals().error_fg_color),
+        Some(TestStatus::Skipped) => (ph::MINUS_CIRCLE, ui.visuals().warn_fg_color),
+        None => (ph::CIRCLE, ui.visuals().weak_text_color()),
+    }
+}
@@ -0,0 +1,55 @@
+use egui_phosphor::bold as ph;
+
+use crate::app::Fexc;
+
+/// Asks the user whether a plugin may use a sensitive permission it tried to use.
+pub struct PermissionPrompt<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> PermissionPrompt<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        PermissionPrompt { app }
+    }
+
+    pub fn show(self, ctx: &egui::Context) {
+        let Some(request) = self.app.plugins.consent.first().cloned() else { return };
+
+        let title = self.app.i18n("permission_request").to_owned();
+        let asks_label = self.app.i18n("plugin_asks_to").to_owned();
+        let permission_label = self.app.i18n(request.permission.i18n_key()).to_owned();
+        let allow_label = self.app.i18n("allow").to_owned();
+        let deny_label = self.app.i18n("deny").to_owned();
+
+        let mut open = true;
+        let mut answer = None;
+
+        egui::Window::new(format!("{} {title}", ph::SHIELD_WARNING))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .open(&mut open)
+            .show(ctx, |ui|
+        {
+            ui.label(format!("{} {asks_label}:", request.plugin));
+            ui.label(egui::RichText::new(&permission_label).strong());
+
+            ui.horizontal(|ui| {
+                if ui.button(&allow_label).clicked() {
+                    answer = Some(true);
+                }
+                if ui.button(&deny_label).clicked() {
+                    answer = Some(false);
+                }
+            });
+        });
+
+        if !open {
+            answer = Some(false);
+        }
+
+        if let Some(granted) = answer {
+            self.app.answer_consent(&request, granted);
+        }
+    }
+}
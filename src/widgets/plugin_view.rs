@@ -0,0 +1,47 @@
+use crate::{app::Fexc, plugin::ui};
+
+/// Renders a view a plugin registered, as a sidebar page, bottom panel tab or status bar item.
+pub struct PluginViewUi<'app> {
+    app: &'app mut Fexc,
+    key: u64,
+    heading: bool,
+}
+
+impl<'app> PluginViewUi<'app> {
+    pub fn new(app: &'app mut Fexc, key: u64) -> Self {
+        PluginViewUi { app, key, heading: false }
+    }
+
+    /// Shows the view title above the content, like the built-in sidebar pages.
+    pub fn heading(mut self) -> Self {
+        self.heading = true;
+        self
+    }
+}
+
+impl egui::Widget for PluginViewUi<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let Some(view) = self.app.plugins.views.get(self.key) else {
+            return ui.label("");
+        };
+
+        let response = ui.push_id(("plugin_view", self.key), |ui| {
+            if self.heading {
+                ui.add(egui::Label::new(
+                    egui::RichText::new(view.title.to_uppercase())
+                        .size(28.0)
+                        .strong()
+                        .family(egui::FontFamily::Monospace)
+                ));
+            }
+
+            ui::show(ui, &view.content)
+        });
+
+        if let Some(action) = response.inner {
+            self.app.plugin_ui_action(self.key, &action);
+        }
+
+        response.response
+    }
+}
@@ -0,0 +1,90 @@
+use egui_phosphor::bold as ph;
+
+use crate::app::Fexc;
+
+pub struct ProblemsView<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> ProblemsView<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        ProblemsView { app }
+    }
+}
+
+impl egui::Widget for ProblemsView<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let watching_label = self.app.i18n("watching").to_owned();
+        let no_problems_label = self.app.i18n("no_problems").to_owned();
+
+        let root = self.app.current_project.clone();
+        let mut clicked = None;
+
+        let response = ui.vertical(|ui| {
+            if self.app.config.watch_mode && self.app.watch.is_running() {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(ph::EYE).weak());
+                    ui.label(egui::RichText::new(format!("{watching_label}: {}", self.app.watch.command())).weak());
+
+                    if self.app.watch.is_checking() {
+                        ui.spinner();
+                    }
+                });
+            }
+
+            let problems = self.app.diagnostics.by_path();
+
+            if problems.is_empty() {
+                ui.label(egui::RichText::new(&no_problems_label).weak());
+            }
+
+            egui::ScrollArea::vertical()
+                .auto_shrink(false)
+                .show(ui, |ui| {
+                    for (path, diagnostics) in problems {
+                        let name = root.as_deref()
+                            .and_then(|root| path.strip_prefix(root).ok())
+                            .unwrap_or(path);
+
+                        egui::CollapsingHeader::new(format!("{} ({})", name.display(), diagnostics.len()))
+                            .id_salt(path)
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for diagnostic in diagnostics {
+                                    let (icon, color) = match diagnostic.severity {
+                                        Some(lsp_types::DiagnosticSeverity::ERROR) | None => (ph::X_CIRCLE, ui.visuals().error_fg_color),
+                                        Some(lsp_types::DiagnosticSeverity::WARNING) => (ph::WARNING, ui.visuals().warn_fg_color),
+                                        _ => (ph::INFO, ui.visuals().hyperlink_color),
+                                    };
+
+                                    let start = diagnostic.range.start;
+                                    let message = diagnostic.message.lines().next().unwrap_or_default();
+
+                                    ui.horizontal(|ui| {
+                                        ui.label(egui::RichText::new(icon).color(color));
+
+                                        let response = ui.selectable_label(false, message)
+                                            .on_hover_text(&diagnostic.message);
+                                        if response.clicked() {
+                                            clicked = Some((path.to_owned(), start.line as usize, start.character as usize));
+                                        }
+
+                                        let source = diagnostic.source.as_deref().unwrap_or_default();
+                                        ui.label(egui::RichText::new(format!("{source} [{}:{}]", start.line + 1, start.character + 1)).weak());
+                                    });
+                                }
+                            });
+                    }
+                });
+        }).response;
+
+        if let Some((path, line, column)) = clicked {
+            match self.app.documents.open(&path) {
+                Ok(doc) => doc.jump_to(line, column),
+                Err(e) => self.app.notifications.error(e.to_string()),
+            }
+        }
+
+        response
+    }
+}
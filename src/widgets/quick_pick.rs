@@ -0,0 +1,110 @@
+/// A keyboard-driven overlay with a query field and a list of results, in the style of a command palette.
+pub struct QuickPick<'a> {
+    id: egui::Id,
+    query: &'a mut String,
+    selected: &'a mut usize,
+    hint: &'a str,
+    len: usize,
+}
+
+pub enum QuickPickResponse {
+    None,
+    Picked(usize),
+    Closed,
+}
+
+impl<'a> QuickPick<'a> {
+    pub const WIDTH: f32 = 560.0;
+    pub const MAX_HEIGHT: f32 = 400.0;
+    pub const ROW_HEIGHT: f32 = 28.0;
+
+    pub fn new(id: &str, query: &'a mut String, selected: &'a mut usize, hint: &'a str, len: usize) -> Self {
+        QuickPick {
+            id: egui::Id::new(id),
+            query,
+            selected,
+            hint,
+            len,
+        }
+    }
+
+    /// Shows the overlay; `row` draws the `i`-th result and whether it is selected.
+    pub fn show(
+        self,
+        ctx: &egui::Context,
+        mut row: impl FnMut(&mut egui::Ui, usize, bool) -> egui::Response,
+    ) -> QuickPickResponse {
+        let mut result = QuickPickResponse::None;
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| (
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+        ));
+
+        if down && *self.selected + 1 < self.len {
+            *self.selected += 1;
+        }
+        if up {
+            *self.selected = self.selected.saturating_sub(1);
+        }
+        *self.selected = (*self.selected).min(self.len.saturating_sub(1));
+
+        if escape {
+            return QuickPickResponse::Closed;
+        }
+        if enter && self.len > 0 {
+            return QuickPickResponse::Picked(*self.selected);
+        }
+
+        egui::Area::new(self.id)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 80.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui|
+        {
+            egui::Frame::popup(ui.style())
+                .show(ui, |ui|
+            {
+                ui.set_width(Self::WIDTH);
+
+                let response = ui.add(
+                    egui::TextEdit::singleline(self.query)
+                        .hint_text(self.hint)
+                        .desired_width(f32::INFINITY)
+                );
+                response.request_focus();
+
+                if response.changed() {
+                    *self.selected = 0;
+                }
+
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(Self::MAX_HEIGHT)
+                    .auto_shrink([false, true])
+                    .show_rows(ui, Self::ROW_HEIGHT, self.len, |ui, range|
+                {
+                    for i in range {
+                        let selected = i == *self.selected;
+                        let response = row(ui, i, selected);
+
+                        if selected && (up || down) {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            result = QuickPickResponse::Picked(i);
+                        }
+                    }
+                });
+            });
+
+            if ui.input(|i| i.pointer.any_click()) && !ui.ui_contains_pointer() {
+                result = QuickPickResponse::Closed;
+            }
+        });
+
+        result
+    }
+}
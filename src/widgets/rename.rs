@@ -0,0 +1,185 @@
+use std::path::PathBuf;
+
+use similar::{ChangeTag, TextDiff};
+
+use crate::{app::Fexc, document::FileEdit};
+
+pub enum RenameState {
+    Prompt {
+        path: PathBuf,
+        position: lsp_types::Position,
+        new_name: String,
+    },
+    Pending,
+    Preview(Vec<FileEdit>),
+}
+
+enum RenameAction {
+    Submit,
+    Apply,
+    Close,
+}
+
+pub struct RenameDialog<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> RenameDialog<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        RenameDialog { app }
+    }
+
+    pub fn show(self, ctx: &egui::Context) {
+        if self.app.rename.is_none() {
+            return;
+        }
+
+        let title = self.app.i18n("rename_symbol").to_owned();
+        let new_name_label = self.app.i18n("new_name").to_owned();
+        let rename_label = self.app.i18n("rename").to_owned();
+        let apply_label = self.app.i18n("apply").to_owned();
+        let cancel_label = self.app.i18n("cancel").to_owned();
+        let waiting_label = self.app.i18n("waiting_for_server").to_owned();
+        let nothing_label = self.app.i18n("nothing_to_change").to_owned();
+
+        let mut open = true;
+        let mut action = None;
+        let current_project = &self.app.current_project;
+        let Some(state) = &mut self.app.rename else { return };
+
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(480.0)
+            .open(&mut open)
+            .show(ctx, |ui|
+        {
+            match state {
+                RenameState::Prompt { new_name, .. } => {
+                    ui.label(format!("{new_name_label}:"));
+
+                    let response = ui.text_edit_singleline(new_name);
+                    response.request_focus();
+
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        action = Some(RenameAction::Submit);
+                    }
+
+                    ui.horizontal(|ui| {
+                        if ui.button(&rename_label).clicked() {
+                            action = Some(RenameAction::Submit);
+                        }
+                        if ui.button(&cancel_label).clicked() {
+                            action = Some(RenameAction::Close);
+                        }
+                    });
+                }
+                RenameState::Pending => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(&waiting_label);
+                    });
+                }
+                RenameState::Preview(edits) => {
+                    if edits.is_empty() {
+                        ui.label(&nothing_label);
+                    }
+
+                    egui::ScrollArea::vertical()
+                        .max_height(480.0)
+                        .show(ui, |ui|
+                    {
+                        for edit in edits.iter() {
+                            let name = current_project.as_ref()
+                                .and_then(|root| edit.path.strip_prefix(root).ok())
+                                .unwrap_or(&edit.path)
+                                .display()
+                                .to_string();
+
+                            egui::CollapsingHeader::new(name)
+                                .default_open(true)
+                                .show(ui, |ui| diff_show(ui, &edit.old_text, &edit.new_text));
+                        }
+                    });
+
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        if ui.button(&apply_label).clicked() {
+                            action = Some(RenameAction::Apply);
+                        }
+                        if ui.button(&cancel_label).clicked() {
+                            action = Some(RenameAction::Close);
+                        }
+                    });
+                }
+            }
+        });
+
+        if !open {
+            action = Some(RenameAction::Close);
+        }
+
+        match action {
+            Some(RenameAction::Submit) => {
+                if let Some(RenameState::Prompt { path, position, new_name }) = self.app.rename.take() {
+                    let app = &mut *self.app;
+                    match app.languages.server_for(Some(&path), &mut app.lsp, &mut app.language_servers) {
+                        Some(lsp) => match lsp.rename(&path, position, &new_name) {
+                            Ok(()) => self.app.rename = Some(RenameState::Pending),
+                            Err(e) => self.app.notifications.error(e.to_string()),
+                        },
+                        None => {
+                            let message = self.app.i18n("lsp_not_running").to_owned();
+                            self.app.notifications.error(message);
+                        }
+                    }
+                }
+            }
+            Some(RenameAction::Apply) => {
+                if let Some(RenameState::Preview(edits)) = self.app.rename.take() {
+                    let count = edits.len();
+
+                    match self.app.documents.apply(edits) {
+                        Ok(()) => {
+                            let message = format!("{} {count}", self.app.i18n("files_changed"));
+                            self.app.notifications.info(message);
+                        }
+                        Err(e) => self.app.notifications.error(format!("Failed to apply rename: {e}")),
+                    }
+                }
+            }
+            Some(RenameAction::Close) => {
+                self.app.rename = None;
+            }
+            None => {}
+        }
+    }
+}
+
+/// Shows a unified diff of two texts with a few lines of context around each change.
+pub fn diff_show(ui: &mut egui::Ui, old: &str, new: &str) {
+    let diff = TextDiff::from_lines(old, new);
+
+    for (i, group) in diff.grouped_ops(2).iter().enumerate() {
+        if i > 0 {
+            ui.separator();
+        }
+
+        for op in group {
+            for change in diff.iter_changes(op) {
+                let (sign, color) = match change.tag() {
+                    ChangeTag::Delete => ("-", ui.visuals().error_fg_color),
+                    ChangeTag::Insert => ("+", egui::Color32::from_rgb(166, 218, 149)),
+                    ChangeTag::Equal => (" ", ui.visuals().weak_text_color()),
+                };
+
+                ui.label(
+                    egui::RichText::new(format!("{sign} {}", change.value().trim_end_matches('\n')))
+                        .family(egui::FontFamily::Monospace)
+                        .color(color)
+                );
+            }
+        }
+    }
+}
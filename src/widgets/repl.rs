@@ -0,0 +1,157 @@
+use egui_extras::syntax_highlighting::{highlight, CodeTheme};
+use egui_phosphor::bold as ph;
+
+use crate::{
+    app::Fexc,
+    repl::ReplLineKind,
+};
+
+pub struct ReplView<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> ReplView<'app> {
+    pub const INPUT_ID: &'static str = "repl_input";
+
+    pub fn new(app: &'app mut Fexc) -> Self {
+        ReplView { app }
+    }
+}
+
+impl egui::Widget for ReplView<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let start_label = format!("{} {}", ph::PLAY, self.app.i18n("start_repl"));
+        let stop_label = self.app.i18n("stop_repl").to_owned();
+        let load_label = self.app.i18n("repl_load").to_owned();
+        let reload_label = self.app.i18n("repl_reload").to_owned();
+        let clear_label = self.app.i18n("clear").to_owned();
+        let not_running_label = self.app.i18n("repl_not_running").to_owned();
+
+        let ctx = ui.ctx().clone();
+        let theme = CodeTheme::from_memory(ui.ctx(), ui.style());
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let row_height = ui.fonts(|f| f.row_height(&font_id));
+        let input_id = egui::Id::new(Self::INPUT_ID);
+
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                if self.app.repl.is_running() {
+                    ui.label(egui::RichText::new(self.app.repl.command.join(" ")).weak());
+
+                    if self.app.repl.is_busy() {
+                        ui.spinner();
+                    }
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button(ph::STOP).on_hover_text(&stop_label).clicked() {
+                            self.app.repl.stop();
+                        }
+                        if ui.button(ph::BROOM).on_hover_text(&clear_label).clicked() {
+                            self.app.repl.transcript.clear();
+                        }
+                        if ui.button(ph::ARROW_CLOCKWISE).on_hover_text(&reload_label).clicked() {
+                            self.app.repl_reload(&ctx);
+                        }
+                        if ui.button(ph::FILE_ARROW_UP).on_hover_text(&load_label).clicked() {
+                            self.app.repl_load_active(&ctx);
+                        }
+                    });
+                } else {
+                    ui.label(egui::RichText::new(&not_running_label).weak());
+
+                    if ui.button(&start_label).clicked()
+                        && let Err(e) = self.app.repl.start(self.app.project.as_ref(), &ctx)
+                    {
+                        self.app.notifications.error(e.to_string());
+                    }
+                }
+            });
+
+            let repl = &mut self.app.repl;
+            let input_rows = repl.input.lines().count().clamp(1, 8);
+            let input_height = input_rows as f32 * row_height + 2.0 * ui.spacing().item_spacing.y + 8.0;
+
+            egui::ScrollArea::vertical()
+                .auto_shrink(false)
+                .stick_to_bottom(true)
+                .max_height((ui.available_height() - input_height).max(row_height))
+                .show_rows(ui, row_height, repl.transcript.len(), |ui, range| {
+                    for line in &repl.transcript[range] {
+                        match line.kind {
+                            ReplLineKind::Input => {
+                                ui.horizontal(|ui| {
+                                    ui.label(egui::RichText::new("λ>").monospace().color(ui.visuals().hyperlink_color));
+                                    ui.label(highlight(ui.ctx(), ui.style(), &theme, &line.text, "hs"));
+                                });
+                            }
+                            ReplLineKind::Output => {
+                                ui.label(highlight(ui.ctx(), ui.style(), &theme, &line.text, "hs"));
+                            }
+                            ReplLineKind::Error => {
+                                let color = if line.text.contains("warning") {
+                                    ui.visuals().warn_fg_color
+                                } else {
+                                    ui.visuals().error_fg_color
+                                };
+                                ui.label(egui::RichText::new(&line.text).monospace().color(color));
+                            }
+                            ReplLineKind::Info => {
+                                ui.label(egui::RichText::new(&line.text).monospace().weak());
+                            }
+                        }
+                    }
+                });
+
+            // Enter evaluates, Shift+Enter starts a new line; arrows browse the history in single-line input
+            let focused = ui.memory(|m| m.has_focus(input_id));
+            let mut submit = false;
+
+            if focused {
+                submit = ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter));
+
+                if !repl.input.contains('\n') && !repl.history.is_empty() {
+                    let last = repl.history.len() - 1;
+
+                    if ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp)) {
+                        let index = repl.history_index.map_or(last, |i| i.saturating_sub(1));
+                        repl.history_index = Some(index);
+                        repl.input = repl.history[index].clone();
+                    }
+
+                    if let Some(index) = repl.history_index
+                        && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown))
+                    {
+                        if index < last {
+                            repl.history_index = Some(index + 1);
+                            repl.input = repl.history[index + 1].clone();
+                        } else {
+                            repl.history_index = None;
+                            repl.input.clear();
+                        }
+                    }
+                }
+            }
+
+            ui.add(
+                egui::TextEdit::multiline(&mut repl.input)
+                    .id(input_id)
+                    .code_editor()
+                    .desired_rows(input_rows)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("λ>")
+            );
+
+            if submit && !repl.input.trim().is_empty() {
+                let input = std::mem::take(&mut repl.input);
+
+                if !repl.is_running()
+                    && let Err(e) = repl.start(self.app.project.as_ref(), &ctx)
+                {
+                    self.app.notifications.error(e.to_string());
+                } else if let Err(e) = repl.send(&input) {
+                    self.app.notifications.error(e.to_string());
+                }
+            }
+        }).response
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{
+    app::Fexc,
+    haskell::DeclarationKind,
+    widgets::{pages::outline::kind_icon, quick_pick::{QuickPick, QuickPickResponse}},
+};
+
+#[derive(Default)]
+pub struct SymbolSearchState {
+    pub query: String,
+    pub selected: usize,
+    results: Vec<usize>,
+    searched: Option<String>,
+}
+
+pub struct SymbolSearchDialog<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> SymbolSearchDialog<'app> {
+    pub const MAX_RESULTS: usize = 500;
+
+    pub fn new(app: &'app mut Fexc) -> Self {
+        SymbolSearchDialog { app }
+    }
+
+    pub fn show(self, ctx: &egui::Context) {
+        let hint = if self.app.symbol_index.is_building() {
+            self.app.i18n("indexing").to_owned()
+        } else {
+            self.app.i18n("search_symbols").to_owned()
+        };
+
+        let index = &self.app.symbol_index;
+        let kind_labels = DeclarationKind::ALL.map(|kind| (kind, self.app.i18n(kind.i18n_key()).to_owned()));
+        let kind_label = |kind| kind_labels.iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, label)| label.as_str())
+            .unwrap_or_default();
+        let Some(state) = &mut self.app.symbol_search else { return };
+
+        // Only rerun the search when the query changed or the index got rebuilt
+        if state.searched.as_deref() != Some(state.query.as_str()) || index.is_building() {
+            state.results = index.search(&state.query, Self::MAX_RESULTS);
+            state.searched = Some(state.query.clone());
+        }
+
+        let results = &state.results;
+        let response = QuickPick::new("symbol_search", &mut state.query, &mut state.selected, &hint, results.len())
+            .show(ctx, |ui, i, selected| {
+                let Some(symbol) = index.get(results[i]) else { return ui.label("") };
+
+                let text = egui::RichText::new(format!("{} {}", kind_icon(symbol.decl.kind), symbol.decl.name))
+                    .family(egui::FontFamily::Monospace);
+
+                ui.horizontal(|ui| {
+                    let response = ui.selectable_label(selected, text);
+                    ui.label(
+                        egui::RichText::new(format!("{} · {}", symbol.module, kind_label(symbol.decl.kind)))
+                            .weak()
+                    );
+                    response
+                }).inner
+            });
+
+        match response {
+            QuickPickResponse::Picked(i) => {
+                let symbol = self.app.symbol_search.as_ref()
+                    .and_then(|s| s.results.get(i))
+                    .and_then(|&i| self.app.symbol_index.get(i))
+                    .cloned();
+
+                self.app.symbol_search = None;
+
+                if let Some(symbol) = symbol {
+                    match self.app.documents.open(&symbol.path) {
+                        Ok(doc) => doc.jump_to(symbol.decl.line, 0),
+                        Err(e) => self.app.notifications.error(e.to_string()),
+                    }
+                }
+            }
+            QuickPickResponse::Closed => self.app.symbol_search = None,
+            QuickPickResponse::None => {}
+        }
+    }
+}
@@ -0,0 +1,552 @@
+use std::path::{Path, PathBuf};
+
+use egui::text::{LayoutJob, TextFormat};
+use egui_phosphor::bold as ph;
+
+use crate::{
+    app::Fexc,
+    links::{self, Link},
+    session::TerminalSession,
+    terminal::{Cell, Color, Terminal},
+};
+
+/// Colors a terminal tab can be marked with.
+const TAB_COLORS: [egui::Color32; 6] = [
+    egui::Color32::from_rgb(138, 173, 244),
+    egui::Color32::from_rgb(166, 218, 149),
+    egui::Color32::from_rgb(245, 169, 127),
+    egui::Color32::from_rgb(198, 160, 246),
+    egui::Color32::from_rgb(237, 135, 150),
+    egui::Color32::from_rgb(139, 213, 202),
+];
+
+pub struct TerminalTab {
+    id: u64,
+    pub name: String,
+    pub color: usize,
+    /// Directory the shell is (re)started in.
+    cwd: Option<PathBuf>,
+    session: Option<Terminal>,
+    error: Option<String>,
+    /// Number of lines scrolled back into the history.
+    scroll: f32,
+    /// Anchor and end of the mouse selection as (line, column) into scrollback plus screen.
+    selection: Option<((usize, usize), (usize, usize))>,
+}
+
+impl TerminalTab {
+    fn focus_id(&self) -> egui::Id {
+        egui::Id::new(("terminal", self.id))
+    }
+
+    /// Sends input to the shell, returning whether it is running.
+    pub fn write(&mut self, bytes: &[u8]) -> bool {
+        match &mut self.session {
+            Some(session) if !session.has_exited() => {
+                session.write(bytes);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The shell's working directory, falling back to the one it was started in.
+    pub fn current_dir(&self) -> Option<PathBuf> {
+        self.session.as_ref()
+            .and_then(Terminal::current_dir)
+            .or_else(|| self.cwd.clone())
+    }
+
+    pub fn to_session(&self) -> TerminalSession {
+        TerminalSession {
+            name: self.name.clone(),
+            color: self.color,
+            cwd: self.current_dir(),
+        }
+    }
+
+    /// Shows the terminal and returns the file location of a link that was Ctrl+clicked.
+    fn ui(&mut self, ui: &mut egui::Ui, labels: &Labels, project: Option<&Path>) -> Option<(PathBuf, usize, usize)> {
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let (glyph_width, row_height) = ui.fonts(|f| (f.glyph_width(&font_id, 'M'), f.row_height(&font_id)));
+
+        let available = ui.available_size();
+        let cols = ((available.x / glyph_width).floor() as u16).max(2);
+        let rows = ((available.y / row_height).floor() as u16).max(1);
+
+        if self.session.is_none() && self.error.is_none() {
+            match Terminal::spawn(self.cwd.as_deref(), cols, rows, ui.ctx().clone()) {
+                Ok(session) => self.session = Some(session),
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        }
+
+        let exited = self.session.as_ref().is_some_and(Terminal::has_exited);
+
+        if exited || self.error.is_some() {
+            let message = self.error.clone().unwrap_or_else(|| labels.exited.clone());
+
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(message).weak());
+                if ui.button(&labels.restart).clicked() {
+                    self.cwd = self.current_dir();
+                    self.session = None;
+                    self.error = None;
+                }
+            });
+
+            return None;
+        }
+
+        let cwd = self.current_dir();
+        let id = self.focus_id();
+        let Some(session) = &mut self.session else { return None };
+
+        session.resize(cols, rows);
+        session.flush_responses();
+
+        let (rect, _) = ui.allocate_exact_size(available, egui::Sense::hover());
+        let response = ui.interact(rect, id, egui::Sense::click_and_drag());
+
+        if response.clicked() || response.drag_started() {
+            response.request_focus();
+        }
+
+        if response.has_focus() {
+            ui.memory_mut(|m| m.set_focus_lock_filter(response.id, egui::EventFilter {
+                tab: true,
+                horizontal_arrows: true,
+                vertical_arrows: true,
+                escape: true,
+            }));
+        }
+
+        let default_fg = ui.visuals().text_color();
+        let default_bg = ui.visuals().extreme_bg_color;
+        let selection_bg = ui.visuals().selection.bg_fill;
+
+        let mut input = vec![];
+        let mut copied = None;
+        let mut opened = None;
+
+        {
+            let screen = session.screen();
+            let grid = &screen.grid;
+            let scrollback = grid.scrollback_len();
+
+            if response.hovered() {
+                let delta = ui.input(|i| i.smooth_scroll_delta.y);
+                self.scroll += delta / row_height;
+            }
+
+            // Clearing the scrollback or resetting the terminal shrinks it under the view
+            self.scroll = self.scroll.clamp(0.0, scrollback as f32);
+            let top = scrollback.saturating_sub(self.scroll.round() as usize);
+
+            let cell_at = |pos: egui::Pos2| {
+                let row = ((pos.y - rect.top()) / row_height).floor().max(0.0) as usize;
+                let col = ((pos.x - rect.left()) / glyph_width).round().max(0.0) as usize;
+                (top + row.min(rows as usize - 1), col.min(cols as usize))
+            };
+
+            // File locations printed by compilers, test runners or `grep -n`
+            let hovered_link = response.hover_pos().and_then(|pos| {
+                let row = ((pos.y - rect.top()) / row_height).floor() as usize;
+                let col = ((pos.x - rect.left()) / glyph_width).floor() as usize;
+                let index = top + row;
+                let text = grid.line(index)?.iter().map(|c| c.ch).collect::<String>();
+
+                links::find(&text).into_iter()
+                    .find(|link| link.range.contains(&col))
+                    .map(|link| (index, link))
+            });
+
+            let command_held = ui.input(|i| i.modifiers.command);
+
+            if let Some((_, link)) = &hovered_link {
+                show_link_hint(ui, response.id, &labels.open_link, command_held);
+
+                if command_held && response.clicked() {
+                    let bases = [project, cwd.as_deref()].into_iter().flatten().collect::<Vec<_>>();
+                    opened = Some((links::resolve(&link.path, &bases), link.line, link.column));
+                }
+            }
+
+            if let Some(pos) = response.interact_pointer_pos() {
+                if response.drag_started() {
+                    self.selection = Some((cell_at(pos), cell_at(pos)));
+                } else if response.dragged()
+                    && let Some((_, end)) = &mut self.selection
+                {
+                    *end = cell_at(pos);
+                } else if response.clicked() {
+                    self.selection = None;
+                }
+            }
+
+            let selection = self.selection.filter(|(start, end)| start != end).map(|(start, end)| {
+                if start <= end { (start, end) } else { (end, start) }
+            });
+
+            if response.has_focus() {
+                for event in ui.input(|i| i.events.clone()) {
+                    match event {
+                        egui::Event::Copy => match selection {
+                            Some((start, end)) => copied = Some(grid.text_range(start, end)),
+                            None => input.extend_from_slice(b"\x03"),
+                        },
+                        egui::Event::Cut => input.extend_from_slice(b"\x18"),
+                        egui::Event::Paste(text) => {
+                            if grid.bracketed_paste {
+                                input.extend_from_slice(b"\x1b[200~");
+                                input.extend(text.bytes());
+                                input.extend_from_slice(b"\x1b[201~");
+                            } else {
+                                input.extend(text.replace("\r\n", "\r").replace('\n', "\r").bytes());
+                            }
+                        }
+                        egui::Event::Text(text) => input.extend(text.bytes()),
+                        egui::Event::Key { key, pressed: true, modifiers, .. } => {
+                            if let Some(bytes) = key_bytes(key, modifiers, grid.app_cursor_keys) {
+                                input.extend(bytes);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            let painter = ui.painter_at(rect);
+            painter.rect_filled(rect, 0.0, default_bg);
+
+            for row in 0..rows as usize {
+                let index = top + row;
+                let Some(cells) = grid.line(index) else { break };
+                let y = rect.top() + row as f32 * row_height;
+
+                let mut job = LayoutJob::default();
+
+                for (col, cell) in cells.iter().enumerate() {
+                    let (fg, bg) = cell_colors(cell, default_fg, default_bg);
+                    let selected = selection.is_some_and(|(start, end)| start <= (index, col) && (index, col) < end);
+
+                    let bg = if selected { selection_bg } else { bg };
+                    let linked = hovered_link.as_ref().is_some_and(|(line, link): &(usize, Link)| *line == index && link.range.contains(&col));
+                    if bg != default_bg {
+                        let x = rect.left() + col as f32 * glyph_width;
+                        painter.rect_filled(
+                            egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(glyph_width, row_height)),
+                            0.0,
+                            bg,
+                        );
+                    }
+
+                    job.append(&cell.ch.to_string(), 0.0, TextFormat {
+                        font_id: font_id.clone(),
+                        color: fg,
+                        italics: cell.style.italic,
+                        underline: if cell.style.underline || linked { egui::Stroke::new(1.0, fg) } else { egui::Stroke::NONE },
+                        ..Default::default()
+                    });
+                }
+
+                let galley = ui.fonts(|f| f.layout_job(job));
+                painter.galley(egui::pos2(rect.left(), y), galley, default_fg);
+            }
+
+            let (cursor_row, cursor_col) = grid.cursor();
+            let cursor_line = scrollback + cursor_row;
+
+            if grid.cursor_visible && cursor_line >= top && cursor_line < top + rows as usize {
+                let cursor = egui::Rect::from_min_size(
+                    egui::pos2(
+                        rect.left() + cursor_col as f32 * glyph_width,
+                        rect.top() + (cursor_line - top) as f32 * row_height,
+                    ),
+                    egui::vec2(glyph_width, row_height),
+                );
+
+                if response.has_focus() {
+                    painter.rect_filled(cursor, 0.0, default_fg.gamma_multiply(0.6));
+                } else {
+                    painter.rect_stroke(cursor, 0.0, egui::Stroke::new(1.0, default_fg), egui::StrokeKind::Inside);
+                }
+            }
+        }
+
+        if let Some(text) = copied {
+            ui.ctx().copy_text(text);
+        }
+
+        if !input.is_empty() {
+            self.scroll = 0.0;
+            self.selection = None;
+            session.write(&input);
+        }
+
+        opened
+    }
+}
+
+/// The terminal sessions of the bottom panel, shown as tabs or side by side.
+#[derive(Default)]
+pub struct TerminalTabs {
+    pub tabs: Vec<TerminalTab>,
+    pub active: usize,
+    pub split: bool,
+    next_id: u64,
+}
+
+impl TerminalTabs {
+    pub fn restore(sessions: &[TerminalSession], split: bool) -> Self {
+        let mut tabs = TerminalTabs { split, ..Default::default() };
+
+        for session in sessions {
+            tabs.add(session.name.clone(), session.color, session.cwd.clone());
+        }
+
+        tabs.active = 0;
+        tabs
+    }
+
+    pub fn add(&mut self, name: String, color: usize, cwd: Option<PathBuf>) {
+        self.tabs.push(TerminalTab {
+            id: self.next_id,
+            name,
+            color: color % TAB_COLORS.len(),
+            cwd,
+            session: None,
+            error: None,
+            scroll: 0.0,
+            selection: None,
+        });
+
+        self.next_id += 1;
+        self.active = self.tabs.len() - 1;
+    }
+
+    /// Whether one of the terminals has keyboard focus, so keys go to its shell.
+    pub fn has_focus(&self, ctx: &egui::Context) -> bool {
+        ctx.memory(|m| self.tabs.iter().any(|tab| m.has_focus(tab.focus_id())))
+    }
+
+    /// Closes a session, killing its shell.
+    pub fn kill(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.tabs.remove(index);
+            self.active = self.active.min(self.tabs.len().saturating_sub(1));
+        }
+    }
+}
+
+struct Labels {
+    exited: String,
+    restart: String,
+    open_link: String,
+}
+
+/// Shows how to open the hovered link, and a pointing hand while the modifier is held.
+pub fn show_link_hint(ui: &egui::Ui, id: egui::Id, hint: &str, command_held: bool) {
+    if command_held {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+    } else {
+        let modifier = ui.ctx().format_modifiers(egui::Modifiers::COMMAND);
+        egui::Tooltip::always_open(ui.ctx().clone(), ui.layer_id(), id.with("link"), egui::PopupAnchor::Pointer)
+            .gap(12.0)
+            .show(|ui| ui.label(format!("{modifier} + {hint}")));
+    }
+}
+
+pub struct TerminalView<'app> {
+    app: &'app mut Fexc,
+}
+
+impl<'app> TerminalView<'app> {
+    pub fn new(app: &'app mut Fexc) -> Self {
+        TerminalView { app }
+    }
+}
+
+impl egui::Widget for TerminalView<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let labels = Labels {
+            exited: self.app.i18n("terminal_exited").to_owned(),
+            restart: format!("{} {}", ph::ARROW_CLOCKWISE, self.app.i18n("restart_terminal")),
+            open_link: self.app.i18n("open_link_hint").to_owned(),
+        };
+        let new_label = self.app.i18n("new_terminal").to_owned();
+        let kill_label = self.app.i18n("kill_terminal").to_owned();
+        let split_label = self.app.i18n("split_terminals").to_owned();
+        let name_label = self.app.i18n("name").to_owned();
+        let default_name = self.app.i18n("terminal").to_owned();
+
+        let project = self.app.current_project.clone();
+        let terminals = &mut self.app.terminals;
+        let mut killed = None;
+        let mut opened = None;
+
+        let response = ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                for (i, tab) in terminals.tabs.iter_mut().enumerate() {
+                    let text = egui::RichText::new(format!("{} {}", ph::TERMINAL_WINDOW, tab.name))
+                        .color(TAB_COLORS[tab.color % TAB_COLORS.len()]);
+
+                    let response = ui.selectable_label(i == terminals.active, text);
+                    if response.clicked() {
+                        terminals.active = i;
+                    }
+
+                    response.context_menu(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(&name_label);
+                            ui.text_edit_singleline(&mut tab.name);
+                        });
+
+                        ui.horizontal(|ui| {
+                            for (c, color) in TAB_COLORS.iter().enumerate() {
+                                let marker = if c == tab.color { ph::CHECK_CIRCLE } else { ph::CIRCLE };
+                                if ui.button(egui::RichText::new(marker).color(*color)).clicked() {
+                                    tab.color = c;
+                                }
+                            }
+                        });
+
+                        ui.separator();
+
+                        if ui.button(format!("{} {kill_label}", ph::TRASH)).clicked() {
+                            killed = Some(i);
+                            ui.close();
+                        }
+                    });
+                }
+
+                if ui.button(ph::PLUS).on_hover_text(&new_label).clicked() {
+                    let name = format!("{default_name} {}", terminals.next_id + 1);
+                    let color = terminals.next_id as usize;
+                    terminals.add(name, color, project.clone());
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.add_enabled(!terminals.tabs.is_empty(), egui::Button::new(ph::TRASH))
+                        .on_hover_text(&kill_label)
+                        .clicked()
+                    {
+                        killed = Some(terminals.active);
+                    }
+
+                    ui.toggle_value(&mut terminals.split, ph::COLUMNS).on_hover_text(&split_label);
+                });
+            });
+
+            if let Some(i) = killed {
+                terminals.kill(i);
+            }
+
+            if terminals.split && terminals.tabs.len() > 1 {
+                ui.columns(terminals.tabs.len(), |columns| {
+                    for (column, tab) in columns.iter_mut().zip(terminals.tabs.iter_mut()) {
+                        opened = opened.take().or(tab.ui(column, &labels, project.as_deref()));
+                    }
+                });
+            } else if let Some(tab) = terminals.tabs.get_mut(terminals.active) {
+                opened = tab.ui(ui, &labels, project.as_deref());
+            }
+        }).response;
+
+        if let Some((path, line, column)) = opened {
+            match self.app.documents.open(&path) {
+                Ok(doc) => doc.jump_to(line, column),
+                Err(e) => self.app.notifications.error(e.to_string()),
+            }
+        }
+
+        response
+    }
+}
+
+/// Translates keys that do not produce text events into the bytes an xterm sends for them.
+fn key_bytes(key: egui::Key, modifiers: egui::Modifiers, app_cursor_keys: bool) -> Option<Vec<u8>> {
+    use egui::Key;
+
+    let cursor = |c: char| {
+        if app_cursor_keys {
+            format!("\x1bO{c}").into_bytes()
+        } else {
+            format!("\x1b[{c}").into_bytes()
+        }
+    };
+
+    if modifiers.ctrl && !modifiers.alt {
+        let name = key.name();
+        if name.len() == 1 && name.as_bytes()[0].is_ascii_uppercase() {
+            return Some(vec![name.as_bytes()[0] & 0x1f]);
+        }
+    }
+
+    let bytes = match key {
+        Key::Enter => b"\r".to_vec(),
+        Key::Backspace => b"\x7f".to_vec(),
+        Key::Tab if modifiers.shift => b"\x1b[Z".to_vec(),
+        Key::Tab => b"\t".to_vec(),
+        Key::Escape => b"\x1b".to_vec(),
+        Key::ArrowUp => cursor('A'),
+        Key::ArrowDown => cursor('B'),
+        Key::ArrowRight => cursor('C'),
+        Key::ArrowLeft => cursor('D'),
+        Key::Home => cursor('H'),
+        Key::End => cursor('F'),
+        Key::Insert => b"\x1b[2~".to_vec(),
+        Key::Delete => b"\x1b[3~".to_vec(),
+        Key::PageUp => b"\x1b[5~".to_vec(),
+        Key::PageDown => b"\x1b[6~".to_vec(),
+        _ => return None,
+    };
+
+    Some(bytes)
+}
+
+fn cell_colors(cell: &Cell, default_fg: egui::Color32, default_bg: egui::Color32) -> (egui::Color32, egui::Color32) {
+    let fg = match cell.style.fg {
+        // Bold text uses the bright variant of the basic colors, like most terminals do
+        Color::Indexed(i) if cell.style.bold && i < 8 => indexed_color(i + 8),
+        Color::Default => default_fg,
+        color => to_color32(color, default_fg),
+    };
+    let bg = to_color32(cell.style.bg, default_bg);
+
+    if cell.style.inverse { (bg, fg) } else { (fg, bg) }
+}
+
+fn to_color32(color: Color, default: egui::Color32) -> egui::Color32 {
+    match color {
+        Color::Default => default,
+        Color::Indexed(i) => indexed_color(i),
+        Color::Rgb(r, g, b) => egui::Color32::from_rgb(r, g, b),
+    }
+}
+
+/// The xterm 256 color palette.
+fn indexed_color(index: u8) -> egui::Color32 {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+        (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+        (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+        (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+    ];
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match index {
+        0..=15 => {
+            let (r, g, b) = BASIC[index as usize];
+            egui::Color32::from_rgb(r, g, b)
+        }
+        16..=231 => {
+            let i = index - 16;
+            egui::Color32::from_rgb(LEVELS[(i / 36) as usize], LEVELS[(i / 6 % 6) as usize], LEVELS[(i % 6) as usize])
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            egui::Color32::from_rgb(level, level, level)
+        }
+    }
+}
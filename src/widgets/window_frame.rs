@@ -66,10 +66,10 @@ impl WindowFrame {
                         ctx.output_mut(|o| o.cursor_icon = icon);
                     }
 
-                    if let Some(dir) = resize_dir {
-                        if ui.input(|i| i.pointer.primary_down()) {
-                            ctx.send_viewport_cmd(egui::ViewportCommand::BeginResize(dir));
-                        }
+                    if let Some(dir) = resize_dir
+                        && ui.input(|i| i.pointer.primary_down())
+                    {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::BeginResize(dir));
                     }
                 }
             }